@@ -0,0 +1,232 @@
+use cosmwasm_std::{StdError, Timestamp, Uint128, Uint256, Uint512};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ContractError;
+use crate::extension::Extension;
+use crate::state::Order;
+
+/// Rate bumps are expressed in units of 1e-7, so this bump is +100%
+pub const RATE_BUMP_BASE: u32 = 10_000_000;
+
+// Length of the amount getter address preceding the auction details
+const GETTER_LEN: usize = 20;
+// Gas bump estimate, gas price estimate, start time, duration and initial rate bump
+const HEADER_LEN: usize = 3 + 4 + 4 + 3 + 3;
+// Rate bump and time delta of one point
+const POINT_LEN: usize = 3 + 2;
+
+/// Dutch auction a Fusion order is priced by, as packed by the settlement extension into
+/// the order's taking amount data.
+///
+/// The rate bump starts at `initial_rate_bump` and decays linearly through `points` to
+/// zero at the end of the auction. The gas estimates let EVM resolvers offset the bump by
+/// the block base fee; there is no base fee here, so they are decoded but not applied.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AuctionDetails {
+    pub gas_bump_estimate: u32,
+    pub gas_price_estimate: u32,
+    pub start_time: u64,
+    pub duration: u64,
+    pub initial_rate_bump: u32,
+    pub points: Vec<AuctionPoint>,
+}
+
+/// Rate bump reached `time_delta` seconds after the previous point, or after the start
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AuctionPoint {
+    pub rate_bump: u32,
+    pub time_delta: u64,
+}
+
+impl AuctionDetails {
+    /// Auction details of an order's extension, or `None` if the order is not auctioned
+    pub fn from_extension(extension: &[u8]) -> Result<Option<Self>, ContractError> {
+        let data = Extension::decode(extension)?.taking_amount_data;
+        if data.is_empty() {
+            return Ok(None);
+        }
+        if data.len() < GETTER_LEN + HEADER_LEN
+            || !(data.len() - GETTER_LEN - HEADER_LEN).is_multiple_of(POINT_LEN)
+        {
+            return Err(ContractError::InvalidExtension {});
+        }
+
+        let details = &data[GETTER_LEN..];
+        let points = details[HEADER_LEN..]
+            .chunks(POINT_LEN)
+            .map(|point| AuctionPoint {
+                rate_bump: be_uint(&point[..3]) as u32,
+                time_delta: be_uint(&point[3..]),
+            })
+            .collect();
+
+        Ok(Some(AuctionDetails {
+            gas_bump_estimate: be_uint(&details[0..3]) as u32,
+            gas_price_estimate: be_uint(&details[3..7]) as u32,
+            start_time: be_uint(&details[7..11]),
+            duration: be_uint(&details[11..14]),
+            initial_rate_bump: be_uint(&details[14..17]) as u32,
+            points,
+        }))
+    }
+
+    /// Rate bump at `now`, interpolated between the points around it
+    pub fn rate_bump(&self, now: Timestamp) -> u32 {
+        let now = now.seconds();
+        let finish_time = self.start_time + self.duration;
+        if now <= self.start_time {
+            return self.initial_rate_bump;
+        }
+        if now >= finish_time {
+            return 0;
+        }
+
+        let mut point_time = self.start_time;
+        let mut rate_bump = self.initial_rate_bump as u64;
+        for point in &self.points {
+            let next_time = point_time + point.time_delta;
+            let next_rate_bump = point.rate_bump as u64;
+            if now <= next_time {
+                return (((now - point_time) * next_rate_bump + (next_time - now) * rate_bump)
+                    / (next_time - point_time)) as u32;
+            }
+            point_time = next_time;
+            rate_bump = next_rate_bump;
+        }
+        ((finish_time - now) * rate_bump / (finish_time - point_time)) as u32
+    }
+}
+
+/// Least a taker must pay for `making_amount` of the order: its pro rata share of the
+/// order's taking amount, raised by the auction's rate bump at `now`. Rounded up once over
+/// the whole product, as the settlement extension's
+/// `mulDiv(takingAmount * makingAmount, BASE + rateBump, orderMakingAmount * BASE, Ceil)`.
+pub fn min_taking_amount(
+    order: &Order,
+    making_amount: Uint128,
+    auction: Option<&AuctionDetails>,
+    now: Timestamp,
+) -> Result<Uint128, ContractError> {
+    let rate_bump = auction.map_or(0, |auction| auction.rate_bump(now));
+    let amount = mul_div_ceil(
+        order.taking_amount.full_mul(making_amount),
+        Uint256::from(RATE_BUMP_BASE as u128 + rate_bump as u128),
+        Uint256::from(order.making_amount) * Uint256::from(RATE_BUMP_BASE),
+    )?;
+    Ok(Uint128::try_from(amount).map_err(StdError::from)?)
+}
+
+fn mul_div_ceil(
+    value: Uint256,
+    numerator: Uint256,
+    denominator: Uint256,
+) -> Result<Uint256, ContractError> {
+    let product = value.full_mul(numerator);
+    let denominator = Uint512::from(denominator);
+    let mut quotient = product.checked_div(denominator).map_err(StdError::from)?;
+    if !product
+        .checked_rem(denominator)
+        .map_err(StdError::from)?
+        .is_zero()
+    {
+        quotient += Uint512::one();
+    }
+    Ok(Uint256::try_from(quotient).map_err(StdError::from)?)
+}
+
+fn be_uint(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0u64, |value, byte| value << 8 | *byte as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auction() -> AuctionDetails {
+        // Starts at +50% and drops to +10% over the first 100s, then to zero at 300s
+        AuctionDetails {
+            gas_bump_estimate: 0,
+            gas_price_estimate: 0,
+            start_time: 1_000,
+            duration: 300,
+            initial_rate_bump: 5_000_000,
+            points: vec![AuctionPoint {
+                rate_bump: 1_000_000,
+                time_delta: 100,
+            }],
+        }
+    }
+
+    #[test]
+    fn rate_bump_decays_through_points() {
+        let auction = auction();
+        let at = Timestamp::from_seconds;
+
+        assert_eq!(auction.rate_bump(at(900)), 5_000_000);
+        assert_eq!(auction.rate_bump(at(1_050)), 3_000_000);
+        assert_eq!(auction.rate_bump(at(1_100)), 1_000_000);
+        assert_eq!(auction.rate_bump(at(1_200)), 500_000);
+        assert_eq!(auction.rate_bump(at(1_300)), 0);
+    }
+
+    #[test]
+    fn min_taking_amount_rounds_once() {
+        let order = Order {
+            salt: Uint128::zero(),
+            maker: cosmwasm_std::Addr::unchecked("maker"),
+            receiver: cosmwasm_std::Addr::unchecked("maker"),
+            maker_asset: cosmwasm_std::Addr::unchecked("maker_asset"),
+            taker_asset: cosmwasm_std::Addr::unchecked("taker_asset"),
+            making_amount: Uint128::new(3_000_000),
+            taking_amount: Uint128::new(1_000_000),
+        };
+        let auction = auction();
+        let at = Timestamp::from_seconds;
+        let min = |making_amount: u128, now| {
+            min_taking_amount(&order, Uint128::new(making_amount), Some(&auction), now)
+                .unwrap()
+                .u128()
+        };
+
+        // What `SimpleSettlement._getTakingAmount` computes for these fills. A third of the
+        // order at +50% is exactly 500_000, where rounding the pro rata share first gives 500_001.
+        assert_eq!(min(1_000_000, at(900)), 500_000);
+        assert_eq!(min(1_000_000, at(1_050)), 433_334);
+        assert_eq!(min(1, at(900)), 1);
+        assert_eq!(min(3_000_000, at(1_300)), 1_000_000);
+        let without_auction = min_taking_amount(&order, Uint128::new(1_000_000), None, at(900));
+        assert_eq!(without_auction.unwrap(), Uint128::new(333_334));
+    }
+
+    #[test]
+    fn decodes_taking_amount_data() {
+        let auction = auction();
+        let mut data = vec![0xee; GETTER_LEN];
+        data.extend_from_slice(&[0, 0, 0]);
+        data.extend_from_slice(&[0, 0, 0, 0]);
+        data.extend_from_slice(&1_000u32.to_be_bytes());
+        data.extend_from_slice(&300u32.to_be_bytes()[1..]);
+        data.extend_from_slice(&5_000_000u32.to_be_bytes()[1..]);
+        data.extend_from_slice(&1_000_000u32.to_be_bytes()[1..]);
+        data.extend_from_slice(&100u16.to_be_bytes());
+
+        // Taking amount data is the fourth field, the ones before it are empty
+        let mut extension = vec![0u8; 32];
+        for index in 3..8 {
+            extension[28 - 4 * index..32 - 4 * index]
+                .copy_from_slice(&(data.len() as u32).to_be_bytes());
+        }
+        extension.extend_from_slice(&data);
+
+        assert_eq!(
+            AuctionDetails::from_extension(&extension).unwrap(),
+            Some(auction)
+        );
+        assert_eq!(AuctionDetails::from_extension(&[]).unwrap(), None);
+    }
+}
@@ -1,29 +1,52 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdError, StdResult,
+    SubMsgResult,
 };
 
+use cw2::set_contract_version;
+use semver::Version;
+
 use crate::error::ContractError;
-use crate::execute;
+use crate::execute::{self, SWEEP_ESCROW_REPLY_ID};
+use crate::ibc::RELAY_SECRET_REPLY_ID;
+use crate::migrations;
 use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
-use crate::query;
+use crate::neutron::{NeutronMsg, NeutronQuery, SudoMsg, REGISTER_QUERY_REPLY_ID};
+use crate::query::{self, EscrowFilter};
+use crate::state::{Config, CONFIG};
+
+// Version info recorded with cw2 for migrations
+pub const CONTRACT_NAME: &str = concat!("crates.io:", env!("CARGO_PKG_NAME"));
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-#[entry_point]
+#[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    _deps: DepsMut,
+    deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
-    _msg: InstantiateMsg,
-) -> Result<Response, ContractError> {
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    execute::validate_rescue_delay(deps.storage, msg.rescue_delay)?;
+    let config = Config {
+        owner: info.sender,
+        safety_deposit_denom: msg.safety_deposit_denom,
+        rescue_delay: msg.rescue_delay,
+    };
+    CONFIG.save(deps.storage, &config)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     Ok(Response::new().add_attribute("method", "instantiate"))
 }
 
-#[entry_point]
+#[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
-) -> Result<Response, ContractError> {
+) -> Result<Response<NeutronMsg>, ContractError> {
     match msg {
         ExecuteMsg::CreateDstEscrow {
             escrow_address,  // Added escrow_address parameter
@@ -44,46 +67,338 @@ pub fn execute(
             deps, env, info, escrow_address, order, extension, order_hash, taker,
             making_amount, taking_amount, remaining_making_amount, extra_data,
         ),
+        ExecuteMsg::Withdraw {
+            escrow_address,
+            secret,
+        } => execute::withdraw(deps, env, info, escrow_address, secret),
+        ExecuteMsg::PublicWithdraw {
+            escrow_address,
+            secret,
+        } => execute::public_withdraw(deps, env, info, escrow_address, secret),
+        ExecuteMsg::Cancel { escrow_address } => execute::cancel(deps, env, info, escrow_address),
+        ExecuteMsg::PublicCancel { escrow_address } => {
+            execute::public_cancel(deps, env, info, escrow_address)
+        }
+        ExecuteMsg::Rescue { escrow_address } => execute::rescue(deps, env, info, escrow_address),
+        ExecuteMsg::BatchCreateDstEscrows { escrows, mode } => {
+            execute::batch_create_dst_escrows(deps, env, info, escrows, mode)
+        }
+        ExecuteMsg::BatchWithdraw { withdrawals, mode } => {
+            execute::batch_withdraw(deps, env, info, withdrawals, mode)
+        }
+        ExecuteMsg::BatchCancel {
+            escrow_addresses,
+            mode,
+        } => execute::batch_cancel(deps, env, info, escrow_addresses, mode),
+        ExecuteMsg::UpdateFeeConfig {
+            fee_bps,
+            integrator_share_bps,
+            fee_collector,
+        } => execute::update_fee_config(deps, info, fee_bps, integrator_share_bps, fee_collector),
+        ExecuteMsg::UpdateSweepConfig { batch_size } => {
+            execute::update_sweep_config(deps, info, batch_size)
+        }
+        ExecuteMsg::SweepEscrow { escrow_address } => {
+            execute::sweep_escrow(deps, env, info, escrow_address)
+        }
+        ExecuteMsg::RegisterChain {
+            chain_id,
+            address_format,
+            factory,
+            connection_id,
+        } => execute::register_chain(deps, info, chain_id, address_format, factory, connection_id),
+        ExecuteMsg::RemoveChain { chain_id } => execute::remove_chain(deps, info, chain_id),
+        ExecuteMsg::RegisterToken {
+            chain_id,
+            src_token,
+            dst_token,
+        } => execute::register_token(deps, info, chain_id, src_token, dst_token),
+        ExecuteMsg::RemoveToken {
+            chain_id,
+            src_token,
+        } => execute::remove_token(deps, info, chain_id, src_token),
+        ExecuteMsg::RequestDstEscrow {
+            src_escrow_address,
+            channel_id,
+            escrow_address,
+            immutables,
+        } => execute::request_dst_escrow(
+            deps,
+            env,
+            info,
+            src_escrow_address,
+            channel_id,
+            escrow_address,
+            immutables,
+        ),
+        ExecuteMsg::FundDstEscrow { escrow_address } => {
+            execute::fund_dst_escrow(deps, env, info, escrow_address)
+        }
+        ExecuteMsg::VerifyCounterpart {
+            escrow_address,
+            chain_id,
+            counterpart_address,
+        } => execute::verify_counterpart(deps, info, escrow_address, chain_id, counterpart_address),
+        ExecuteMsg::UpdateSecretRelay { channel_id } => {
+            execute::update_secret_relay(deps, info, channel_id)
+        }
+        ExecuteMsg::IbcHook {
+            channel,
+            original_sender,
+            recovery_address,
+            msg,
+        } => execute::ibc_hook(
+            deps,
+            env,
+            info,
+            channel,
+            original_sender,
+            recovery_address,
+            *msg,
+        ),
+        ExecuteMsg::ClaimFunds {} => execute::claim_funds(deps, info),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response<NeutronMsg>, ContractError> {
+    match (msg.id, msg.result) {
+        // Relaying a secret is best effort, the withdrawal stands without it
+        (RELAY_SECRET_REPLY_ID, SubMsgResult::Err(err)) => Ok(Response::new()
+            .add_attribute("action", "relay_secret_failed")
+            .add_attribute("error", err)),
+        // An escrow the sweep fails to cancel stays open until the sweep comes back to it
+        (SWEEP_ESCROW_REPLY_ID, SubMsgResult::Err(err)) => Ok(Response::new()
+            .add_attribute("action", "sweep_escrow_failed")
+            .add_attribute("error", err)),
+        (REGISTER_QUERY_REPLY_ID, SubMsgResult::Ok(response)) => {
+            execute::save_counterpart_query(deps, response.data)
+        }
+        (id, _) => Err(StdError::generic_err(format!("Unknown reply id {}", id)).into()),
+    }
+}
+
+/// Callbacks from Neutron's interchain queries and cron modules
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    msg: SudoMsg,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    match msg {
+        SudoMsg::KvQueryResult { query_id } => execute::counterpart_query_result(deps, query_id),
+        SudoMsg::SweepExpired {} => execute::sweep_expired(deps, env),
     }
 }
 
-#[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetDstEscrow { escrow_address } => {  // Changed from escrow_key
-            to_binary(&query::query_dst_escrow(deps, escrow_address)?)
+            to_json_binary(&query::query_dst_escrow(deps, escrow_address)?)
         }
         QueryMsg::GetSrcEscrow { escrow_address } => {
-            to_binary(&query::query_src_escrow(deps, escrow_address)?)
+            to_json_binary(&query::query_src_escrow(deps, escrow_address)?)
         }
-        QueryMsg::ListDstEscrows { start_after, limit } => {
-            to_binary(&query::query_all_dst_escrows(deps, start_after, limit)?)
+        QueryMsg::ListDstEscrows {
+            start_after,
+            end_before,
+            limit,
+            order_by,
+            status,
+            token,
+            maker,
+        } => to_json_binary(&query::query_all_dst_escrows(
+            deps,
+            start_after,
+            end_before,
+            limit,
+            order_by,
+            EscrowFilter {
+                status,
+                token,
+                maker,
+            },
+        )?),
+        QueryMsg::ListSrcEscrows {
+            start_after,
+            end_before,
+            limit,
+            order_by,
+            status,
+            token,
+            maker,
+        } => to_json_binary(&query::query_all_src_escrows(
+            deps,
+            start_after,
+            end_before,
+            limit,
+            order_by,
+            EscrowFilter {
+                status,
+                token,
+                maker,
+            },
+        )?),
+        QueryMsg::EscrowsByMaker {
+            side,
+            maker,
+            start_after,
+            limit,
+        } => to_json_binary(&query::query_escrows_by_maker(
+            deps,
+            side,
+            maker,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::EscrowsByTaker {
+            side,
+            taker,
+            start_after,
+            limit,
+        } => to_json_binary(&query::query_escrows_by_taker(
+            deps,
+            side,
+            taker,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::EscrowByOrderHash {
+            side,
+            order_hash,
+            start_after,
+            limit,
+        } => to_json_binary(&query::query_escrows_by_order_hash(
+            deps,
+            side,
+            order_hash,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::EscrowByHashlock { hashlock } => {
+            to_json_binary(&query::query_escrow_by_hashlock(deps, hashlock)?)
+        }
+        QueryMsg::GetEscrowStage { escrow_address } => {
+            to_json_binary(&query::query_escrow_stage(deps, env, escrow_address)?)
+        }
+        QueryMsg::GetRevealedSecret { hashlock } => {
+            to_json_binary(&query::query_revealed_secret(deps, hashlock)?)
+        }
+        QueryMsg::GetFeeConfig {} => to_json_binary(&query::query_fee_config(deps)?),
+        QueryMsg::GetSweepConfig {} => to_json_binary(&query::query_sweep_config(deps)?),
+        QueryMsg::QuoteAuctionRate { order, extension } => {
+            to_json_binary(&query::query_auction_rate(env, order, extension)?)
+        }
+        QueryMsg::DecodeExtension { extension } => {
+            to_json_binary(&query::query_decode_extension(extension)?)
         }
-        QueryMsg::ListSrcEscrows { start_after, limit } => {
-            to_binary(&query::query_all_src_escrows(deps, start_after, limit)?)
+        QueryMsg::ListSupportedChains { start_after, limit } => {
+            to_json_binary(&query::query_supported_chains(deps, start_after, limit)?)
         }
+        QueryMsg::ListSupportedTokens {
+            chain_id,
+            start_after,
+            limit,
+        } => to_json_binary(&query::query_supported_tokens(
+            deps,
+            chain_id,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetSecretRelay {} => to_json_binary(&query::query_secret_relay(deps)?),
+        QueryMsg::GetRelayedSecret { hashlock } => {
+            to_json_binary(&query::query_relayed_secret(deps, hashlock)?)
+        }
+        QueryMsg::GetClaimableFunds { address } => {
+            to_json_binary(&query::query_claimable_funds(deps, address)?)
+        }
+        QueryMsg::GetStats { asset } => to_json_binary(&query::query_stats(deps, asset)?),
     }
 }
 
-#[entry_point]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    Ok(Response::default())
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(
+    mut deps: DepsMut,
+    env: Env,
+    msg: MigrateMsg,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let from = migrations::stored_version(deps.storage)?;
+    let to = migrations::parse_version(CONTRACT_VERSION)?;
+    if from > to {
+        return Err(ContractError::MigrationDowngrade {
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+    }
+
+    // State migrations, oldest first
+    let unmigrated = if from < Version::new(0, 2, 0) {
+        migrations::migrate_from_v0_1(deps.storage, &env)?
+    } else {
+        0
+    };
+    migrations::seed_stats(deps.storage)?;
+    migrations::migrate_config(deps.branch(), &msg)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", from.to_string())
+        .add_attribute("to_version", to.to_string())
+        .add_attribute("unmigrated_escrows", unmigrated.to_string()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coins, Uint128};
+    use bech32::{ToBase32, Variant};
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{
+        coin, coins, Addr, BankMsg, Binary, ContractResult, CosmosMsg, QuerierWrapper, ReplyOn,
+        Storage, SubMsg, SubMsgResponse, SystemResult, Uint128, Uint256, WasmMsg,
+    };
+    use cw_multi_test::{BasicAppBuilder, ContractWrapper, Executor};
 
-    use crate::msg::{DstEscrowResponse, ExecuteMsg};
-    use crate::state::Immutables;
+    use crate::events::EscrowEvent;
+    use crate::msg::{
+        AuctionQuoteResponse, AvailableAction, BatchDstEscrow, BatchItemResult, BatchMode,
+        BatchResponse, BatchWithdrawal, ClaimableFundsResponse, DstEscrowListResponse,
+        DstEscrowResponse, Escrow, EscrowStageResponse, EscrowsResponse, ExecuteMsg,
+        ExtensionResponse, FeeConfigResponse, HashlockEscrowResponse, OrderBy,
+        RevealedSecretResponse, SrcEscrowResponse, StatsResponse, SupportedChainsResponse,
+        SupportedTokensResponse, SweepConfigResponse,
+    };
+    use crate::neutron::{
+        contract_store_key, InterchainQueryResult, KvKey, QueryResultResponse,
+        RegisterQueryResponse, StorageValue, WASM_STORE,
+    };
+    use crate::state::{
+        AddressFormat, AssetStats, DstEscrow, EscrowSide, EscrowStatus, FeeConfig, Immutables,
+        Order, RevealedSecret, SideStats, SrcEscrow, SupportedChain, SupportedToken, SweepConfig,
+        UnmigratedReason, DST_ESCROWS, SRC_ESCROWS, UNMIGRATED_ESCROWS,
+    };
+    use crate::testing::{dst_immutables, extra_data, hashlock_of, keccak, DST_CHAIN_ID};
+    use crate::timelocks::{ActionCaller, EscrowAction, EscrowStage, Stage, Timelocks};
+
+    const DEPOSIT_DENOM: &str = "ucosm";
+    const RESCUE_DELAY: u64 = 86_400;
+
+    fn instantiate_msg() -> InstantiateMsg {
+        InstantiateMsg {
+            safety_deposit_denom: DEPOSIT_DENOM.to_string(),
+            rescue_delay: RESCUE_DELAY,
+        }
+    }
 
     #[test]
     fn proper_initialization() {
         let mut deps = mock_dependencies();
 
-        let msg = InstantiateMsg {};
+        let msg = instantiate_msg();
         let info = mock_info("creator", &coins(1000, "earth"));
 
         // We can just call .unwrap() to assert this was a success
@@ -95,11 +410,13 @@ mod tests {
     fn test_create_dst_escrow() {
         let mut deps = mock_dependencies();
         let env = mock_env();
-        let info = mock_info("creator", &coins(1000, "token"));
+        let info = mock_info(
+            "creator",
+            &[coin(1000, "token_address"), coin(100, DEPOSIT_DENOM)],
+        );
 
         // First instantiate the contract
-        let instantiate_msg = InstantiateMsg {};
-        instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg).unwrap();
+        instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg()).unwrap();
 
         let immutables = Immutables {
             order_hash: "0x1234567890abcdef".to_string(),
@@ -109,7 +426,7 @@ mod tests {
             token: cosmwasm_std::Addr::unchecked("token_address"),
             amount: Uint128::from(1000u128),
             safety_deposit: Uint128::from(100u128),
-            timelocks: Uint128::from(3600u128),
+            timelocks: Uint256::from(3600u128),
         };
 
         let timestamp = Uint128::from(env.block.time.seconds() + 3600);
@@ -124,7 +441,10 @@ mod tests {
 
         // Check that event was emitted
         assert_eq!(res.events.len(), 1);
-        assert_eq!(res.events[0].ty, crate::state::EVENT_TYPE_DST_ESCROW_CREATED);
+        assert_eq!(
+            res.events[0].ty,
+            crate::events::EVENT_TYPE_DST_ESCROW_CREATED
+        );
 
         // Check that escrow was stored by querying
         let escrow_address = "cosmos1test".to_string();
@@ -133,7 +453,7 @@ mod tests {
         };
 
         let query_res = query(deps.as_ref(), env, query_msg).unwrap();
-        let escrow_response: DstEscrowResponse = cosmwasm_std::from_binary(&query_res).unwrap();
+        let escrow_response: DstEscrowResponse = cosmwasm_std::from_json(&query_res).unwrap();
 
         assert_eq!(escrow_response.escrow_address, escrow_address);  // Changed from escrow_key
         assert_eq!(escrow_response.immutables, Some(immutables));
@@ -146,8 +466,7 @@ mod tests {
         let info = mock_info("creator", &coins(1000, "token"));
 
         // First instantiate the contract
-        let instantiate_msg = InstantiateMsg {};
-        instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg).unwrap();
+        instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg()).unwrap();
 
         let immutables = Immutables {
             order_hash: "0x1234567890abcdef".to_string(),
@@ -157,7 +476,7 @@ mod tests {
             token: cosmwasm_std::Addr::unchecked("token_address"),
             amount: Uint128::from(1000u128),
             safety_deposit: Uint128::from(100u128),
-            timelocks: Uint128::from(3600u128),
+            timelocks: Uint256::from(3600u128),
         };
 
         // Use past timestamp
@@ -172,4 +491,2735 @@ mod tests {
         let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
         assert!(matches!(err, ContractError::InvalidTimestamp {}));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_status_transitions() {
+        let settled = [
+            EscrowStatus::Withdrawn,
+            EscrowStatus::Cancelled,
+            EscrowStatus::Rescued,
+        ];
+
+        // Only an active escrow can settle, and it settles exactly once
+        for next in settled {
+            assert_eq!(EscrowStatus::Active.transition(next).unwrap(), next);
+            for status in settled {
+                match status.transition(next).unwrap_err() {
+                    ContractError::InvalidStatusTransition { from, to } => {
+                        assert_eq!((from, to), (status, next))
+                    }
+                    err => panic!("unexpected error: {err}"),
+                }
+            }
+        }
+        for status in [EscrowStatus::Active].into_iter().chain(settled) {
+            assert!(status.transition(EscrowStatus::Active).is_err());
+        }
+    }
+
+    fn dst_funds() -> Vec<cosmwasm_std::Coin> {
+        vec![coin(1000, "token_address"), coin(100, DEPOSIT_DENOM)]
+    }
+
+    #[test]
+    fn test_query_dst_escrows_by_index() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &dst_funds());
+        instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg()).unwrap();
+
+        let escrows = [
+            (
+                "cosmos1escrowa",
+                dst_immutables("maker_one", "0xorder1", "0xhash1"),
+            ),
+            (
+                "cosmos1escrowb",
+                dst_immutables("maker_two", "0xorder1", "0xhash2"),
+            ),
+            (
+                "cosmos1escrowc",
+                dst_immutables("maker_one", "0xorder2", "0xhash3"),
+            ),
+        ];
+        for (escrow_address, immutables) in escrows.iter() {
+            let msg = ExecuteMsg::CreateDstEscrow {
+                escrow_address: escrow_address.to_string(),
+                immutables: immutables.clone(),
+                timestamp: Uint128::from(env.block.time.seconds() + 3600),
+            };
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        let stored = |immutables: &Immutables| DstEscrow {
+            immutables: immutables.clone(),
+            status: EscrowStatus::Active,
+            deployed_at: env.block.time,
+            funded: true,
+            counterpart_verified: false,
+        };
+
+        // Maker index, paginated
+        let query_msg = QueryMsg::EscrowsByMaker {
+            side: EscrowSide::Dst,
+            maker: "maker_one".to_string(),
+            start_after: None,
+            limit: Some(1),
+        };
+        let res: EscrowsResponse =
+            cosmwasm_std::from_json(query(deps.as_ref(), env.clone(), query_msg).unwrap()).unwrap();
+        assert_eq!(
+            res.escrows,
+            vec![(
+                "cosmos1escrowa".to_string(),
+                Escrow::Dst(stored(&escrows[0].1))
+            )]
+        );
+
+        let query_msg = QueryMsg::EscrowsByMaker {
+            side: EscrowSide::Dst,
+            maker: "maker_one".to_string(),
+            start_after: Some("cosmos1escrowa".to_string()),
+            limit: None,
+        };
+        let res: EscrowsResponse =
+            cosmwasm_std::from_json(query(deps.as_ref(), env.clone(), query_msg).unwrap()).unwrap();
+        assert_eq!(
+            res.escrows,
+            vec![(
+                "cosmos1escrowc".to_string(),
+                Escrow::Dst(stored(&escrows[2].1))
+            )]
+        );
+
+        // Taker index
+        let query_msg = QueryMsg::EscrowsByTaker {
+            side: EscrowSide::Dst,
+            taker: "taker_address".to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let res: EscrowsResponse =
+            cosmwasm_std::from_json(query(deps.as_ref(), env.clone(), query_msg).unwrap()).unwrap();
+        assert_eq!(res.escrows.len(), 3);
+
+        // Order hash index
+        let query_msg = QueryMsg::EscrowByOrderHash {
+            side: EscrowSide::Dst,
+            order_hash: "0xorder1".to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let res: EscrowsResponse =
+            cosmwasm_std::from_json(query(deps.as_ref(), env.clone(), query_msg).unwrap()).unwrap();
+        let addresses: Vec<_> = res
+            .escrows
+            .into_iter()
+            .map(|(address, _)| address)
+            .collect();
+        assert_eq!(addresses, vec!["cosmos1escrowa", "cosmos1escrowb"]);
+
+        // Unique hashlock index
+        let query_msg = QueryMsg::EscrowByHashlock {
+            hashlock: "0xhash2".to_string(),
+        };
+        let res: HashlockEscrowResponse =
+            cosmwasm_std::from_json(query(deps.as_ref(), env.clone(), query_msg).unwrap()).unwrap();
+        assert_eq!(
+            res.escrow,
+            Some((
+                "cosmos1escrowb".to_string(),
+                Escrow::Dst(stored(&escrows[1].1))
+            ))
+        );
+
+        let query_msg = QueryMsg::EscrowByHashlock {
+            hashlock: "0xunknown".to_string(),
+        };
+        let res: HashlockEscrowResponse =
+            cosmwasm_std::from_json(query(deps.as_ref(), env, query_msg).unwrap()).unwrap();
+        assert_eq!(res.escrow, None);
+    }
+
+    #[test]
+    fn test_list_escrows_pagination() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &dst_funds());
+        instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg()).unwrap();
+
+        let list = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, msg: QueryMsg| {
+            let res: DstEscrowListResponse =
+                cosmwasm_std::from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+            let addresses: Vec<_> = res
+                .escrows
+                .into_iter()
+                .map(|(address, _)| address)
+                .collect();
+            (addresses, res.next_start_after, res.total)
+        };
+        let list_msg = |start_after: Option<&str>, end_before: Option<&str>, order_by| {
+            QueryMsg::ListDstEscrows {
+                start_after: start_after.map(String::from),
+                end_before: end_before.map(String::from),
+                limit: Some(2),
+                order_by,
+                status: None,
+                token: None,
+                maker: None,
+            }
+        };
+        let (addresses, next, total) = list(&deps, list_msg(None, None, None));
+        assert_eq!((addresses.len(), next, total), (0, None, 0));
+
+        for (escrow_address, maker, hashlock) in [
+            ("cosmos1escrowa", "maker_one", "0xhash1"),
+            ("cosmos1escrowb", "maker_two", "0xhash2"),
+            ("cosmos1escrowc", "maker_one", "0xhash3"),
+        ] {
+            let msg = ExecuteMsg::CreateDstEscrow {
+                escrow_address: escrow_address.to_string(),
+                immutables: dst_immutables(maker, "0xorder1", hashlock),
+                timestamp: Uint128::from(env.block.time.seconds() + 3600),
+            };
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        // Ascending pages hand out a cursor until the last one
+        let (addresses, next, total) = list(&deps, list_msg(None, None, None));
+        assert_eq!(addresses, vec!["cosmos1escrowa", "cosmos1escrowb"]);
+        assert_eq!(next.as_deref(), Some("cosmos1escrowb"));
+        assert_eq!(total, 3);
+        let (addresses, next, _) = list(&deps, list_msg(next.as_deref(), None, None));
+        assert_eq!(addresses, vec!["cosmos1escrowc"]);
+        assert_eq!(next, None);
+
+        // Descending pages continue below the cursor
+        let descending = Some(OrderBy::Descending);
+        let (addresses, next, _) = list(&deps, list_msg(None, None, descending));
+        assert_eq!(addresses, vec!["cosmos1escrowc", "cosmos1escrowb"]);
+        let (addresses, next, _) = list(&deps, list_msg(next.as_deref(), None, descending));
+        assert_eq!(addresses, vec!["cosmos1escrowa"]);
+        assert_eq!(next, None);
+        let (addresses, _, _) = list(&deps, list_msg(None, Some("cosmos1escrowa"), descending));
+        assert_eq!(addresses, vec!["cosmos1escrowc", "cosmos1escrowb"]);
+        let (addresses, next, _) = list(&deps, list_msg(None, Some("cosmos1escrowc"), None));
+        assert_eq!(addresses, vec!["cosmos1escrowa", "cosmos1escrowb"]);
+        assert_eq!(next, None);
+
+        // Filters narrow the page but not the total
+        let msg = QueryMsg::ListDstEscrows {
+            start_after: None,
+            end_before: None,
+            limit: None,
+            order_by: None,
+            status: Some(EscrowStatus::Active),
+            token: Some("token_address".to_string()),
+            maker: Some("maker_one".to_string()),
+        };
+        let (addresses, _, total) = list(&deps, msg);
+        assert_eq!(addresses, vec!["cosmos1escrowa", "cosmos1escrowc"]);
+        assert_eq!(total, 3);
+        let msg = QueryMsg::ListDstEscrows {
+            start_after: None,
+            end_before: None,
+            limit: None,
+            order_by: None,
+            status: None,
+            token: Some("other_token".to_string()),
+            maker: None,
+        };
+        assert!(list(&deps, msg).0.is_empty());
+
+        // Maker and token filters page through their index with the same cursors
+        let by_maker = |start_after: Option<&str>| QueryMsg::ListDstEscrows {
+            start_after: start_after.map(String::from),
+            end_before: None,
+            limit: Some(1),
+            order_by: descending,
+            status: None,
+            token: None,
+            maker: Some("maker_one".to_string()),
+        };
+        let (addresses, next, _) = list(&deps, by_maker(None));
+        assert_eq!(addresses, vec!["cosmos1escrowc"]);
+        let (addresses, next, _) = list(&deps, by_maker(next.as_deref()));
+        assert_eq!(addresses, vec!["cosmos1escrowa"]);
+        assert_eq!(next, None);
+        let msg = QueryMsg::ListDstEscrows {
+            start_after: Some("cosmos1escrowa".to_string()),
+            end_before: None,
+            limit: None,
+            order_by: None,
+            status: None,
+            token: Some("token_address".to_string()),
+            maker: None,
+        };
+        let (addresses, _, _) = list(&deps, msg);
+        assert_eq!(addresses, vec!["cosmos1escrowb", "cosmos1escrowc"]);
+    }
+
+    #[test]
+    fn test_list_escrows_scan_cap() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        // Only the escrow past a long run of cancelled ones is active
+        for i in 0..=crate::query::MAX_SCAN {
+            let escrow = DstEscrow {
+                immutables: dst_immutables("maker_address", "0xorder1", &format!("0xhash{}", i)),
+                status: if i < crate::query::MAX_SCAN {
+                    EscrowStatus::Cancelled
+                } else {
+                    EscrowStatus::Active
+                },
+                deployed_at: env.block.time,
+                funded: true,
+                counterpart_verified: false,
+            };
+            DST_ESCROWS
+                .save(&mut deps.storage, format!("cosmos1escrow{:05}", i), &escrow)
+                .unwrap();
+        }
+
+        // A page stops after reading its share of escrows and hands out where it stopped
+        let list = |start_after: Option<String>| {
+            let msg = QueryMsg::ListDstEscrows {
+                start_after,
+                end_before: None,
+                limit: None,
+                order_by: None,
+                status: Some(EscrowStatus::Active),
+                token: None,
+                maker: None,
+            };
+            let res: DstEscrowListResponse =
+                cosmwasm_std::from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+            res
+        };
+        let res = list(None);
+        assert!(res.escrows.is_empty());
+        let next = format!("cosmos1escrow{:05}", crate::query::MAX_SCAN - 1);
+        assert_eq!(res.next_start_after, Some(next.clone()));
+        let res = list(Some(next));
+        assert_eq!(res.escrows.len(), 1);
+        assert_eq!(res.next_start_after, None);
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let taker = mock_info("taker_address", &[]);
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), info, instantiate_msg()).unwrap();
+        register_counterpart(deps.as_mut());
+
+        let stats = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, asset: Option<&str>| {
+            let msg = QueryMsg::GetStats {
+                asset: asset.map(String::from),
+            };
+            cosmwasm_std::from_json::<StatsResponse>(query(deps.as_ref(), mock_env(), msg).unwrap())
+                .unwrap()
+        };
+        let res = stats(&deps, None);
+        assert_eq!(res.dst, SideStats::default());
+        assert!(res.assets.is_empty());
+
+        // Two destination escrows, withdrawable from +10 and cancellable from +1000
+        for (escrow_address, secret) in [("cosmos1escrowa", b"a"), ("cosmos1escrowb", b"b")] {
+            let mut immutables = dst_immutables("maker_address", "0xorder1", &hashlock_of(secret));
+            immutables.timelocks = Timelocks::pack([0, 0, 0, 0, 10, 100, 1000]);
+            let msg = ExecuteMsg::CreateDstEscrow {
+                escrow_address: escrow_address.to_string(),
+                immutables,
+                timestamp: Uint128::from(env.block.time.seconds() + 3600),
+            };
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("taker_address", &dst_funds()),
+                msg,
+            )
+            .unwrap();
+        }
+        let msg = create_src_escrow_msg("cosmos1escrowsrc", [1u8; 32], Timelocks::pack([0; 7]));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &src_funds()),
+            msg,
+        )
+        .unwrap();
+
+        let locked = |amount: u128| AssetStats {
+            locked: Uint128::from(amount),
+            settled: Uint128::zero(),
+        };
+        let res = stats(&deps, None);
+        assert_eq!(res.dst.created, 2);
+        assert_eq!(res.src.created, 1);
+        assert_eq!(
+            res.assets,
+            vec![
+                ("maker_asset".to_string(), locked(1000)),
+                ("token_address".to_string(), locked(2000)),
+            ]
+        );
+
+        env.block.time = env.block.time.plus_seconds(10);
+        let msg = ExecuteMsg::Withdraw {
+            escrow_address: "cosmos1escrowa".to_string(),
+            secret: hex::encode(b"a"),
+        };
+        execute(deps.as_mut(), env.clone(), taker.clone(), msg).unwrap();
+        env.block.time = env.block.time.plus_seconds(1000);
+        let msg = ExecuteMsg::Cancel {
+            escrow_address: "cosmos1escrowb".to_string(),
+        };
+        execute(deps.as_mut(), env, taker, msg).unwrap();
+
+        // Withdrawals settle the locked amount, cancellations only release it
+        let res = stats(&deps, Some("token_address"));
+        assert_eq!(
+            res.dst,
+            SideStats {
+                created: 2,
+                withdrawn: 1,
+                cancelled: 1,
+            }
+        );
+        assert_eq!(res.src.withdrawn + res.src.cancelled, 0);
+        assert_eq!(
+            res.assets,
+            vec![(
+                "token_address".to_string(),
+                AssetStats {
+                    locked: Uint128::zero(),
+                    settled: Uint128::from(1000u128),
+                }
+            )]
+        );
+        let res = stats(&deps, Some("unknown"));
+        assert_eq!(
+            res.assets,
+            vec![("unknown".to_string(), AssetStats::default())]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_hashlock() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &dst_funds());
+        instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg()).unwrap();
+
+        let immutables = dst_immutables("maker_address", "0xorder1", "0xhash1");
+        let msg = ExecuteMsg::CreateDstEscrow {
+            escrow_address: "cosmos1escrowa".to_string(),
+            immutables: immutables.clone(),
+            timestamp: Uint128::from(env.block.time.seconds() + 3600),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // A different escrow address cannot reuse the same hashlock
+        let msg = ExecuteMsg::CreateDstEscrow {
+            escrow_address: "cosmos1escrowb".to_string(),
+            immutables,
+            timestamp: Uint128::from(env.block.time.seconds() + 3600),
+        };
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::HashlockAlreadyExists {}));
+    }
+
+    #[test]
+    fn test_query_src_escrows_by_maker() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &src_funds());
+        instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg()).unwrap();
+        register_counterpart(deps.as_mut());
+
+        let msg = create_src_escrow_msg("cosmos1escrowsrc", [1u8; 32], Timelocks::pack([0; 7]));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let query_msg = QueryMsg::EscrowsByMaker {
+            side: EscrowSide::Src,
+            maker: "maker_address".to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let res: EscrowsResponse =
+            cosmwasm_std::from_json(query(deps.as_ref(), env.clone(), query_msg).unwrap()).unwrap();
+        assert_eq!(res.escrows.len(), 1);
+        assert_eq!(res.escrows[0].0, "cosmos1escrowsrc");
+
+        // The destination side has no escrows for this maker
+        let query_msg = QueryMsg::EscrowsByMaker {
+            side: EscrowSide::Dst,
+            maker: "maker_address".to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let res: EscrowsResponse =
+            cosmwasm_std::from_json(query(deps.as_ref(), env, query_msg).unwrap()).unwrap();
+        assert!(res.escrows.is_empty());
+    }
+
+    #[test]
+    fn test_src_escrow_hashlock() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &src_funds());
+        instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg()).unwrap();
+        register_counterpart(deps.as_mut());
+
+        let msg = create_src_escrow_msg("cosmos1escrowsrc", [1u8; 32], Timelocks::pack([0; 7]));
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Source escrows are found by the hashlock decoded from their extra data
+        let query_msg = QueryMsg::EscrowByHashlock {
+            hashlock: format!("0x{}", hex::encode([1u8; 32])),
+        };
+        let res: HashlockEscrowResponse =
+            cosmwasm_std::from_json(query(deps.as_ref(), env.clone(), query_msg).unwrap()).unwrap();
+        assert!(matches!(
+            res.escrow,
+            Some((address, Escrow::Src(_))) if address == "cosmos1escrowsrc"
+        ));
+
+        // and cannot share it
+        let msg = create_src_escrow_msg("cosmos1escrowsrc2", [1u8; 32], Timelocks::pack([0; 7]));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::HashlockAlreadyExists {}));
+
+        // Stages starting out of order are rejected
+        let msg = create_src_escrow_msg(
+            "cosmos1escrowsrc3",
+            [3u8; 32],
+            Timelocks::pack([20, 10, 30, 40, 0, 0, 0]),
+        );
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidTimelocks {
+                earlier: Stage::SrcWithdrawal,
+                later: Stage::SrcPublicWithdrawal,
+            }
+        ));
+    }
+
+    /// Registers the counterpart chain and token the source escrow helpers swap into
+    fn register_counterpart(mut deps: DepsMut) {
+        let owner = mock_info("creator", &[]);
+        let msg = ExecuteMsg::RegisterChain {
+            chain_id: Uint256::from(DST_CHAIN_ID),
+            address_format: AddressFormat::Evm,
+            factory: format!("0x{}", "11".repeat(20)),
+            connection_id: None,
+        };
+        execute(deps.branch(), mock_env(), owner.clone(), msg).unwrap();
+        let msg = ExecuteMsg::RegisterToken {
+            chain_id: Uint256::from(DST_CHAIN_ID),
+            src_token: "maker_asset".to_string(),
+            dst_token: format!("0x{}", "dd".repeat(20)),
+        };
+        execute(deps, mock_env(), owner, msg).unwrap();
+    }
+
+    #[test]
+    fn test_counterpart_registry() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), owner.clone(), instantiate_msg()).unwrap();
+
+        // Source escrows cannot be created before their counterpart is registered
+        let msg = create_src_escrow_msg("cosmos1escrowsrc", [1u8; 32], Timelocks::pack([0; 7]));
+        let info = mock_info("resolver", &src_funds());
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedChain { chain_id } if chain_id == "56"));
+
+        let register_chain = |chain_id: u128, factory: String| ExecuteMsg::RegisterChain {
+            chain_id: Uint256::from(chain_id),
+            address_format: AddressFormat::Evm,
+            factory,
+            connection_id: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            register_chain(1, format!("0x{}", "11".repeat(20))),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            owner.clone(),
+            register_chain(1, "0x1234".to_string()),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidCounterpartAddress { .. }
+        ));
+
+        // Tokens can only be paired on a registered chain
+        let msg = ExecuteMsg::RegisterToken {
+            chain_id: Uint256::from(1u128),
+            src_token: "maker_asset".to_string(),
+            dst_token: format!("0x{}", "dd".repeat(20)),
+        };
+        let err = execute(deps.as_mut(), env.clone(), owner.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedChain { .. }));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            owner.clone(),
+            register_chain(1, format!("0x{}", "AA".repeat(20))),
+        )
+        .unwrap();
+        execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+        register_counterpart(deps.as_mut());
+
+        // Chains list by id, with addresses normalized to lowercase
+        let query_msg = QueryMsg::ListSupportedChains {
+            start_after: None,
+            limit: None,
+        };
+        let res: SupportedChainsResponse =
+            cosmwasm_std::from_json(query(deps.as_ref(), env.clone(), query_msg).unwrap()).unwrap();
+        assert_eq!(
+            res.chains,
+            vec![
+                SupportedChain {
+                    chain_id: Uint256::from(1u128),
+                    address_format: AddressFormat::Evm,
+                    factory: format!("0x{}", "aa".repeat(20)),
+                    connection_id: None,
+                },
+                SupportedChain {
+                    chain_id: Uint256::from(DST_CHAIN_ID),
+                    address_format: AddressFormat::Evm,
+                    factory: format!("0x{}", "11".repeat(20)),
+                    connection_id: None,
+                },
+            ]
+        );
+
+        // The extra data has to name the token paired with the maker asset
+        let mut bad_token =
+            create_src_escrow_msg("cosmos1escrowsrc", [1u8; 32], Timelocks::pack([0; 7]));
+        if let ExecuteMsg::CreateSrcEscrow { extra_data, .. } = &mut bad_token {
+            let mut bytes = extra_data.to_vec();
+            bytes[95] = 0xee;
+            *extra_data = Binary::from(bytes);
+        }
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), bad_token).unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedToken { .. }));
+        let msg = create_src_escrow_msg("cosmos1escrowsrc", [1u8; 32], Timelocks::pack([0; 7]));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // Removing a chain drops its token pairs
+        let msg = ExecuteMsg::RemoveChain {
+            chain_id: Uint256::from(DST_CHAIN_ID),
+        };
+        execute(deps.as_mut(), env.clone(), owner, msg).unwrap();
+        let query_msg = QueryMsg::ListSupportedTokens {
+            chain_id: Uint256::from(DST_CHAIN_ID),
+            start_after: None,
+            limit: None,
+        };
+        let res: SupportedTokensResponse =
+            cosmwasm_std::from_json(query(deps.as_ref(), env.clone(), query_msg).unwrap()).unwrap();
+        assert!(res.tokens.is_empty());
+        let query_msg = QueryMsg::ListSupportedTokens {
+            chain_id: Uint256::from(1u128),
+            start_after: None,
+            limit: None,
+        };
+        let res: SupportedTokensResponse =
+            cosmwasm_std::from_json(query(deps.as_ref(), env, query_msg).unwrap()).unwrap();
+        assert_eq!(
+            res.tokens,
+            vec![SupportedToken {
+                chain_id: Uint256::from(1u128),
+                src_token: "maker_asset".to_string(),
+                dst_token: format!("0x{}", "dd".repeat(20)),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_request_dst_escrow_over_ibc() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            instantiate_msg(),
+        )
+        .unwrap();
+        register_counterpart(deps.as_mut());
+
+        let timelocks = Timelocks::pack([10, 100, 1000, 2000, 5, 50, 500]);
+        let msg = create_src_escrow_msg("cosmos1escrowsrc", [1u8; 32], timelocks);
+        let info = mock_info("resolver", &src_funds());
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // The channel leads to the escrow factory of a registered Cosmos chain
+        let msg = ExecuteMsg::RegisterChain {
+            chain_id: Uint256::from(7u128),
+            address_format: AddressFormat::Bytes32,
+            factory: format!("0x{}", "22".repeat(32)),
+            connection_id: Some("connection-2".to_string()),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let factory = bech32::encode("cosmos", [0x22u8; 32].to_base32(), Variant::Bech32).unwrap();
+        let mut channel = cosmwasm_std::testing::mock_ibc_channel(
+            "channel-1",
+            cosmwasm_std::IbcOrder::Unordered,
+            crate::ibc::IBC_VERSION,
+        );
+        channel.counterparty_endpoint.port_id = format!("wasm.{}", factory);
+        let connect = cosmwasm_std::IbcChannelConnectMsg::new_ack(channel, crate::ibc::IBC_VERSION);
+        crate::ibc::ibc_channel_connect(deps.as_mut(), env.clone(), connect).unwrap();
+
+        let mut immutables = dst_immutables(
+            "maker_address",
+            "0xorder1",
+            &format!("0x{}", hex::encode([1u8; 32])),
+        );
+        immutables.timelocks = timelocks;
+        let request = |channel_id: &str, immutables: Immutables| ExecuteMsg::RequestDstEscrow {
+            src_escrow_address: "cosmos1escrowsrc".to_string(),
+            channel_id: channel_id.to_string(),
+            escrow_address: "cosmos1escrowdst".to_string(),
+            immutables,
+        };
+        let taker = mock_info("taker_address", &[]);
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            request("channel-1", immutables.clone()),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let mut other_secret = immutables.clone();
+        other_secret.hashlock = "0xhash2".to_string();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            taker.clone(),
+            request("channel-1", other_secret),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ImmutablesMismatch {}));
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            taker.clone(),
+            request("channel-9", immutables.clone()),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::UnknownChannel { .. }));
+
+        // The request travels as a packet the counterpart factory can decode
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            taker,
+            request("channel-1", immutables.clone()),
+        )
+        .unwrap();
+        let CosmosMsg::Ibc(cosmwasm_std::IbcMsg::SendPacket {
+            channel_id,
+            data,
+            timeout,
+        }) = &res.messages[0].msg
+        else {
+            panic!("expected an IBC packet");
+        };
+        assert_eq!(channel_id, "channel-1");
+        assert_eq!(timeout.timestamp(), Some(env.block.time.plus_seconds(600)));
+        assert_eq!(
+            cosmwasm_std::from_json::<crate::ibc::EscrowPacket>(data).unwrap(),
+            crate::ibc::EscrowPacket::CreateDstEscrow {
+                escrow_address: "cosmos1escrowdst".to_string(),
+                immutables,
+            }
+        );
+    }
+
+    #[test]
+    fn test_ibc_hook_funds_escrow() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            instantiate_msg(),
+        )
+        .unwrap();
+
+        let intermediary =
+            crate::hooks::intermediary_sender("channel-0", "osmo1maker", "neutron").unwrap();
+        let hook = |msg: ExecuteMsg| ExecuteMsg::IbcHook {
+            channel: "channel-0".to_string(),
+            original_sender: "osmo1maker".to_string(),
+            recovery_address: "maker_address".to_string(),
+            msg: Box::new(msg),
+        };
+        let create = |escrow_address: &str| ExecuteMsg::CreateDstEscrow {
+            escrow_address: escrow_address.to_string(),
+            immutables: dst_immutables("maker_address", "0xorder1", "0xhash1"),
+            timestamp: Uint128::from(env.block.time.seconds() + 3600),
+        };
+
+        // The memo can only be executed by the intermediary derived for its channel and sender
+        let other =
+            crate::hooks::intermediary_sender("channel-1", "osmo1maker", "neutron").unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(&other, &dst_funds()),
+            hook(create("cosmos1escrowa")),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidHookSender {}));
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(&intermediary, &dst_funds()),
+            hook(ExecuteMsg::ClaimFunds {}),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedHookMsg {}));
+
+        // The transferred coins fund the escrow
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(&intermediary, &dst_funds()),
+            hook(create("cosmos1escrowa")),
+        )
+        .unwrap();
+        let escrow = crate::state::DST_ESCROWS
+            .load(deps.as_ref().storage, "cosmos1escrowa".to_string())
+            .unwrap();
+        assert!(escrow.funded);
+
+        // A second transfer reusing the hashlock cannot create its escrow, its coins are
+        // left for the recovery address
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(&intermediary, &dst_funds()),
+            hook(create("cosmos1escrowb")),
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+        let query_msg = QueryMsg::GetClaimableFunds {
+            address: "maker_address".to_string(),
+        };
+        let res: ClaimableFundsResponse =
+            cosmwasm_std::from_json(query(deps.as_ref(), env.clone(), query_msg).unwrap()).unwrap();
+        assert_eq!(res.funds, dst_funds());
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("maker_address", &[]),
+            ExecuteMsg::ClaimFunds {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "maker_address".to_string(),
+                amount: dst_funds(),
+            })
+        );
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info("maker_address", &[]),
+            ExecuteMsg::ClaimFunds {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NothingToClaim {}));
+    }
+
+    /// Answers a counterpart query with `value` through a mocked Neutron interchain
+    /// queries module, then runs the sudo callback Neutron makes on a new result
+    fn deliver_query_result(
+        deps: &mut cosmwasm_std::OwnedDeps<MockStorage, MockApi, MockQuerier>,
+        query_id: u64,
+        value: Vec<u8>,
+    ) -> Result<Response<NeutronMsg>, ContractError> {
+        let querier = MockQuerier::<NeutronQuery>::new(&[]).with_custom_handler(move |query| {
+            let NeutronQuery::InterchainQueryResult { query_id: queried } = query;
+            assert_eq!(*queried, query_id);
+            let response = QueryResultResponse {
+                result: InterchainQueryResult {
+                    kv_results: vec![StorageValue {
+                        storage_prefix: WASM_STORE.to_string(),
+                        key: Binary::default(),
+                        value: Binary::from(value.clone()),
+                    }],
+                    height: 1234,
+                    revision: 1,
+                },
+            };
+            SystemResult::Ok(ContractResult::Ok(to_json_binary(&response).unwrap()))
+        });
+        run_sudo(
+            deps,
+            &querier,
+            mock_env(),
+            SudoMsg::KvQueryResult { query_id },
+        )
+    }
+
+    /// Runs a sudo callback on `deps`, with custom queries answered by `querier`
+    fn run_sudo(
+        deps: &mut cosmwasm_std::OwnedDeps<MockStorage, MockApi, MockQuerier>,
+        querier: &MockQuerier<NeutronQuery>,
+        env: Env,
+        msg: SudoMsg,
+    ) -> Result<Response<NeutronMsg>, ContractError> {
+        let neutron_deps = DepsMut {
+            storage: &mut deps.storage,
+            api: &deps.api,
+            querier: QuerierWrapper::new(querier),
+        };
+        sudo(neutron_deps, env, msg)
+    }
+
+    fn query_registered(deps: DepsMut, query_id: u64) -> Response<NeutronMsg> {
+        let data = to_json_binary(&RegisterQueryResponse { id: query_id }).unwrap();
+        let msg = Reply {
+            id: REGISTER_QUERY_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(data),
+            }),
+        };
+        reply(deps, mock_env(), msg).unwrap()
+    }
+
+    #[test]
+    fn test_verify_counterpart_over_icq() {
+        let timelocks = Timelocks::pack([10, 100, 1000, 2000, 5, 50, 500]);
+        let owner = mock_info("creator", &[]);
+
+        // The source leg, as the factory on the counterpart chain stores it
+        let mut counterpart = mock_dependencies();
+        let env = mock_env();
+        instantiate(
+            counterpart.as_mut(),
+            env.clone(),
+            owner.clone(),
+            instantiate_msg(),
+        )
+        .unwrap();
+        register_counterpart(counterpart.as_mut());
+        let msg = create_src_escrow_msg("cosmos1escrowsrc", [1u8; 32], timelocks);
+        execute(
+            counterpart.as_mut(),
+            env.clone(),
+            mock_info("resolver", &src_funds()),
+            msg,
+        )
+        .unwrap();
+        let src_key = SRC_ESCROWS.key("cosmos1escrowsrc".to_string());
+        let src_value = counterpart.storage.get(&src_key).unwrap();
+
+        let mut deps = mock_dependencies();
+        instantiate(deps.as_mut(), env.clone(), owner.clone(), instantiate_msg()).unwrap();
+        register_counterpart(deps.as_mut());
+        let msg = ExecuteMsg::RegisterChain {
+            chain_id: Uint256::from(7u128),
+            address_format: AddressFormat::Bytes32,
+            factory: format!("0x{}", "22".repeat(32)),
+            connection_id: Some("connection-0".to_string()),
+        };
+        execute(deps.as_mut(), env.clone(), owner, msg).unwrap();
+        for (escrow_address, hashlock) in
+            [("cosmos1escrowdst", [1u8; 32]), ("cosmos1other", [2; 32])]
+        {
+            let mut immutables = dst_immutables(
+                "maker_address",
+                "0xorder1",
+                &format!("0x{}", hex::encode(hashlock)),
+            );
+            immutables.timelocks = timelocks;
+            let msg = ExecuteMsg::CreateDstEscrow {
+                escrow_address: escrow_address.to_string(),
+                immutables,
+                timestamp: Uint128::from(env.block.time.seconds() + 3600),
+            };
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("taker_address", &dst_funds()),
+                msg,
+            )
+            .unwrap();
+        }
+        let verify = |escrow_address: &str, chain_id: u128| ExecuteMsg::VerifyCounterpart {
+            escrow_address: escrow_address.to_string(),
+            chain_id: Uint256::from(chain_id),
+            counterpart_address: "cosmos1escrowsrc".to_string(),
+        };
+        let taker = mock_info("taker_address", &[]);
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            verify("cosmos1escrowdst", 7),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // EVM factories cannot be queried over IBC
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            taker.clone(),
+            verify("cosmos1escrowdst", 56),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::CounterpartNotQueryable { .. }));
+
+        // A source escrow's counterpart has to be on its order's destination chain
+        let msg = create_src_escrow_msg("cosmos1escrowsrc", [3u8; 32], timelocks);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("resolver", &src_funds()),
+            msg,
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            taker.clone(),
+            verify("cosmos1escrowsrc", 7),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::CounterpartChainMismatch { .. }
+        ));
+
+        // The query watches the counterpart escrow's key in the remote factory's state
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            taker.clone(),
+            verify("cosmos1escrowdst", 7),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_success(
+                NeutronMsg::RegisterInterchainQuery {
+                    query_type: "kv".to_string(),
+                    keys: vec![KvKey {
+                        path: "wasm".to_string(),
+                        key: contract_store_key(&[0x22; 32], &src_key).into(),
+                    }],
+                    transactions_filter: String::new(),
+                    connection_id: "connection-0".to_string(),
+                    update_period: 10,
+                },
+                REGISTER_QUERY_REPLY_ID,
+            )]
+        );
+        query_registered(deps.as_mut(), 7);
+
+        // Until the counterpart shows up the query keeps running
+        let res = deliver_query_result(&mut deps, 7, vec![]).unwrap();
+        assert!(res.messages.is_empty());
+        let escrow = DST_ESCROWS
+            .load(&deps.storage, "cosmos1escrowdst".to_string())
+            .unwrap();
+        assert!(!escrow.counterpart_verified);
+
+        // A counterpart that was never funded or can no longer be withdrawn does not verify
+        let stored: SrcEscrow = cosmwasm_std::from_json(&src_value).unwrap();
+        let unfunded = SrcEscrow {
+            funded: false,
+            ..stored.clone()
+        };
+        let cancelled = SrcEscrow {
+            status: EscrowStatus::Cancelled,
+            ..stored
+        };
+        for (query_id, counterpart) in [(5, unfunded), (6, cancelled)] {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                taker.clone(),
+                verify("cosmos1escrowdst", 7),
+            )
+            .unwrap();
+            query_registered(deps.as_mut(), query_id);
+            let value = cosmwasm_std::to_json_vec(&counterpart).unwrap();
+            let res = deliver_query_result(&mut deps, query_id, value).unwrap();
+            assert_eq!(
+                res.messages,
+                vec![SubMsg::new(NeutronMsg::RemoveInterchainQuery { query_id })]
+            );
+            let escrow = DST_ESCROWS
+                .load(&deps.storage, "cosmos1escrowdst".to_string())
+                .unwrap();
+            assert!(!escrow.counterpart_verified);
+        }
+
+        // A matching counterpart verifies the escrow, and the query is removed
+        let res = deliver_query_result(&mut deps, 7, src_value.clone()).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(NeutronMsg::RemoveInterchainQuery {
+                query_id: 7
+            })]
+        );
+        let escrow = DST_ESCROWS
+            .load(&deps.storage, "cosmos1escrowdst".to_string())
+            .unwrap();
+        assert!(escrow.counterpart_verified);
+        let err = deliver_query_result(&mut deps, 7, src_value.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::UnknownInterchainQuery { query_id: 7 }
+        ));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            taker.clone(),
+            verify("cosmos1escrowdst", 7),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::CounterpartAlreadyVerified {}));
+
+        // An escrow locking another secret does not verify
+        execute(deps.as_mut(), env, taker, verify("cosmos1other", 7)).unwrap();
+        query_registered(deps.as_mut(), 8);
+        let res = deliver_query_result(&mut deps, 8, src_value).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(NeutronMsg::RemoveInterchainQuery {
+                query_id: 8
+            })]
+        );
+        let escrow = DST_ESCROWS
+            .load(&deps.storage, "cosmos1other".to_string())
+            .unwrap();
+        assert!(!escrow.counterpart_verified);
+    }
+
+    #[test]
+    fn test_sweep_expired() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let owner = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), owner.clone(), instantiate_msg()).unwrap();
+        register_counterpart(deps.as_mut());
+
+        // Source escrows expire at +2000, the destination escrow at +500
+        let timelocks = Timelocks::pack([10, 100, 1000, 2000, 5, 50, 500]);
+        for (escrow_address, hashlock) in [("cosmos1srca", [1u8; 32]), ("cosmos1srcb", [2; 32])] {
+            let msg = create_src_escrow_msg(escrow_address, hashlock, timelocks);
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("resolver", &src_funds()),
+                msg,
+            )
+            .unwrap();
+        }
+        let mut immutables = dst_immutables("maker_address", "0xorder1", "0xhash1");
+        immutables.timelocks = timelocks;
+        let msg = ExecuteMsg::CreateDstEscrow {
+            escrow_address: "cosmos1dsta".to_string(),
+            immutables,
+            timestamp: Uint128::from(env.block.time.seconds() + 3600),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("taker_address", &dst_funds()),
+            msg,
+        )
+        .unwrap();
+
+        // Only the owner sets the batch size, which has to be positive
+        let update = |batch_size: u32| ExecuteMsg::UpdateSweepConfig { batch_size };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            update(1),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = execute(deps.as_mut(), env.clone(), owner.clone(), update(0)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSweepConfig {}));
+        execute(deps.as_mut(), env.clone(), owner, update(1)).unwrap();
+        let res: SweepConfigResponse = cosmwasm_std::from_json(
+            query(deps.as_ref(), env.clone(), QueryMsg::GetSweepConfig {}).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.sweep_config, SweepConfig { batch_size: 1 });
+
+        let querier = MockQuerier::<NeutronQuery>::new(&[]);
+        let status = |storage: &dyn Storage, escrow_address: &str| {
+            crate::state::load_escrow(storage, escrow_address)
+                .unwrap()
+                .status
+        };
+
+        // Each expired escrow is cancelled by a call the contract sends itself, which nobody
+        // else may make
+        let sweep = |deps: &mut cosmwasm_std::OwnedDeps<MockStorage, MockApi, MockQuerier>,
+                     env: Env| {
+            let res = run_sudo(deps, &querier, env.clone(), SudoMsg::SweepExpired {}).unwrap();
+            res.messages
+                .into_iter()
+                .map(|sub| {
+                    assert_eq!(sub.reply_on, ReplyOn::Error);
+                    assert_eq!(sub.id, SWEEP_ESCROW_REPLY_ID);
+                    let CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr, msg, ..
+                    }) = sub.msg
+                    else {
+                        panic!("unexpected sweep message");
+                    };
+                    assert_eq!(contract_addr, env.contract.address.as_str());
+                    let msg: ExecuteMsg = cosmwasm_std::from_json(msg).unwrap();
+                    let anyone = mock_info("anyone", &[]);
+                    let err = execute(deps.as_mut(), env.clone(), anyone, msg.clone()).unwrap_err();
+                    assert!(matches!(err, ContractError::Unauthorized {}));
+                    let contract = mock_info(env.contract.address.as_str(), &[]);
+                    execute(deps.as_mut(), env.clone(), contract, msg).unwrap()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Nothing has expired yet
+        assert!(sweep(&mut deps, env.clone()).is_empty());
+
+        // The taker gets the destination escrow's funds and deposit back
+        env.block.time = env.block.time.plus_seconds(600);
+        let res = sweep(&mut deps, env.clone());
+        assert_eq!(res.len(), 1);
+        let messages: Vec<_> = res[0].messages.iter().map(|sub| sub.msg.clone()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                bank_send("taker_address", 1000, "token_address"),
+                bank_send("taker_address", 100, DEPOSIT_DENOM),
+            ]
+        );
+        assert_eq!(
+            EscrowEvent::try_from(&res[0].events[0]).unwrap(),
+            EscrowEvent::Cancelled {
+                escrow_address: "cosmos1dsta".to_string(),
+                side: EscrowSide::Dst,
+                recipient: Addr::unchecked("taker_address"),
+                amount: Uint128::from(1000u128),
+                caller: env.contract.address.clone(),
+            }
+        );
+        assert_eq!(
+            status(&deps.storage, "cosmos1dsta"),
+            EscrowStatus::Cancelled
+        );
+        assert_eq!(status(&deps.storage, "cosmos1srca"), EscrowStatus::Active);
+
+        // One escrow of each side per sweep, starting over past the last one; the maker is
+        // refunded and the taker gets back the deposit they posted
+        env.block.time = env.block.time.plus_seconds(1500);
+        let res = sweep(&mut deps, env.clone());
+        assert_eq!(res.len(), 1);
+        let messages: Vec<_> = res[0].messages.iter().map(|sub| sub.msg.clone()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                bank_send("maker_address", 1000, "maker_asset"),
+                bank_send("taker_address", 100, DEPOSIT_DENOM),
+            ]
+        );
+        assert_eq!(
+            status(&deps.storage, "cosmos1srca"),
+            EscrowStatus::Cancelled
+        );
+        assert_eq!(status(&deps.storage, "cosmos1srcb"), EscrowStatus::Active);
+
+        let res = sweep(&mut deps, env.clone());
+        assert_eq!(res[0].messages.len(), 2);
+        assert_eq!(
+            status(&deps.storage, "cosmos1srcb"),
+            EscrowStatus::Cancelled
+        );
+
+        // A cancellation that fails only rolls back its own call; the sweep records it and
+        // carries on
+        let contract = mock_info(env.contract.address.as_str(), &[]);
+        let msg = ExecuteMsg::SweepEscrow {
+            escrow_address: "cosmos1srcb".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), contract, msg).unwrap_err();
+        assert!(matches!(err, ContractError::NotActive { .. }));
+        let msg = Reply {
+            id: SWEEP_ESCROW_REPLY_ID,
+            result: SubMsgResult::Err("transfer failed".to_string()),
+        };
+        let res = reply(deps.as_mut(), env, msg).unwrap();
+        assert!(res
+            .attributes
+            .contains(&cosmwasm_std::attr("action", "sweep_escrow_failed")));
+    }
+
+    #[test]
+    fn test_batch_messages() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let taker = mock_info("taker_address", &[]);
+        instantiate(deps.as_mut(), env.clone(), taker.clone(), instantiate_msg()).unwrap();
+
+        // Withdrawable by the taker from +10, cancellable from +1000
+        let timelocks = Timelocks::pack([0, 0, 0, 0, 10, 100, 1000]);
+        let escrow = |escrow_address: &str, secret: &[u8]| {
+            let mut immutables = dst_immutables("maker_address", "0xorder1", &hashlock_of(secret));
+            immutables.timelocks = timelocks;
+            BatchDstEscrow {
+                escrow_address: escrow_address.to_string(),
+                immutables,
+                timestamp: Uint128::from(env.block.time.seconds() + 3600),
+            }
+        };
+        let create = |escrows: Vec<BatchDstEscrow>, mode: BatchMode| {
+            ExecuteMsg::BatchCreateDstEscrows { escrows, mode }
+        };
+        let results = |res: &Response<NeutronMsg>| {
+            cosmwasm_std::from_json::<BatchResponse>(res.data.as_ref().unwrap())
+                .unwrap()
+                .results
+        };
+        let double_funds = vec![coin(2000, "token_address"), coin(200, DEPOSIT_DENOM)];
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            taker.clone(),
+            create(vec![], BatchMode::AllOrNothing),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::EmptyBatch {}));
+
+        // The funds have to cover every escrow of the batch
+        let escrows = vec![
+            escrow("cosmos1escrowa", b"a"),
+            escrow("cosmos1escrowb", b"b"),
+        ];
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("taker_address", &dst_funds()),
+            create(escrows.clone(), BatchMode::AllOrNothing),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFunds { .. }));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("taker_address", &double_funds),
+            create(escrows, BatchMode::AllOrNothing),
+        )
+        .unwrap();
+        assert_eq!(res.events.len(), 2);
+        assert!(results(&res).iter().all(|result| result.error.is_none()));
+
+        // All or nothing fails on any item; best effort creates what it can and refunds the
+        // funds of the rest
+        let escrows = vec![
+            escrow("cosmos1escrowc", b"c"),
+            escrow("cosmos1escrowa", b"d"),
+        ];
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("taker_address", &double_funds),
+            create(escrows.clone(), BatchMode::AllOrNothing),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::EscrowAlreadyExists {}));
+        let escrows = vec![
+            escrow("cosmos1escrowd", b"d"),
+            escrow("cosmos1escrowa", b"e"),
+        ];
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("taker_address", &double_funds),
+            create(escrows, BatchMode::BestEffort),
+        )
+        .unwrap();
+        assert_eq!(
+            results(&res),
+            vec![
+                BatchItemResult {
+                    escrow_address: "cosmos1escrowd".to_string(),
+                    error: None,
+                },
+                BatchItemResult {
+                    escrow_address: "cosmos1escrowa".to_string(),
+                    error: Some(ContractError::EscrowAlreadyExists {}.to_string()),
+                },
+            ]
+        );
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "taker_address".to_string(),
+                amount: dst_funds(),
+            })
+        );
+
+        // A wrong secret fails its item without touching the escrow
+        env.block.time = env.block.time.plus_seconds(10);
+        let withdrawal = |escrow_address: &str, secret: &[u8]| BatchWithdrawal {
+            escrow_address: escrow_address.to_string(),
+            secret: hex::encode(secret),
+        };
+        let msg = ExecuteMsg::BatchWithdraw {
+            withdrawals: vec![
+                withdrawal("cosmos1escrowa", b"a"),
+                withdrawal("cosmos1escrowb", b"x"),
+            ],
+            mode: BatchMode::BestEffort,
+        };
+        let res = execute(deps.as_mut(), env.clone(), taker.clone(), msg).unwrap();
+        let messages: Vec<_> = res.messages.iter().map(|sub| sub.msg.clone()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                bank_send("maker_address", 1000, "token_address"),
+                bank_send("taker_address", 100, DEPOSIT_DENOM),
+            ]
+        );
+        let failed = &results(&res)[1];
+        assert_eq!(
+            failed.error,
+            Some(ContractError::InvalidSecret {}.to_string())
+        );
+        let status = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, escrow_address: &str| {
+            crate::state::load_escrow(&deps.storage, escrow_address)
+                .unwrap()
+                .status
+        };
+        assert_eq!(status(&deps, "cosmos1escrowa"), EscrowStatus::Withdrawn);
+        assert_eq!(status(&deps, "cosmos1escrowb"), EscrowStatus::Active);
+
+        // Cancelling the withdrawn escrow along with the rest fails the whole batch
+        env.block.time = env.block.time.plus_seconds(1000);
+        let cancel = |escrow_addresses: &[&str], mode: BatchMode| ExecuteMsg::BatchCancel {
+            escrow_addresses: escrow_addresses.iter().map(|a| a.to_string()).collect(),
+            mode,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            taker.clone(),
+            cancel(
+                &["cosmos1escrowa", "cosmos1escrowb"],
+                BatchMode::AllOrNothing,
+            ),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidStatusTransition { .. }));
+        let res = execute(
+            deps.as_mut(),
+            env,
+            taker,
+            cancel(
+                &["cosmos1escrowb", "cosmos1escrowd"],
+                BatchMode::AllOrNothing,
+            ),
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 4);
+        assert_eq!(status(&deps, "cosmos1escrowb"), EscrowStatus::Cancelled);
+        assert_eq!(status(&deps, "cosmos1escrowd"), EscrowStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_best_effort_item_failing_after_validation() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let taker = mock_info("taker_address", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            instantiate_msg(),
+        )
+        .unwrap();
+        register_counterpart(deps.as_mut());
+        let msg = ExecuteMsg::UpdateFeeConfig {
+            fee_bps: 100,
+            integrator_share_bps: 5000,
+            fee_collector: "fee_collector".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let timelocks = Timelocks::pack([0, 100, 1000, 2000, 0, 0, 0]);
+        for (escrow_address, secret) in [("cosmos1escrowa", [1u8; 32]), ("cosmos1escrowb", [2; 32])]
+        {
+            let msg = create_src_escrow_msg(
+                escrow_address,
+                keccak(&secret),
+                timelocks,
+            );
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("resolver", &src_funds()),
+                msg,
+            )
+            .unwrap();
+        }
+
+        // The second escrow passes every check up to its fees, which cannot be worked out
+        let mut stored = SRC_ESCROWS
+            .load(&deps.storage, "cosmos1escrowb".to_string())
+            .unwrap();
+        stored.escrow_data.extension = Binary::from(vec![1, 2, 3]);
+        SRC_ESCROWS
+            .save(&mut deps.storage, "cosmos1escrowb".to_string(), &stored)
+            .unwrap();
+
+        let withdrawal = |escrow_address: &str, secret: [u8; 32]| BatchWithdrawal {
+            escrow_address: escrow_address.to_string(),
+            secret: format!("0x{}", hex::encode(secret)),
+        };
+        let msg = ExecuteMsg::BatchWithdraw {
+            withdrawals: vec![
+                withdrawal("cosmos1escrowa", [1u8; 32]),
+                withdrawal("cosmos1escrowb", [2u8; 32]),
+            ],
+            mode: BatchMode::BestEffort,
+        };
+        let res = execute(deps.as_mut(), env, taker, msg).unwrap();
+        let results = cosmwasm_std::from_json::<BatchResponse>(res.data.unwrap())
+            .unwrap()
+            .results;
+        assert_eq!(results[0].error, None);
+        assert_eq!(
+            results[1].error,
+            Some(ContractError::InvalidExtension {}.to_string())
+        );
+
+        // The failed item left its escrow open and its secret unrevealed
+        let escrow = crate::state::load_escrow(&deps.storage, "cosmos1escrowb").unwrap();
+        assert_eq!(escrow.status, EscrowStatus::Active);
+        let hashlock = hashlock_of(&[2u8; 32]);
+        assert!(!crate::state::REVEALED_SECRETS.has(&deps.storage, hashlock));
+        let stats = crate::state::SIDE_STATS
+            .load(&deps.storage, EscrowSide::Src.as_str())
+            .unwrap();
+        assert_eq!(stats.withdrawn, 1);
+    }
+
+    #[test]
+    fn test_best_effort_item_failing_stats_update() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let taker = mock_info("taker_address", &[]);
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            instantiate_msg(),
+        )
+        .unwrap();
+        register_counterpart(deps.as_mut());
+
+        let timelocks = Timelocks::pack([0, 100, 1000, 2000, 0, 0, 0]);
+        for (escrow_address, hashlock) in
+            [("cosmos1escrowa", [1u8; 32]), ("cosmos1escrowb", [2; 32])]
+        {
+            let msg = create_src_escrow_msg(escrow_address, hashlock, timelocks);
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("resolver", &src_funds()),
+                msg,
+            )
+            .unwrap();
+        }
+
+        // Only the first escrow's funds are counted as locked, so releasing the second fails
+        crate::state::ASSET_STATS
+            .update(&mut deps.storage, "maker_asset", |stats| -> StdResult<_> {
+                let mut stats = stats.unwrap();
+                stats.locked = Uint128::new(1000);
+                Ok(stats)
+            })
+            .unwrap();
+
+        env.block.time = env.block.time.plus_seconds(1000);
+        let msg = ExecuteMsg::BatchCancel {
+            escrow_addresses: vec!["cosmos1escrowa".to_string(), "cosmos1escrowb".to_string()],
+            mode: BatchMode::BestEffort,
+        };
+        let res = execute(deps.as_mut(), env, taker, msg).unwrap();
+        let results = cosmwasm_std::from_json::<BatchResponse>(res.data.unwrap())
+            .unwrap()
+            .results;
+        assert_eq!(results[0].error, None);
+        assert!(results[1].error.is_some());
+        assert_eq!(res.messages.len(), 2);
+
+        // The failed item left its escrow open rather than closed with its funds unpaid
+        let escrow = crate::state::load_escrow(&deps.storage, "cosmos1escrowb").unwrap();
+        assert_eq!(escrow.status, EscrowStatus::Active);
+        let stats = crate::state::SIDE_STATS
+            .load(&deps.storage, EscrowSide::Src.as_str())
+            .unwrap();
+        assert_eq!(stats.cancelled, 1);
+    }
+
+    fn create_src_escrow_msg(
+        escrow_address: &str,
+        hashlock: [u8; 32],
+        timelocks: Uint256,
+    ) -> ExecuteMsg {
+        let order = Order {
+            salt: Uint128::from(1u128),
+            maker: cosmwasm_std::Addr::unchecked("maker_address"),
+            receiver: cosmwasm_std::Addr::unchecked("maker_address"),
+            maker_asset: cosmwasm_std::Addr::unchecked("maker_asset"),
+            taker_asset: cosmwasm_std::Addr::unchecked("taker_asset"),
+            making_amount: Uint128::from(1000u128),
+            taking_amount: Uint128::from(2000u128),
+        };
+        ExecuteMsg::CreateSrcEscrow {
+            escrow_address: escrow_address.to_string(),
+            order,
+            extension: Binary::default(),
+            order_hash: "0xorder1".to_string(),
+            taker: "taker_address".to_string(),
+            making_amount: Uint128::from(1000u128),
+            taking_amount: Uint128::from(2000u128),
+            remaining_making_amount: Uint128::from(1000u128),
+            extra_data: extra_data(hashlock, (100, 0), timelocks),
+        }
+    }
+
+    fn src_funds() -> Vec<cosmwasm_std::Coin> {
+        vec![coin(1000, "maker_asset"), coin(100, DEPOSIT_DENOM)]
+    }
+
+    fn bank_send(to: &str, amount: u128, denom: &str) -> CosmosMsg<NeutronMsg> {
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: to.to_string(),
+            amount: coins(amount, denom),
+        })
+    }
+
+    #[test]
+    fn test_create_escrow_requires_funds() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), info, instantiate_msg()).unwrap();
+
+        // Safety deposit is missing
+        let msg = ExecuteMsg::CreateDstEscrow {
+            escrow_address: "cosmos1escrowa".to_string(),
+            immutables: dst_immutables("maker_address", "0xorder1", "0xhash1"),
+            timestamp: Uint128::from(env.block.time.seconds() + 3600),
+        };
+        let info = mock_info("creator", &coins(1000, "token_address"));
+        let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFunds { .. }));
+
+        // Extra data must carry all five words
+        let mut msg = create_src_escrow_msg("cosmos1escrowb", [2u8; 32], Timelocks::pack([0; 7]));
+        if let ExecuteMsg::CreateSrcEscrow { extra_data, .. } = &mut msg {
+            *extra_data = Binary::from(vec![0u8; 32]);
+        }
+        let info = mock_info("creator", &src_funds());
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidExtraDataLength {
+                expected: 160,
+                actual: 32
+            }
+        ));
+    }
+
+    #[test]
+    fn test_src_escrow_fill_amounts() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &src_funds());
+        instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg()).unwrap();
+        register_counterpart(deps.as_mut());
+
+        // Fill of `making` out of `remaining` left, paying `taking`
+        let fill = |making: u128, taking: u128, remaining: u128| {
+            let mut msg =
+                create_src_escrow_msg("cosmos1escrowsrc", [1u8; 32], Timelocks::pack([0; 7]));
+            if let ExecuteMsg::CreateSrcEscrow {
+                making_amount,
+                taking_amount,
+                remaining_making_amount,
+                ..
+            } = &mut msg
+            {
+                *making_amount = Uint128::new(making);
+                *taking_amount = Uint128::new(taking);
+                *remaining_making_amount = Uint128::new(remaining);
+            }
+            msg
+        };
+
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), fill(0, 0, 1000)).unwrap_err();
+        assert!(matches!(err, ContractError::EmptyFill {}));
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            fill(1000, 2000, 1001),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::RemainingExceedsOrder { .. }));
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            fill(1000, 2000, 600),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::FillExceedsRemaining {
+                making_amount,
+                remaining_making_amount,
+            } if making_amount == Uint128::new(1000) && remaining_making_amount == Uint128::new(600)
+        ));
+
+        // 333 of 1000 at 2000 per 1000 rounds up to 666, as the EVM order does
+        let info = mock_info(
+            "creator",
+            &[coin(333, "maker_asset"), coin(100, DEPOSIT_DENOM)],
+        );
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            fill(333, 665, 1000),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::TakingAmountTooLow { minimum } if minimum == Uint128::new(666)
+        ));
+        execute(deps.as_mut(), env, info, fill(333, 666, 1000)).unwrap();
+    }
+
+    #[test]
+    fn test_dst_escrow_withdraw() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), info, instantiate_msg()).unwrap();
+
+        let secret = [7u8; 32];
+        let mut immutables = dst_immutables("maker_address", "0xorder1", &hashlock_of(&secret));
+        immutables.timelocks = Timelocks::pack([0, 0, 0, 0, 10, 100, 1000]);
+        let msg = ExecuteMsg::CreateDstEscrow {
+            escrow_address: "cosmos1escrowa".to_string(),
+            immutables,
+            timestamp: Uint128::from(env.block.time.seconds() + 3600),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("taker_address", &dst_funds()),
+            msg,
+        )
+        .unwrap();
+
+        let withdraw = ExecuteMsg::Withdraw {
+            escrow_address: "cosmos1escrowa".to_string(),
+            secret: format!("0x{}", hex::encode(secret)),
+        };
+        let taker = mock_info("taker_address", &[]);
+
+        // Still in the finality lock
+        let err = execute(deps.as_mut(), env.clone(), taker.clone(), withdraw.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTime {}));
+
+        env.block.time = env.block.time.plus_seconds(10);
+
+        // Only the taker may withdraw privately
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            withdraw.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // The secret has to match the hashlock
+        let wrong_secret = ExecuteMsg::Withdraw {
+            escrow_address: "cosmos1escrowa".to_string(),
+            secret: format!("0x{}", hex::encode([8u8; 32])),
+        };
+        let err = execute(deps.as_mut(), env.clone(), taker.clone(), wrong_secret).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSecret {}));
+
+        let res = execute(deps.as_mut(), env.clone(), taker.clone(), withdraw).unwrap();
+        let messages: Vec<_> = res.messages.into_iter().map(|sub| sub.msg).collect();
+        assert_eq!(
+            messages,
+            vec![
+                bank_send("maker_address", 1000, "token_address"),
+                bank_send("taker_address", 100, DEPOSIT_DENOM),
+            ]
+        );
+
+        let query_msg = QueryMsg::GetDstEscrow {
+            escrow_address: "cosmos1escrowa".to_string(),
+        };
+        let res: DstEscrowResponse =
+            cosmwasm_std::from_json(query(deps.as_ref(), env.clone(), query_msg).unwrap()).unwrap();
+        assert_eq!(res.status, Some(EscrowStatus::Withdrawn));
+
+        // The secret can be looked up by its hashlock, in any casing
+        let query_msg = QueryMsg::GetRevealedSecret {
+            hashlock: hashlock_of(&secret).to_uppercase(),
+        };
+        let res: RevealedSecretResponse =
+            cosmwasm_std::from_json(query(deps.as_ref(), env.clone(), query_msg).unwrap()).unwrap();
+        assert_eq!(
+            res.revealed,
+            Some(RevealedSecret {
+                secret: format!("0x{}", hex::encode(secret)),
+                escrow_address: "cosmos1escrowa".to_string(),
+                height: env.block.height,
+                time: env.block.time,
+            })
+        );
+
+        // A withdrawn escrow cannot be cancelled afterwards
+        env.block.time = env.block.time.plus_seconds(1000);
+        let cancel = ExecuteMsg::Cancel {
+            escrow_address: "cosmos1escrowa".to_string(),
+        };
+        let err = execute(deps.as_mut(), env, taker, cancel).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidStatusTransition {
+                from: EscrowStatus::Withdrawn,
+                to: EscrowStatus::Cancelled,
+            }
+        ));
+    }
+
+    #[test]
+    fn test_src_escrow_public_cancel() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), info, instantiate_msg()).unwrap();
+        register_counterpart(deps.as_mut());
+
+        let timelocks = Timelocks::pack([10, 100, 1000, 2000, 0, 0, 0]);
+        let msg = create_src_escrow_msg("cosmos1escrowsrc", [1u8; 32], timelocks);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("resolver", &src_funds()),
+            msg,
+        )
+        .unwrap();
+
+        let public_cancel = ExecuteMsg::PublicCancel {
+            escrow_address: "cosmos1escrowsrc".to_string(),
+        };
+        env.block.time = env.block.time.plus_seconds(1999);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            public_cancel.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTime {}));
+
+        // Anyone may cancel once the public cancellation period starts; the maker is refunded
+        env.block.time = env.block.time.plus_seconds(1);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            public_cancel,
+        )
+        .unwrap();
+        let messages: Vec<_> = res.messages.iter().map(|sub| sub.msg.clone()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                bank_send("maker_address", 1000, "maker_asset"),
+                bank_send("anyone", 100, DEPOSIT_DENOM),
+            ]
+        );
+
+        // The cancellation is followed by a public action event naming the caller
+        let events: Vec<EscrowEvent> = res
+            .events
+            .iter()
+            .map(|event| EscrowEvent::try_from(event).unwrap())
+            .collect();
+        assert_eq!(
+            events[1],
+            EscrowEvent::PublicAction {
+                escrow_address: "cosmos1escrowsrc".to_string(),
+                side: EscrowSide::Src,
+                action: EscrowAction::PublicCancel,
+                caller: Addr::unchecked("anyone"),
+                safety_deposit: Uint128::new(100),
+            }
+        );
+
+        let query_msg = QueryMsg::GetSrcEscrow {
+            escrow_address: "cosmos1escrowsrc".to_string(),
+        };
+        let res: SrcEscrowResponse =
+            cosmwasm_std::from_json(query(deps.as_ref(), env, query_msg).unwrap()).unwrap();
+        assert_eq!(res.status, Some(EscrowStatus::Cancelled));
+    }
+
+    #[test]
+    fn test_rescue_and_status_filter() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), info, instantiate_msg()).unwrap();
+
+        for (escrow_address, hashlock) in
+            [("cosmos1escrowa", "0xhash1"), ("cosmos1escrowb", "0xhash2")]
+        {
+            let msg = ExecuteMsg::CreateDstEscrow {
+                escrow_address: escrow_address.to_string(),
+                immutables: dst_immutables("maker_address", "0xorder1", hashlock),
+                timestamp: Uint128::from(env.block.time.seconds() + 3600),
+            };
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("taker_address", &dst_funds()),
+                msg,
+            )
+            .unwrap();
+        }
+
+        let rescue = ExecuteMsg::Rescue {
+            escrow_address: "cosmos1escrowa".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("taker_address", &[]),
+            rescue.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTime {}));
+
+        env.block.time = env.block.time.plus_seconds(RESCUE_DELAY);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("taker_address", &[]),
+            rescue,
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 2);
+
+        let query_msg = QueryMsg::ListDstEscrows {
+            start_after: None,
+            end_before: None,
+            limit: None,
+            order_by: None,
+            status: Some(EscrowStatus::Rescued),
+            token: None,
+            maker: None,
+        };
+        let res: DstEscrowListResponse =
+            cosmwasm_std::from_json(query(deps.as_ref(), env.clone(), query_msg).unwrap()).unwrap();
+        assert_eq!(res.escrows.len(), 1);
+        assert_eq!(res.escrows[0].0, "cosmos1escrowa");
+
+        let query_msg = QueryMsg::ListDstEscrows {
+            start_after: None,
+            end_before: None,
+            limit: None,
+            order_by: None,
+            status: Some(EscrowStatus::Active),
+            token: None,
+            maker: None,
+        };
+        let res: DstEscrowListResponse =
+            cosmwasm_std::from_json(query(deps.as_ref(), env, query_msg).unwrap()).unwrap();
+        assert_eq!(res.escrows.len(), 1);
+        assert_eq!(res.escrows[0].0, "cosmos1escrowb");
+    }
+
+    #[test]
+    fn test_rescue_delay_outlasts_cancellation() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        // No escrow could ever open rescue after its cancellation with a zero delay
+        let msg = InstantiateMsg {
+            rescue_delay: 0,
+            ..instantiate_msg()
+        };
+        let err =
+            instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::RescueDelayTooShort { .. }));
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            instantiate_msg(),
+        )
+        .unwrap();
+
+        // Escrows whose cancellation starts at or after the rescue delay are refused
+        let create = |escrow_address: &str, hashlock: &str, dst_cancellation: u32| {
+            let mut immutables = dst_immutables("maker_address", "0xorder1", hashlock);
+            immutables.timelocks = Timelocks::pack([0, 0, 0, 0, 10, 20, dst_cancellation]);
+            ExecuteMsg::CreateDstEscrow {
+                escrow_address: escrow_address.to_string(),
+                immutables,
+                timestamp: Uint128::from(env.block.time.seconds() + 3600),
+            }
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("taker_address", &dst_funds()),
+            create("cosmos1escrowa", "0xhash1", RESCUE_DELAY as u32),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::RescueDelayTooShort {
+                rescue_delay: RESCUE_DELAY,
+                cancellation: RESCUE_DELAY,
+            }
+        ));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("taker_address", &dst_funds()),
+            create("cosmos1escrowa", "0xhash1", 3600),
+        )
+        .unwrap();
+
+        // A migration cannot shorten the delay below the cancellation of an escrow created so far
+        let migrate_msg = |rescue_delay| MigrateMsg {
+            owner: None,
+            safety_deposit_denom: None,
+            rescue_delay: Some(rescue_delay),
+        };
+        let err = migrate(deps.as_mut(), env.clone(), migrate_msg(3600)).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::RescueDelayTooShort {
+                rescue_delay: 3600,
+                cancellation: 3600,
+            }
+        ));
+        migrate(deps.as_mut(), env, migrate_msg(3601)).unwrap();
+        assert_eq!(
+            CONFIG.load(deps.as_ref().storage).unwrap().rescue_delay,
+            3601
+        );
+    }
+
+    #[test]
+    fn test_escrow_stage_query() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), info, instantiate_msg()).unwrap();
+        register_counterpart(deps.as_mut());
+
+        let deployed_at = env.block.time;
+        let timelocks = Timelocks::pack([10, 100, 1000, 2000, 0, 0, 0]);
+        let msg = create_src_escrow_msg("cosmos1escrowsrc", [1u8; 32], timelocks);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("resolver", &src_funds()),
+            msg,
+        )
+        .unwrap();
+
+        let stage_at = |deps: Deps, env: &Env| -> EscrowStageResponse {
+            let query_msg = QueryMsg::GetEscrowStage {
+                escrow_address: "cosmos1escrowsrc".to_string(),
+            };
+            cosmwasm_std::from_json(query(deps, env.clone(), query_msg).unwrap()).unwrap()
+        };
+
+        let res = stage_at(deps.as_ref(), &env);
+        assert_eq!(res.side, EscrowSide::Src);
+        assert_eq!(res.stage, EscrowStage::FinalityLock);
+        assert!(res.actions.is_empty());
+        assert_eq!(res.next_transition, Some(deployed_at.plus_seconds(10)));
+
+        // Exactly at the public withdrawal boundary both withdrawals are callable
+        env.block.time = deployed_at.plus_seconds(100);
+        let res = stage_at(deps.as_ref(), &env);
+        assert_eq!(res.stage, EscrowStage::PublicWithdrawal);
+        assert_eq!(
+            res.actions,
+            vec![
+                AvailableAction {
+                    action: EscrowAction::Withdraw,
+                    caller: ActionCaller::Taker,
+                },
+                AvailableAction {
+                    action: EscrowAction::PublicWithdraw,
+                    caller: ActionCaller::Anyone,
+                },
+            ]
+        );
+        assert_eq!(res.next_transition, Some(deployed_at.plus_seconds(1000)));
+
+        // Past the rescue delay the taker may also rescue
+        env.block.time = deployed_at.plus_seconds(RESCUE_DELAY);
+        let res = stage_at(deps.as_ref(), &env);
+        assert_eq!(res.stage, EscrowStage::PublicCancellation);
+        assert_eq!(res.next_transition, None);
+        let actions: Vec<_> = res
+            .actions
+            .iter()
+            .map(|available| available.action)
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                EscrowAction::Cancel,
+                EscrowAction::PublicCancel,
+                EscrowAction::Rescue
+            ]
+        );
+
+        // Closed escrows have nothing left to do
+        let cancel = ExecuteMsg::Cancel {
+            escrow_address: "cosmos1escrowsrc".to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("taker_address", &[]),
+            cancel,
+        )
+        .unwrap();
+        let res = stage_at(deps.as_ref(), &env);
+        assert_eq!(res.status, EscrowStatus::Cancelled);
+        assert_eq!(res.stage, EscrowStage::Closed);
+        assert!(res.actions.is_empty());
+    }
+    /// Entry points of v0.1, which stored bare records and recorded no contract version
+    fn legacy_instantiate(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: cosmwasm_std::Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::default())
+    }
+
+    fn legacy_execute(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: ExecuteMsg,
+    ) -> StdResult<Response> {
+        match msg {
+            ExecuteMsg::CreateDstEscrow {
+                escrow_address,
+                immutables,
+                ..
+            } => migrations::v0_1::DST_ESCROWS.save(deps.storage, escrow_address, &immutables)?,
+            ExecuteMsg::CreateSrcEscrow {
+                escrow_address,
+                order,
+                extension,
+                order_hash,
+                taker,
+                making_amount,
+                taking_amount,
+                remaining_making_amount,
+                extra_data,
+            } => {
+                let escrow_data = migrations::v0_1::SrcEscrowData {
+                    order,
+                    extension,
+                    order_hash,
+                    taker: Addr::unchecked(taker),
+                    making_amount,
+                    taking_amount,
+                    remaining_making_amount,
+                    extra_data,
+                };
+                migrations::v0_1::SRC_ESCROWS.save(deps.storage, escrow_address, &escrow_data)?
+            }
+            _ => return Err(cosmwasm_std::StdError::generic_err("not supported in v0.1")),
+        }
+        Ok(Response::default())
+    }
+
+    fn legacy_query(_deps: Deps, _env: Env, _msg: cosmwasm_std::Empty) -> StdResult<Binary> {
+        Err(cosmwasm_std::StdError::generic_err("not supported in v0.1"))
+    }
+
+    #[test]
+    fn test_migrate_populated_v0_1_contract() {
+        let mut app =
+            BasicAppBuilder::<NeutronMsg, cosmwasm_std::Empty>::new_custom().build(|_, _, _| {});
+        let legacy_code_id = app.store_code(Box::new(ContractWrapper::new_with_empty(
+            legacy_execute,
+            legacy_instantiate,
+            legacy_query,
+        )));
+        let code_id = app.store_code(Box::new(
+            ContractWrapper::new(execute, instantiate, query).with_migrate(migrate),
+        ));
+
+        let admin = Addr::unchecked("admin");
+        let contract = app
+            .instantiate_contract(
+                legacy_code_id,
+                admin.clone(),
+                &cosmwasm_std::Empty {},
+                &[],
+                "escrow factory v0.1",
+                Some(admin.to_string()),
+            )
+            .unwrap();
+
+        // Populate v0.1 state with one escrow on each side
+        let secret = [7u8; 32];
+        let msg = ExecuteMsg::CreateDstEscrow {
+            escrow_address: "cosmos1escrowdst".to_string(),
+            immutables: dst_immutables("maker_address", "0xorder1", &hashlock_of(&secret)),
+            timestamp: Uint128::zero(),
+        };
+        app.execute_contract(admin.clone(), contract.clone(), &msg, &[])
+            .unwrap();
+        let msg = create_src_escrow_msg("cosmos1escrowsrc", [1u8; 32], Uint256::zero());
+        app.execute_contract(admin.clone(), contract.clone(), &msg, &[])
+            .unwrap();
+
+        // v0.1 had no config, so migrating from it needs the whole of it
+        let err = app
+            .migrate_contract(
+                admin.clone(),
+                contract.clone(),
+                &MigrateMsg {
+                    owner: None,
+                    safety_deposit_denom: None,
+                    rescue_delay: None,
+                },
+                code_id,
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::MissingMigrationField { .. }
+        ));
+
+        let migrate_msg = MigrateMsg {
+            owner: Some(admin.to_string()),
+            safety_deposit_denom: Some(DEPOSIT_DENOM.to_string()),
+            rescue_delay: Some(RESCUE_DELAY),
+        };
+        app.migrate_contract(admin.clone(), contract.clone(), &migrate_msg, code_id)
+            .unwrap();
+
+        let version = cw2::query_contract_info(&app.wrap(), contract.to_string()).unwrap();
+        assert_eq!(version.contract, CONTRACT_NAME);
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        // Records were re-saved with a status and are reachable through the indexes
+        let res: DstEscrowResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract.clone(),
+                &QueryMsg::GetDstEscrow {
+                    escrow_address: "cosmos1escrowdst".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.status, Some(EscrowStatus::Active));
+        let res: HashlockEscrowResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract.clone(),
+                &QueryMsg::EscrowByHashlock {
+                    hashlock: hashlock_of(&secret),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.escrow.unwrap().0, "cosmos1escrowdst");
+        // Escrow counts are seeded from the migrated records
+        let res: DstEscrowListResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract.clone(),
+                &QueryMsg::ListDstEscrows {
+                    start_after: None,
+                    end_before: None,
+                    limit: None,
+                    order_by: None,
+                    status: None,
+                    token: None,
+                    maker: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(res.total, 1);
+        let res: EscrowsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract.clone(),
+                &QueryMsg::EscrowsByMaker {
+                    side: EscrowSide::Src,
+                    maker: "maker_address".to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(res.escrows.len(), 1);
+
+        // v0.1 never held the escrowed funds, so a migrated escrow cannot be settled
+        let err = app
+            .execute_contract(
+                Addr::unchecked("taker_address"),
+                contract.clone(),
+                &ExecuteMsg::Cancel {
+                    escrow_address: "cosmos1escrowdst".to_string(),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::EscrowNotFunded {}
+        ));
+        let res: DstEscrowResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract,
+                &QueryMsg::GetDstEscrow {
+                    escrow_address: "cosmos1escrowdst".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.status, Some(EscrowStatus::Active));
+    }
+
+    #[test]
+    fn test_migrate_rejects_downgrade_and_foreign_contract() {
+        let mut deps = mock_dependencies();
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            instantiate_msg(),
+        )
+        .unwrap();
+        let msg = MigrateMsg {
+            owner: None,
+            safety_deposit_denom: None,
+            rescue_delay: Some(3600),
+        };
+
+        // Same version: config fields given are applied, the rest kept
+        migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+        let config = CONFIG.load(deps.as_ref().storage).unwrap();
+        assert_eq!(config.rescue_delay, 3600);
+        assert_eq!(config.safety_deposit_denom, DEPOSIT_DENOM);
+
+        cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "9.0.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::MigrationDowngrade { .. }));
+
+        cw2::set_contract_version(deps.as_mut().storage, "crates.io:other", "0.1.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidContractName { .. }));
+    }
+
+    #[test]
+    fn test_migrate_v0_1_sets_aside_unmigratable_escrows() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let timelocks = Timelocks::pack([10, 20, 30, 40, 0, 0, 0]);
+        let hashlock = |byte: u8| format!("0x{}", hex::encode([byte; 32]));
+        let escrow_data = |extra_data: Binary| migrations::v0_1::SrcEscrowData {
+            order: Order {
+                salt: Uint128::zero(),
+                maker: Addr::unchecked("maker_address"),
+                receiver: Addr::unchecked("maker_address"),
+                maker_asset: Addr::unchecked("maker_asset"),
+                taker_asset: Addr::unchecked("taker_asset"),
+                making_amount: Uint128::new(1000),
+                taking_amount: Uint128::new(2000),
+            },
+            extension: Binary::default(),
+            order_hash: "0xorder1".to_string(),
+            taker: Addr::unchecked("taker_address"),
+            making_amount: Uint128::new(1000),
+            taking_amount: Uint128::new(2000),
+            remaining_making_amount: Uint128::new(1000),
+            extra_data,
+        };
+
+        // v0.1 neither kept hashlocks unique nor checked the layout of the extra data
+        let dst = dst_immutables("maker_address", "0xorder1", &hashlock(1));
+        for escrow_address in ["cosmos1dsta", "cosmos1dstb"] {
+            migrations::v0_1::DST_ESCROWS
+                .save(deps.as_mut().storage, escrow_address.to_string(), &dst)
+                .unwrap();
+        }
+        let malformed = escrow_data(Binary::from(vec![1, 2, 3]));
+        for (escrow_address, escrow_data) in [
+            ("cosmos1srca", malformed.clone()),
+            (
+                "cosmos1srcb",
+                escrow_data(extra_data([1u8; 32], (100, 0), timelocks)),
+            ),
+            (
+                "cosmos1srcc",
+                escrow_data(extra_data([2u8; 32], (100, 0), timelocks)),
+            ),
+            (
+                "cosmos1srcd",
+                escrow_data(extra_data([2u8; 32], (100, 0), timelocks)),
+            ),
+        ] {
+            migrations::v0_1::SRC_ESCROWS
+                .save(
+                    deps.as_mut().storage,
+                    escrow_address.to_string(),
+                    &escrow_data,
+                )
+                .unwrap();
+        }
+
+        let msg = MigrateMsg {
+            owner: Some("creator".to_string()),
+            safety_deposit_denom: Some(DEPOSIT_DENOM.to_string()),
+            rescue_delay: Some(RESCUE_DELAY),
+        };
+        let res = migrate(deps.as_mut(), env, msg).unwrap();
+        assert!(res
+            .attributes
+            .contains(&cosmwasm_std::attr("unmigrated_escrows", "3")));
+
+        // The first holder of each hashlock on each side is migrated, so the source leg of
+        // a swap whose destination leg is on this chain keeps its shared hashlock
+        assert!(DST_ESCROWS.has(&deps.storage, "cosmos1dsta".to_string()));
+        assert!(SRC_ESCROWS.has(&deps.storage, "cosmos1srcb".to_string()));
+        assert!(SRC_ESCROWS.has(&deps.storage, "cosmos1srcc".to_string()));
+        for escrow_address in ["cosmos1dstb", "cosmos1srca", "cosmos1srcd"] {
+            assert!(crate::state::load_escrow(&deps.storage, escrow_address).is_err());
+        }
+
+        // The rest are kept as they were stored, with the reason they were set aside
+        let unmigrated = |side: EscrowSide, escrow_address: &str| {
+            UNMIGRATED_ESCROWS
+                .load(&deps.storage, (side.as_str(), escrow_address.to_string()))
+                .unwrap()
+        };
+        let escrow = unmigrated(EscrowSide::Dst, "cosmos1dstb");
+        assert_eq!(escrow.reason, UnmigratedReason::DuplicateHashlock);
+        assert_eq!(escrow.record, to_json_binary(&dst).unwrap());
+        let escrow = unmigrated(EscrowSide::Src, "cosmos1srca");
+        assert_eq!(escrow.reason, UnmigratedReason::UndecodableExtraData);
+        assert_eq!(escrow.record, to_json_binary(&malformed).unwrap());
+        let escrow = unmigrated(EscrowSide::Src, "cosmos1srcd");
+        assert_eq!(escrow.reason, UnmigratedReason::DuplicateHashlock);
+    }
+
+    /// Extension with no order fields and `custom_data` after the offsets word
+    fn extension_with_custom_data(custom_data: &[u8]) -> Binary {
+        let mut extension = vec![0u8; 32];
+        extension.extend_from_slice(custom_data);
+        Binary::from(extension)
+    }
+
+    #[test]
+    fn test_src_withdraw_charges_fees() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            instantiate_msg(),
+        )
+        .unwrap();
+        register_counterpart(deps.as_mut());
+
+        let update = |fee_bps: u16| ExecuteMsg::UpdateFeeConfig {
+            fee_bps,
+            integrator_share_bps: 5000,
+            fee_collector: "fee_collector".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            update(100),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            update(10_001),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFee {}));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            update(crate::execute::MAX_FEE_BPS + 1),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFee {}));
+
+        // 1% fee, half of it to the integrator
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            update(100),
+        )
+        .unwrap();
+        let res: FeeConfigResponse = cosmwasm_std::from_json(
+            query(deps.as_ref(), env.clone(), QueryMsg::GetFeeConfig {}).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.fee_config,
+            Some(FeeConfig {
+                fee_bps: 100,
+                integrator_share_bps: 5000,
+                fee_collector: Addr::unchecked("fee_collector"),
+            })
+        );
+
+        // The integrator must be a valid address
+        let secret = [3u8; 32];
+        let timelocks = Timelocks::pack([0, 100, 1000, 2000, 0, 0, 0]);
+        let mut msg = create_src_escrow_msg("cosmos1escrowsrc", keccak(&secret), timelocks);
+        if let ExecuteMsg::CreateSrcEscrow { extension, .. } = &mut msg {
+            *extension = extension_with_custom_data(b"NOT AN ADDRESS");
+        }
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("resolver", &src_funds()),
+            msg.clone(),
+        )
+        .unwrap_err();
+
+        if let ExecuteMsg::CreateSrcEscrow { extension, .. } = &mut msg {
+            *extension = extension_with_custom_data(b"integrator_address");
+        }
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("resolver", &src_funds()),
+            msg,
+        )
+        .unwrap();
+
+        // Raising the fee later does not change the terms the escrow was created under
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            update(crate::execute::MAX_FEE_BPS),
+        )
+        .unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("taker_address", &[]),
+            ExecuteMsg::Withdraw {
+                escrow_address: "cosmos1escrowsrc".to_string(),
+                secret: format!("0x{}", hex::encode(secret)),
+            },
+        )
+        .unwrap();
+        let messages: Vec<_> = res.messages.iter().map(|sub| sub.msg.clone()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                bank_send("taker_address", 990, "maker_asset"),
+                bank_send("fee_collector", 5, "maker_asset"),
+                bank_send("integrator_address", 5, "maker_asset"),
+                bank_send("taker_address", 100, DEPOSIT_DENOM),
+            ]
+        );
+        let fees = res
+            .events
+            .iter()
+            .map(|event| EscrowEvent::try_from(event).unwrap())
+            .find(|event| matches!(event, EscrowEvent::FeesCharged { .. }));
+        assert_eq!(
+            fees,
+            Some(EscrowEvent::FeesCharged {
+                escrow_address: "cosmos1escrowsrc".to_string(),
+                token: Addr::unchecked("maker_asset"),
+                protocol_fee: Uint128::new(5),
+                fee_collector: Addr::unchecked("fee_collector"),
+                integrator_fee: Uint128::new(5),
+                integrator: Some(Addr::unchecked("integrator_address")),
+            })
+        );
+    }
+    /// Extension whose taking amount data prices the order with a linear Dutch auction
+    fn auction_extension(start_time: u64, duration: u32, initial_rate_bump: u32) -> Binary {
+        let mut data = vec![0u8; 20 + 7];
+        data.extend_from_slice(&(start_time as u32).to_be_bytes());
+        data.extend_from_slice(&duration.to_be_bytes()[1..]);
+        data.extend_from_slice(&initial_rate_bump.to_be_bytes()[1..]);
+
+        let mut extension = vec![0u8; 32];
+        for index in 3..8 {
+            extension[28 - 4 * index..32 - 4 * index]
+                .copy_from_slice(&(data.len() as u32).to_be_bytes());
+        }
+        extension.extend_from_slice(&data);
+        Binary::from(extension)
+    }
+
+    fn msg_extension(msg: &ExecuteMsg) -> Binary {
+        match msg {
+            ExecuteMsg::CreateSrcEscrow { extension, .. } => extension.clone(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_src_escrow_auction_price() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            instantiate_msg(),
+        )
+        .unwrap();
+        register_counterpart(deps.as_mut());
+
+        // +10% at the start, decaying to nothing over 1000s
+        let extension = auction_extension(env.block.time.seconds(), 1000, 1_000_000);
+        let mut msg = create_src_escrow_msg("cosmos1escrowsrc", [1u8; 32], Uint256::zero());
+        if let ExecuteMsg::CreateSrcEscrow {
+            extension: msg_extension,
+            ..
+        } = &mut msg
+        {
+            *msg_extension = extension.clone();
+        }
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("resolver", &src_funds()),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::TakingAmountTooLow { minimum } if minimum == Uint128::new(2200)
+        ));
+
+        // Halfway through the auction the bump is down to 5%
+        env.block.time = env.block.time.plus_seconds(500);
+        let res: AuctionQuoteResponse = cosmwasm_std::from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::QuoteAuctionRate {
+                    order: match &msg {
+                        ExecuteMsg::CreateSrcEscrow { order, .. } => order.clone(),
+                        _ => unreachable!(),
+                    },
+                    extension,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.rate_bump, 500_000);
+        assert_eq!(res.taking_amount, Uint128::new(2100));
+
+        // The auction sits in the taking amount data, behind the 20-byte getter address
+        let res: ExtensionResponse = cosmwasm_std::from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::DecodeExtension {
+                    extension: msg_extension(&msg),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.extension.taking_amount_data.len(), 20 + 17);
+        assert!(res.extension.custom_data.is_empty());
+
+        if let ExecuteMsg::CreateSrcEscrow { taking_amount, .. } = &mut msg {
+            *taking_amount = Uint128::new(2100);
+        }
+        execute(deps.as_mut(), env, mock_info("resolver", &src_funds()), msg).unwrap();
+    }
+}
@@ -1,6 +1,10 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
 
+use crate::execute::MAX_FEE_BPS;
+use crate::state::EscrowStatus;
+use crate::timelocks::Stage;
+
 #[derive(Error, Debug)]
 pub enum ContractError {
     #[error("{0}")]
@@ -12,9 +16,156 @@ pub enum ContractError {
     #[error("Escrow already exists for this address")]
     EscrowAlreadyExists {},
 
+    #[error("Escrow already exists for this hashlock")]
+    HashlockAlreadyExists {},
+
     #[error("Invalid timestamp")]
     InvalidTimestamp {},
 
     #[error("Escrow not found")]
     EscrowNotFound {},
-}
\ No newline at end of file
+
+    #[error("Invalid status transition from {from:?} to {to:?}")]
+    InvalidStatusTransition {
+        from: EscrowStatus,
+        to: EscrowStatus,
+    },
+
+    #[error("Escrow is {status:?}, not active")]
+    NotActive { status: EscrowStatus },
+
+    #[error("Action is not allowed at this time")]
+    InvalidTime {},
+
+    #[error("Secret does not match the hashlock")]
+    InvalidSecret {},
+
+    #[error("Extra data must be {expected} bytes, got {actual}")]
+    InvalidExtraDataLength { expected: usize, actual: usize },
+
+    #[error("Extra data carries an empty hashlock")]
+    EmptyHashlock {},
+
+    #[error("Timelocks start {later:?} before {earlier:?}")]
+    InvalidTimelocks { earlier: Stage, later: Stage },
+
+    #[error("Rescue delay of {rescue_delay}s must exceed the {cancellation}s cancellation offset")]
+    RescueDelayTooShort {
+        rescue_delay: u64,
+        cancellation: u64,
+    },
+
+    #[error("Invalid extension")]
+    InvalidExtension {},
+
+    #[error("Taking amount is below the {minimum} the order's rate asks for this fill")]
+    TakingAmountTooLow { minimum: Uint128 },
+
+    #[error("Making amount of a fill must not be zero")]
+    EmptyFill {},
+
+    #[error("Fill of {making_amount} exceeds the {remaining_making_amount} left of the order")]
+    FillExceedsRemaining {
+        making_amount: Uint128,
+        remaining_making_amount: Uint128,
+    },
+
+    #[error(
+        "Remaining amount {remaining_making_amount} exceeds the order's {order_making_amount}"
+    )]
+    RemainingExceedsOrder {
+        remaining_making_amount: Uint128,
+        order_making_amount: Uint128,
+    },
+
+    #[error("Chain {chain_id} is not supported")]
+    UnsupportedChain { chain_id: String },
+
+    #[error("Token {token} is not supported for {dst_token} on chain {chain_id}")]
+    UnsupportedToken {
+        chain_id: String,
+        token: String,
+        dst_token: String,
+    },
+
+    #[error("Invalid counterpart chain address {address}")]
+    InvalidCounterpartAddress { address: String },
+
+    #[error(
+        "Invalid fee: at most {} basis points, with an integrator share of at most 10000",
+        MAX_FEE_BPS
+    )]
+    InvalidFee {},
+
+    #[error("Sweep batch size must be positive")]
+    InvalidSweepConfig {},
+
+    #[error("Invalid funds: expected {expected}")]
+    InvalidFunds { expected: String },
+
+    #[error("Action is not supported for this escrow")]
+    UnsupportedAction {},
+
+    #[error("Escrow channels must be unordered")]
+    InvalidIbcOrder {},
+
+    #[error("Unsupported IBC channel version {version}")]
+    InvalidIbcVersion { version: String },
+
+    #[error("Counterparty port {port_id} is not a wasm contract port")]
+    InvalidCounterpartyPort { port_id: String },
+
+    #[error("No registered counterpart chain has port {port_id} over {connection_id}")]
+    UnregisteredCounterparty {
+        connection_id: String,
+        port_id: String,
+    },
+
+    #[error("Channel {channel_id} is not connected")]
+    UnknownChannel { channel_id: String },
+
+    #[error("Immutables do not match the source escrow")]
+    ImmutablesMismatch {},
+
+    #[error("Escrow is already funded")]
+    EscrowAlreadyFunded {},
+
+    #[error("Escrow has not been funded")]
+    EscrowNotFunded {},
+
+    #[error("Escrow can no longer be funded, its withdrawal stage has started")]
+    FundingDeadlinePassed {},
+
+    #[error("Chain {chain_id} has no escrow factory that can be queried")]
+    CounterpartNotQueryable { chain_id: String },
+
+    #[error("Escrow's counterpart is not on chain {chain_id}")]
+    CounterpartChainMismatch { chain_id: String },
+
+    #[error("Escrow's counterpart is already verified")]
+    CounterpartAlreadyVerified {},
+
+    #[error("Unknown interchain query {query_id}")]
+    UnknownInterchainQuery { query_id: u64 },
+
+    #[error("Batch has no items")]
+    EmptyBatch {},
+
+    #[error("Sender is not the ibc-hooks intermediary for this channel and sender")]
+    InvalidHookSender {},
+
+    #[error("Hook transfers can only create escrows")]
+    UnsupportedHookMsg {},
+
+    #[error("No funds to claim")]
+    NothingToClaim {},
+
+    #[error("Cannot migrate from contract {name}")]
+    InvalidContractName { name: String },
+
+    #[error("Cannot migrate from version {from} to older version {to}")]
+    MigrationDowngrade { from: String, to: String },
+
+    #[error("Migration requires {field}")]
+    MissingMigrationField { field: String },
+}
@@ -0,0 +1,428 @@
+use std::str::FromStr;
+
+use cosmwasm_std::{Addr, Event, StdError, StdResult, Uint128, Uint256};
+
+use crate::state::{EscrowSide, Immutables};
+use crate::timelocks::EscrowAction;
+
+// Event types emitted by the contract
+pub const EVENT_TYPE_DST_ESCROW_CREATED: &str = "dst_escrow_created";
+pub const EVENT_TYPE_SRC_ESCROW_CREATED: &str = "src_escrow_created";
+pub const EVENT_TYPE_DST_ESCROW_FUNDED: &str = "dst_escrow_funded";
+pub const EVENT_TYPE_ESCROW_WITHDRAWN: &str = "escrow_withdrawn";
+pub const EVENT_TYPE_ESCROW_CANCELLED: &str = "escrow_cancelled";
+pub const EVENT_TYPE_ESCROW_RESCUED: &str = "escrow_rescued";
+pub const EVENT_TYPE_ESCROW_PUBLIC_ACTION: &str = "escrow_public_action";
+pub const EVENT_TYPE_ESCROW_FEES_CHARGED: &str = "escrow_fees_charged";
+
+/// Lifecycle events emitted by the contract.
+///
+/// Each variant converts into a `cosmwasm_std::Event` and can be parsed back from one, so
+/// indexers can decode the contract's events without repeating attribute names.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EscrowEvent {
+    DstEscrowCreated {
+        escrow_address: String,
+        immutables: Immutables,
+        timestamp: Uint128,
+        creator: Addr,
+    },
+    SrcEscrowCreated {
+        escrow_address: String,
+        order_hash: String,
+        maker: Addr,
+        taker: Addr,
+        maker_asset: Addr,
+        taker_asset: Addr,
+        making_amount: Uint128,
+        taking_amount: Uint128,
+        remaining_making_amount: Uint128,
+        creator: Addr,
+    },
+    // Emitted when the taker locks the funds of a destination escrow opened over IBC
+    DstEscrowFunded {
+        escrow_address: String,
+        funder: Addr,
+        amount: Uint128,
+        safety_deposit: Uint128,
+    },
+    Withdrawn {
+        escrow_address: String,
+        side: EscrowSide,
+        secret: String,
+        recipient: Addr,
+        amount: Uint128,
+        caller: Addr,
+    },
+    Cancelled {
+        escrow_address: String,
+        side: EscrowSide,
+        recipient: Addr,
+        amount: Uint128,
+        caller: Addr,
+    },
+    Rescued {
+        escrow_address: String,
+        side: EscrowSide,
+        recipient: Addr,
+        amount: Uint128,
+    },
+    // Emitted alongside `Withdrawn`/`Cancelled` when a public caller settled the escrow
+    // and collected its safety deposit
+    PublicAction {
+        escrow_address: String,
+        side: EscrowSide,
+        action: EscrowAction,
+        caller: Addr,
+        safety_deposit: Uint128,
+    },
+    // Emitted alongside `Withdrawn` when fees were deducted from the escrowed amount
+    FeesCharged {
+        escrow_address: String,
+        token: Addr,
+        protocol_fee: Uint128,
+        fee_collector: Addr,
+        integrator_fee: Uint128,
+        integrator: Option<Addr>,
+    },
+}
+
+impl EscrowEvent {
+    /// Type of the `Event` this converts into
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            EscrowEvent::DstEscrowCreated { .. } => EVENT_TYPE_DST_ESCROW_CREATED,
+            EscrowEvent::SrcEscrowCreated { .. } => EVENT_TYPE_SRC_ESCROW_CREATED,
+            EscrowEvent::DstEscrowFunded { .. } => EVENT_TYPE_DST_ESCROW_FUNDED,
+            EscrowEvent::Withdrawn { .. } => EVENT_TYPE_ESCROW_WITHDRAWN,
+            EscrowEvent::Cancelled { .. } => EVENT_TYPE_ESCROW_CANCELLED,
+            EscrowEvent::Rescued { .. } => EVENT_TYPE_ESCROW_RESCUED,
+            EscrowEvent::PublicAction { .. } => EVENT_TYPE_ESCROW_PUBLIC_ACTION,
+            EscrowEvent::FeesCharged { .. } => EVENT_TYPE_ESCROW_FEES_CHARGED,
+        }
+    }
+}
+
+impl From<EscrowEvent> for Event {
+    fn from(escrow_event: EscrowEvent) -> Self {
+        let event = Event::new(escrow_event.event_type());
+        match escrow_event {
+            EscrowEvent::DstEscrowCreated {
+                escrow_address,
+                immutables,
+                timestamp,
+                creator,
+            } => event
+                .add_attribute("escrow_address", escrow_address)
+                .add_attribute("order_hash", immutables.order_hash)
+                .add_attribute("hashlock", immutables.hashlock)
+                .add_attribute("maker", immutables.maker)
+                .add_attribute("taker", immutables.taker)
+                .add_attribute("token", immutables.token)
+                .add_attribute("amount", immutables.amount.to_string())
+                .add_attribute("safety_deposit", immutables.safety_deposit.to_string())
+                .add_attribute("timelocks", immutables.timelocks.to_string())
+                .add_attribute("timestamp", timestamp.to_string())
+                .add_attribute("creator", creator),
+            EscrowEvent::SrcEscrowCreated {
+                escrow_address,
+                order_hash,
+                maker,
+                taker,
+                maker_asset,
+                taker_asset,
+                making_amount,
+                taking_amount,
+                remaining_making_amount,
+                creator,
+            } => event
+                .add_attribute("escrow_address", escrow_address)
+                .add_attribute("order_hash", order_hash)
+                .add_attribute("maker", maker)
+                .add_attribute("taker", taker)
+                .add_attribute("maker_asset", maker_asset)
+                .add_attribute("taker_asset", taker_asset)
+                .add_attribute("making_amount", making_amount.to_string())
+                .add_attribute("taking_amount", taking_amount.to_string())
+                .add_attribute(
+                    "remaining_making_amount",
+                    remaining_making_amount.to_string(),
+                )
+                .add_attribute("creator", creator),
+            EscrowEvent::DstEscrowFunded {
+                escrow_address,
+                funder,
+                amount,
+                safety_deposit,
+            } => event
+                .add_attribute("escrow_address", escrow_address)
+                .add_attribute("funder", funder)
+                .add_attribute("amount", amount.to_string())
+                .add_attribute("safety_deposit", safety_deposit.to_string()),
+            EscrowEvent::Withdrawn {
+                escrow_address,
+                side,
+                secret,
+                recipient,
+                amount,
+                caller,
+            } => event
+                .add_attribute("escrow_address", escrow_address)
+                .add_attribute("side", side.as_str())
+                .add_attribute("secret", secret)
+                .add_attribute("recipient", recipient)
+                .add_attribute("amount", amount.to_string())
+                .add_attribute("caller", caller),
+            EscrowEvent::Cancelled {
+                escrow_address,
+                side,
+                recipient,
+                amount,
+                caller,
+            } => event
+                .add_attribute("escrow_address", escrow_address)
+                .add_attribute("side", side.as_str())
+                .add_attribute("recipient", recipient)
+                .add_attribute("amount", amount.to_string())
+                .add_attribute("caller", caller),
+            EscrowEvent::Rescued {
+                escrow_address,
+                side,
+                recipient,
+                amount,
+            } => event
+                .add_attribute("escrow_address", escrow_address)
+                .add_attribute("side", side.as_str())
+                .add_attribute("recipient", recipient)
+                .add_attribute("amount", amount.to_string()),
+            EscrowEvent::PublicAction {
+                escrow_address,
+                side,
+                action,
+                caller,
+                safety_deposit,
+            } => event
+                .add_attribute("escrow_address", escrow_address)
+                .add_attribute("side", side.as_str())
+                .add_attribute("action", action.as_str())
+                .add_attribute("caller", caller)
+                .add_attribute("safety_deposit", safety_deposit.to_string()),
+            EscrowEvent::FeesCharged {
+                escrow_address,
+                token,
+                protocol_fee,
+                fee_collector,
+                integrator_fee,
+                integrator,
+            } => {
+                let event = event
+                    .add_attribute("escrow_address", escrow_address)
+                    .add_attribute("token", token)
+                    .add_attribute("protocol_fee", protocol_fee.to_string())
+                    .add_attribute("fee_collector", fee_collector)
+                    .add_attribute("integrator_fee", integrator_fee.to_string());
+                match integrator {
+                    Some(integrator) => event.add_attribute("integrator", integrator),
+                    None => event,
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<&Event> for EscrowEvent {
+    type Error = StdError;
+
+    /// Parses an event emitted by the contract. The `wasm-` prefix the chain adds to
+    /// custom event types is accepted, and unknown attributes are ignored.
+    fn try_from(event: &Event) -> StdResult<Self> {
+        let ty = event.ty.strip_prefix("wasm-").unwrap_or(&event.ty);
+        let attr = |key: &str| -> StdResult<String> {
+            event
+                .attributes
+                .iter()
+                .find(|attribute| attribute.key == key)
+                .map(|attribute| attribute.value.clone())
+                .ok_or_else(|| {
+                    StdError::parse_err("EscrowEvent", format!("{} has no {} attribute", ty, key))
+                })
+        };
+        let addr = |key: &str| attr(key).map(Addr::unchecked);
+        let optional_addr = |key: &str| {
+            event
+                .attributes
+                .iter()
+                .find(|attribute| attribute.key == key)
+                .map(|attribute| Addr::unchecked(&attribute.value))
+        };
+        let side = || -> StdResult<EscrowSide> {
+            match attr("side")?.as_str() {
+                "src" => Ok(EscrowSide::Src),
+                "dst" => Ok(EscrowSide::Dst),
+                other => Err(StdError::parse_err(
+                    "EscrowEvent",
+                    format!("unknown escrow side {}", other),
+                )),
+            }
+        };
+
+        match ty {
+            EVENT_TYPE_DST_ESCROW_CREATED => Ok(EscrowEvent::DstEscrowCreated {
+                escrow_address: attr("escrow_address")?,
+                immutables: Immutables {
+                    order_hash: attr("order_hash")?,
+                    hashlock: attr("hashlock")?,
+                    maker: addr("maker")?,
+                    taker: addr("taker")?,
+                    token: addr("token")?,
+                    amount: parse_number(&attr("amount")?)?,
+                    safety_deposit: parse_number(&attr("safety_deposit")?)?,
+                    timelocks: parse_number::<Uint256>(&attr("timelocks")?)?,
+                },
+                timestamp: parse_number(&attr("timestamp")?)?,
+                creator: addr("creator")?,
+            }),
+            EVENT_TYPE_SRC_ESCROW_CREATED => Ok(EscrowEvent::SrcEscrowCreated {
+                escrow_address: attr("escrow_address")?,
+                order_hash: attr("order_hash")?,
+                maker: addr("maker")?,
+                taker: addr("taker")?,
+                maker_asset: addr("maker_asset")?,
+                taker_asset: addr("taker_asset")?,
+                making_amount: parse_number(&attr("making_amount")?)?,
+                taking_amount: parse_number(&attr("taking_amount")?)?,
+                remaining_making_amount: parse_number(&attr("remaining_making_amount")?)?,
+                creator: addr("creator")?,
+            }),
+            EVENT_TYPE_DST_ESCROW_FUNDED => Ok(EscrowEvent::DstEscrowFunded {
+                escrow_address: attr("escrow_address")?,
+                funder: addr("funder")?,
+                amount: parse_number(&attr("amount")?)?,
+                safety_deposit: parse_number(&attr("safety_deposit")?)?,
+            }),
+            EVENT_TYPE_ESCROW_WITHDRAWN => Ok(EscrowEvent::Withdrawn {
+                escrow_address: attr("escrow_address")?,
+                side: side()?,
+                secret: attr("secret")?,
+                recipient: addr("recipient")?,
+                amount: parse_number(&attr("amount")?)?,
+                caller: addr("caller")?,
+            }),
+            EVENT_TYPE_ESCROW_CANCELLED => Ok(EscrowEvent::Cancelled {
+                escrow_address: attr("escrow_address")?,
+                side: side()?,
+                recipient: addr("recipient")?,
+                amount: parse_number(&attr("amount")?)?,
+                caller: addr("caller")?,
+            }),
+            EVENT_TYPE_ESCROW_RESCUED => Ok(EscrowEvent::Rescued {
+                escrow_address: attr("escrow_address")?,
+                side: side()?,
+                recipient: addr("recipient")?,
+                amount: parse_number(&attr("amount")?)?,
+            }),
+            EVENT_TYPE_ESCROW_PUBLIC_ACTION => Ok(EscrowEvent::PublicAction {
+                escrow_address: attr("escrow_address")?,
+                side: side()?,
+                action: parse_action(&attr("action")?)?,
+                caller: addr("caller")?,
+                safety_deposit: parse_number(&attr("safety_deposit")?)?,
+            }),
+            EVENT_TYPE_ESCROW_FEES_CHARGED => Ok(EscrowEvent::FeesCharged {
+                escrow_address: attr("escrow_address")?,
+                token: addr("token")?,
+                protocol_fee: parse_number(&attr("protocol_fee")?)?,
+                fee_collector: addr("fee_collector")?,
+                integrator_fee: parse_number(&attr("integrator_fee")?)?,
+                integrator: optional_addr("integrator"),
+            }),
+            other => Err(StdError::parse_err(
+                "EscrowEvent",
+                format!("unknown event type {}", other),
+            )),
+        }
+    }
+}
+
+fn parse_action(value: &str) -> StdResult<EscrowAction> {
+    EscrowAction::ALL
+        .into_iter()
+        .find(|action| action.as_str() == value)
+        .ok_or_else(|| StdError::parse_err("EscrowEvent", format!("unknown action {}", value)))
+}
+
+fn parse_number<T: FromStr<Err = StdError>>(value: &str) -> StdResult<T> {
+    T::from_str(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_event() {
+        let events = vec![
+            EscrowEvent::DstEscrowCreated {
+                escrow_address: "cosmos1escrow".to_string(),
+                immutables: Immutables {
+                    order_hash: "0x01".to_string(),
+                    hashlock: "0x02".to_string(),
+                    maker: Addr::unchecked("maker"),
+                    taker: Addr::unchecked("taker"),
+                    token: Addr::unchecked("token"),
+                    amount: Uint128::new(1000),
+                    safety_deposit: Uint128::new(100),
+                    timelocks: Uint256::from(3600u128),
+                },
+                timestamp: Uint128::new(1_700_000_000),
+                creator: Addr::unchecked("creator"),
+            },
+            EscrowEvent::DstEscrowFunded {
+                escrow_address: "cosmos1escrow".to_string(),
+                funder: Addr::unchecked("taker"),
+                amount: Uint128::new(1000),
+                safety_deposit: Uint128::new(100),
+            },
+            EscrowEvent::Withdrawn {
+                escrow_address: "cosmos1escrow".to_string(),
+                side: EscrowSide::Src,
+                secret: "0x03".to_string(),
+                recipient: Addr::unchecked("taker"),
+                amount: Uint128::new(1000),
+                caller: Addr::unchecked("resolver"),
+            },
+            EscrowEvent::PublicAction {
+                escrow_address: "cosmos1escrow".to_string(),
+                side: EscrowSide::Dst,
+                action: EscrowAction::PublicWithdraw,
+                caller: Addr::unchecked("resolver"),
+                safety_deposit: Uint128::new(100),
+            },
+            EscrowEvent::FeesCharged {
+                escrow_address: "cosmos1escrow".to_string(),
+                token: Addr::unchecked("token"),
+                protocol_fee: Uint128::new(7),
+                fee_collector: Addr::unchecked("collector"),
+                integrator_fee: Uint128::zero(),
+                integrator: None,
+            },
+        ];
+
+        for escrow_event in events {
+            let event = Event::from(escrow_event.clone());
+            assert_eq!(EscrowEvent::try_from(&event).unwrap(), escrow_event);
+
+            // As seen by indexers, with the chain's prefix and contract address
+            let mut wasm_event = event.add_attribute("_contract_address", "contract");
+            wasm_event.ty = format!("wasm-{}", wasm_event.ty);
+            assert_eq!(EscrowEvent::try_from(&wasm_event).unwrap(), escrow_event);
+        }
+    }
+
+    #[test]
+    fn rejects_foreign_events() {
+        assert!(EscrowEvent::try_from(&Event::new("transfer")).is_err());
+        let missing = Event::new(EVENT_TYPE_ESCROW_RESCUED).add_attribute("side", "src");
+        assert!(EscrowEvent::try_from(&missing).is_err());
+    }
+}
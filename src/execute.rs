@@ -1,11 +1,146 @@
+use std::collections::BTreeMap;
 
+use cosmwasm_std::{
+    from_json, to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, CustomQuery, DepsMut, Env,
+    Event, IbcMsg, MessageInfo, QuerierWrapper, Response, StdError, StdResult, Storage, SubMsg,
+    Timestamp, Uint128, Uint256, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use cw_storage_plus::Bound;
+use sha3::{Digest, Keccak256};
+
+use crate::auction::{min_taking_amount, AuctionDetails};
 use crate::error::ContractError;
-use crate::state::{Immutables, Order, SrcEscrowData, DST_ESCROWS, SRC_ESCROWS, EVENT_TYPE_DST_ESCROW_CREATED, EVENT_TYPE_SRC_ESCROW_CREATED};
+use crate::events::EscrowEvent;
+use crate::extension::Extension;
+use crate::extra_data::EscrowExtraData;
+use crate::hooks::{address_prefix, intermediary_sender};
+use crate::ibc::{EscrowPacket, RELAY_SECRET_REPLY_ID};
+use crate::msg::{
+    BatchDstEscrow, BatchItemResult, BatchMode, BatchResponse, BatchWithdrawal, ExecuteMsg,
+};
+use crate::neutron::{
+    contract_store_key, KvKey, NeutronMsg, NeutronQuery, QueryResultResponse,
+    RegisterQueryResponse, KV_QUERY_TYPE, REGISTER_QUERY_REPLY_ID, WASM_STORE,
+};
+use crate::state::{
+    chain_key, escrow_by_hashlock, load_escrow, record_closed, record_created, record_locked,
+    AddressFormat, CounterpartQuery, DstEscrow, EscrowSide, EscrowState, EscrowStatus, FeeConfig,
+    Immutables, Order, RelayedSecret, RevealedSecret, SrcEscrow, SrcEscrowData, SupportedChain,
+    SupportedToken, SweepConfig, CLAIMABLE_FUNDS, CONFIG, COUNTERPART_QUERIES, DST_ESCROWS,
+    FEE_CONFIG, IBC_CHANNELS, MAX_CANCELLATION_OFFSET, PENDING_COUNTERPART_QUERY, RELAYED_SECRETS,
+    REVEALED_SECRETS, SECRET_RELAY, SRC_ESCROWS, SUPPORTED_CHAINS, SUPPORTED_TOKENS, SWEEP_CONFIG,
+    SWEEP_CURSORS,
+};
+use crate::timelocks::{ActionCaller, EscrowAction, Stage, Timelocks};
+
+// Fees are expressed in basis points
+const BPS_DENOMINATOR: u128 = 10_000;
+
+// Highest fee the owner can set, in basis points of the escrowed amount
+pub(crate) const MAX_FEE_BPS: u16 = 500;
+
+// Seconds a packet has to reach the counterpart chain
+const PACKET_TIMEOUT: u64 = 10 * 60;
+
+// Blocks between updates of a counterpart query's result
+const COUNTERPART_QUERY_PERIOD: u64 = 10;
+
+// Reply to the call cancelling one escrow of a sweep, which is only handled on failure
+pub const SWEEP_ESCROW_REPLY_ID: u64 = 3;
+
+/// Creates a new destination escrow
+pub fn create_dst_escrow(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    escrow_address: String,  // User-provided cosmos address
+    immutables: Immutables,
+    timestamp: Uint128,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    // Validate timestamp (ensure it's not in the past)
+    if timestamp < Uint128::from(env.block.time.seconds()) {
+        return Err(ContractError::InvalidTimestamp {});
+    }
+
+    // The taker's tokens and the safety deposit are locked in the contract
+    let config = CONFIG.load(deps.storage)?;
+    let pull = lock_funds(
+        &deps.querier,
+        &env,
+        &info,
+        &immutables,
+        &config.safety_deposit_denom,
+    )?;
+
+    let escrow = store_dst_escrow(deps, &env, &escrow_address, immutables, true)?;
+
+    // Create the event
+    let event = EscrowEvent::DstEscrowCreated {
+        escrow_address: escrow_address.clone(),
+        immutables: escrow.immutables,
+        timestamp,
+        creator: info.sender,
+    };
+
+    Ok(Response::new()
+        .add_messages(pull)
+        .add_event(event.into())
+        .add_attribute("action", "create_dst_escrow")
+        .add_attribute("escrow_address", escrow_address))  // Changed from escrow_key
+}
+
+/// Stores a new active destination escrow after checking its address and hashlock are free.
+/// Every check comes before the first write, which IBC packet handling relies on.
+pub(crate) fn store_dst_escrow(
+    deps: DepsMut,
+    env: &Env,
+    escrow_address: &str,
+    immutables: Immutables,
+    funded: bool,
+) -> Result<DstEscrow, ContractError> {
+    // Validate the cosmos address format
+    deps.api.addr_validate(escrow_address)?;
+
+    // Check if escrow already exists for this address
+    if escrow_exists(deps.storage, escrow_address) {
+        return Err(ContractError::EscrowAlreadyExists {});
+    }
+
+    // Hashlocks are unique, so a secret can only ever unlock one escrow
+    if DST_ESCROWS
+        .idx
+        .hashlock
+        .item(deps.storage, immutables.hashlock.clone())?
+        .is_some()
+    {
+        return Err(ContractError::HashlockAlreadyExists {});
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    ensure_rescue_after_cancellation(immutables.timelocks, EscrowSide::Dst, config.rescue_delay)?;
+
+    // Store the escrow using the provided address
+    let escrow = DstEscrow {
+        immutables,
+        status: EscrowStatus::Active,
+        deployed_at: env.block.time,
+        funded,
+        counterpart_verified: false,
+    };
+    DST_ESCROWS.save(deps.storage, escrow_address.to_string(), &escrow)?;
+    record_created(deps.storage, EscrowSide::Dst, escrow.immutables.timelocks)?;
+    if funded {
+        record_locked(deps.storage, &escrow.immutables)?;
+    }
+    Ok(escrow)
+}
 
 /// Creates a new source escrow
+#[allow(clippy::too_many_arguments)]
 pub fn create_src_escrow(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     escrow_address: String,
     order: Order,
@@ -16,16 +151,33 @@ pub fn create_src_escrow(
     taking_amount: Uint128,
     remaining_making_amount: Uint128,
     extra_data: Binary,
-) -> Result<Response, ContractError> {
+) -> Result<Response<NeutronMsg>, ContractError> {
     // Validate the cosmos addresses
     deps.api.addr_validate(&escrow_address)?;
     let taker_addr = deps.api.addr_validate(&taker)?;
 
     // Check if escrow already exists for this address
-    if SRC_ESCROWS.has(deps.storage, escrow_address.clone()) {
+    if escrow_exists(deps.storage, &escrow_address) {
         return Err(ContractError::EscrowAlreadyExists {});
     }
 
+    // An integrator named by the order must be able to receive its fee share
+    if let Some(integrator) = Extension::decode(&extension)?.integrator()? {
+        deps.api.addr_validate(&integrator)?;
+    }
+
+    // Each hashlock can lock only one source escrow
+    let extra_data = EscrowExtraData::decode(&extra_data)?;
+    if SRC_ESCROWS
+        .idx
+        .hashlock
+        .item(deps.storage, extra_data.hashlock_info.clone())?
+        .is_some()
+    {
+        return Err(ContractError::HashlockAlreadyExists {});
+    }
+    validate_counterpart(deps.storage, &order.maker_asset, &extra_data)?;
+
     // Create the escrow data
     let escrow_data = SrcEscrowData {
         order,
@@ -38,83 +190,1660 @@ pub fn create_src_escrow(
         extra_data,
     };
 
+    // Fills pay at least the order's rate, raised by its Dutch auction for Fusion orders
+    validate_fill_amounts(&escrow_data)?;
+    let auction = AuctionDetails::from_extension(&escrow_data.extension)?;
+    let minimum = min_taking_amount(
+        &escrow_data.order,
+        making_amount,
+        auction.as_ref(),
+        env.block.time,
+    )?;
+    if taking_amount < minimum {
+        return Err(ContractError::TakingAmountTooLow { minimum });
+    }
+
+    // The maker's tokens and the safety deposit are locked in the contract
+    let immutables = escrow_data.immutables();
+    let config = CONFIG.load(deps.storage)?;
+    ensure_rescue_after_cancellation(immutables.timelocks, EscrowSide::Src, config.rescue_delay)?;
+    let pull = lock_funds(
+        &deps.querier,
+        &env,
+        &info,
+        &immutables,
+        &config.safety_deposit_denom,
+    )?;
+
     // Store the escrow using the provided address
-    SRC_ESCROWS.save(deps.storage, escrow_address.clone(), &escrow_data)?;
+    let escrow = SrcEscrow {
+        escrow_data,
+        status: EscrowStatus::Active,
+        deployed_at: env.block.time,
+        funded: true,
+        counterpart_verified: false,
+        fee_config: FEE_CONFIG.may_load(deps.storage)?,
+    };
+    SRC_ESCROWS.save(deps.storage, escrow_address.clone(), &escrow)?;
+    record_created(deps.storage, EscrowSide::Src, immutables.timelocks)?;
+    record_locked(deps.storage, &immutables)?;
+    let escrow_data = escrow.escrow_data;
 
     // Create the event
-    let event = Event::new(EVENT_TYPE_SRC_ESCROW_CREATED)
-        .add_attribute("escrow_address", &escrow_address)
-        .add_attribute("order_hash", &order_hash)
-        .add_attribute("maker", escrow_data.order.maker.to_string())
-        .add_attribute("taker", escrow_data.taker.to_string())
-        .add_attribute("maker_asset", escrow_data.order.maker_asset.to_string())
-        .add_attribute("taker_asset", escrow_data.order.taker_asset.to_string())
-        .add_attribute("making_amount", making_amount.to_string())
-        .add_attribute("taking_amount", taking_amount.to_string())
-        .add_attribute("remaining_making_amount", remaining_making_amount.to_string())
-        .add_attribute("creator", info.sender.to_string());
-
-    Ok(Response::new()
-        .add_event(event)
+    let event = EscrowEvent::SrcEscrowCreated {
+        escrow_address: escrow_address.clone(),
+        order_hash,
+        maker: escrow_data.order.maker,
+        taker: escrow_data.taker,
+        maker_asset: escrow_data.order.maker_asset,
+        taker_asset: escrow_data.order.taker_asset,
+        making_amount,
+        taking_amount,
+        remaining_making_amount,
+        creator: info.sender,
+    };
+
+    Ok(Response::new()
+        .add_messages(pull)
+        .add_event(event.into())
         .add_attribute("action", "create_src_escrow")
         .add_attribute("escrow_address", escrow_address))
-}use cosmwasm_std::{DepsMut, Env, Event, MessageInfo, Response, Uint128, Binary};
+}
 
+/// Withdraws an escrow with its secret during the taker's private withdrawal period
+pub fn withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    escrow_address: String,
+    secret: String,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let escrow = load_escrow(deps.storage, &escrow_address)?;
+    ensure_callable(deps.storage, &env, &info, &escrow, EscrowAction::Withdraw)?;
+    let relay = relay_secret(deps.storage, &env, &escrow.immutables.hashlock, &secret)?;
 
-/// Creates a new destination escrow
-pub fn create_dst_escrow(
+    let response = settle_withdrawal(
+        deps,
+        env,
+        info,
+        escrow_address,
+        escrow,
+        secret,
+        EscrowAction::Withdraw,
+    )?;
+    Ok(response.add_submessages(relay))
+}
+
+/// Withdraws an escrow with its secret during the public withdrawal period. Anyone may
+/// call this and collects the safety deposit.
+pub fn public_withdraw(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    escrow_address: String,  // User-provided cosmos address
+    escrow_address: String,
+    secret: String,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let escrow = load_escrow(deps.storage, &escrow_address)?;
+    ensure_callable(
+        deps.storage,
+        &env,
+        &info,
+        &escrow,
+        EscrowAction::PublicWithdraw,
+    )?;
+    let relay = relay_secret(deps.storage, &env, &escrow.immutables.hashlock, &secret)?;
+
+    let response = settle_withdrawal(
+        deps,
+        env,
+        info,
+        escrow_address,
+        escrow,
+        secret,
+        EscrowAction::PublicWithdraw,
+    )?;
+    Ok(response.add_submessages(relay))
+}
+
+/// Cancels an escrow during the taker's private cancellation period
+pub fn cancel(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    escrow_address: String,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let escrow = load_escrow(deps.storage, &escrow_address)?;
+    ensure_callable(deps.storage, &env, &info, &escrow, EscrowAction::Cancel)?;
+
+    settle_cancellation(deps, info, escrow_address, escrow, EscrowAction::Cancel)
+}
+
+/// Cancels a source escrow during the public cancellation period. Anyone may call this
+/// and collects the safety deposit.
+pub fn public_cancel(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    escrow_address: String,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let escrow = load_escrow(deps.storage, &escrow_address)?;
+    ensure_callable(
+        deps.storage,
+        &env,
+        &info,
+        &escrow,
+        EscrowAction::PublicCancel,
+    )?;
+
+    settle_cancellation(
+        deps,
+        info,
+        escrow_address,
+        escrow,
+        EscrowAction::PublicCancel,
+    )
+}
+
+/// Returns all locked funds to the taker once the rescue delay has passed
+pub fn rescue(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    escrow_address: String,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let escrow = load_escrow(deps.storage, &escrow_address)?;
+    ensure_callable(deps.storage, &env, &info, &escrow, EscrowAction::Rescue)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    set_status(
+        deps.storage,
+        &escrow_address,
+        &escrow,
+        EscrowStatus::Rescued,
+    )?;
+
+    let immutables = &escrow.immutables;
+    let messages = payout(
+        &deps.querier,
+        &escrow,
+        &config.safety_deposit_denom,
+        &immutables.taker,
+        &immutables.taker,
+        &[],
+    )?;
+
+    let event = EscrowEvent::Rescued {
+        escrow_address: escrow_address.clone(),
+        side: escrow.side,
+        recipient: immutables.taker.clone(),
+        amount: immutables.amount,
+    };
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_event(event.into())
+        .add_attribute("action", "rescue")
+        .add_attribute("escrow_address", escrow_address))
+}
+
+/// Creates several destination escrows at once. The attached funds have to cover every
+/// escrow; in best-effort mode the funds of escrows that fail are sent back.
+pub fn batch_create_dst_escrows(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    escrows: Vec<BatchDstEscrow>,
+    mode: BatchMode,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let funds: Vec<Vec<Coin>> = escrows
+        .iter()
+        .map(|escrow| {
+            let cw20 = is_cw20(&deps.querier, &escrow.immutables.token);
+            locked_funds(&escrow.immutables, &config.safety_deposit_denom, cw20)
+        })
+        .collect();
+    assert_funds(&info, &funds.concat())?;
+
+    let items = escrows
+        .into_iter()
+        .zip(funds.clone())
+        .map(|(escrow, funds)| (escrow.escrow_address.clone(), (escrow, funds)))
+        .collect();
+    let (mut response, results) = run_batch(deps, mode, items, |deps, (escrow, funds)| {
+        let info = MessageInfo {
+            sender: info.sender.clone(),
+            funds,
+        };
+        create_dst_escrow(
+            deps,
+            env.clone(),
+            info,
+            escrow.escrow_address,
+            escrow.immutables,
+            escrow.timestamp,
+        )
+    })?;
+
+    let mut refund: BTreeMap<String, Uint128> = BTreeMap::new();
+    for (result, funds) in results.iter().zip(funds) {
+        if result.error.is_some() {
+            for coin in funds.into_iter().filter(|coin| !coin.amount.is_zero()) {
+                *refund.entry(coin.denom).or_default() += coin.amount;
+            }
+        }
+    }
+    if !refund.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: refund
+                .into_iter()
+                .map(|(denom, amount)| Coin { denom, amount })
+                .collect(),
+        });
+    }
+
+    batch_response(response, "batch_create_dst_escrows", results)
+}
+
+/// Withdraws several escrows at once, each as `Withdraw` would
+pub fn batch_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    withdrawals: Vec<BatchWithdrawal>,
+    mode: BatchMode,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let items = withdrawals
+        .into_iter()
+        .map(|withdrawal| (withdrawal.escrow_address.clone(), withdrawal))
+        .collect();
+    let (response, results) = run_batch(deps, mode, items, |deps, withdrawal| {
+        withdraw(
+            deps,
+            env.clone(),
+            info.clone(),
+            withdrawal.escrow_address,
+            withdrawal.secret,
+        )
+    })?;
+
+    batch_response(response, "batch_withdraw", results)
+}
+
+/// Cancels several escrows at once, each as `Cancel` would
+pub fn batch_cancel(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    escrow_addresses: Vec<String>,
+    mode: BatchMode,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let items = escrow_addresses
+        .into_iter()
+        .map(|escrow_address| (escrow_address.clone(), escrow_address))
+        .collect();
+    let (response, results) = run_batch(deps, mode, items, |deps, escrow_address| {
+        cancel(deps, env.clone(), info.clone(), escrow_address)
+    })?;
+
+    batch_response(response, "batch_cancel", results)
+}
+
+/// Runs `handle` on each item of a batch, keyed by escrow address, and collects what the
+/// items send and emit. An all-or-nothing batch fails with its first failing item; a
+/// best-effort one records the error and moves on. Nothing rolls back the writes of a
+/// failed best-effort item, so `handle` must not write before its last fallible step.
+fn run_batch<T>(
+    mut deps: DepsMut,
+    mode: BatchMode,
+    items: Vec<(String, T)>,
+    mut handle: impl FnMut(DepsMut, T) -> Result<Response<NeutronMsg>, ContractError>,
+) -> Result<(Response<NeutronMsg>, Vec<BatchItemResult>), ContractError> {
+    if items.is_empty() {
+        return Err(ContractError::EmptyBatch {});
+    }
+
+    let mut response = Response::new();
+    let mut results = Vec::with_capacity(items.len());
+    for (escrow_address, item) in items {
+        let error = match handle(deps.branch(), item) {
+            Ok(item_response) => {
+                response = response
+                    .add_submessages(item_response.messages)
+                    .add_events(item_response.events);
+                None
+            }
+            Err(err) if mode == BatchMode::BestEffort => Some(err.to_string()),
+            Err(err) => return Err(err),
+        };
+        results.push(BatchItemResult {
+            escrow_address,
+            error,
+        });
+    }
+    Ok((response, results))
+}
+
+/// Sets a batch's per-item results as the response data
+fn batch_response(
+    response: Response<NeutronMsg>,
+    action: &str,
+    results: Vec<BatchItemResult>,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let failed = results
+        .iter()
+        .filter(|result| result.error.is_some())
+        .count();
+    let succeeded = results.len() - failed;
+
+    Ok(response
+        .set_data(to_json_binary(&BatchResponse { results })?)
+        .add_attribute("action", action)
+        .add_attribute("succeeded", succeeded.to_string())
+        .add_attribute("failed", failed.to_string()))
+}
+
+/// Asks the counterpart factory behind an escrow channel to open the destination escrow
+/// matching a source escrow. Only the source escrow's taker may call this.
+pub fn request_dst_escrow(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    src_escrow_address: String,
+    channel_id: String,
+    escrow_address: String,
     immutables: Immutables,
-    timestamp: Uint128,
-) -> Result<Response, ContractError> {
-    // Validate timestamp (ensure it's not in the past)
-    if timestamp < Uint128::from(env.block.time.seconds()) {
-        return Err(ContractError::InvalidTimestamp {});
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let src_escrow = SRC_ESCROWS
+        .may_load(deps.storage, src_escrow_address.clone())?
+        .ok_or(ContractError::EscrowNotFound {})?;
+    let src_immutables = src_escrow.escrow_data.immutables();
+    if info.sender != src_immutables.taker {
+        return Err(ContractError::Unauthorized {});
+    }
+    if src_escrow.status != EscrowStatus::Active {
+        return Err(ContractError::NotActive {
+            status: src_escrow.status,
+        });
     }
 
-    // Validate the cosmos address format
-    deps.api.addr_validate(&escrow_address)?;
+    // The destination escrow has to lock the same order with the same secret and timelocks
+    if !immutables.matches_counterpart(&src_immutables) {
+        return Err(ContractError::ImmutablesMismatch {});
+    }
+    if !IBC_CHANNELS.has(deps.storage, channel_id.clone()) {
+        return Err(ContractError::UnknownChannel { channel_id });
+    }
 
-    // Check if escrow already exists for this address
-    if DST_ESCROWS.has(deps.storage, escrow_address.clone()) {
-        return Err(ContractError::EscrowAlreadyExists {});
+    let packet = EscrowPacket::CreateDstEscrow {
+        escrow_address: escrow_address.clone(),
+        immutables,
+    };
+    let msg = IbcMsg::SendPacket {
+        channel_id: channel_id.clone(),
+        data: to_json_binary(&packet)?,
+        timeout: env.block.time.plus_seconds(PACKET_TIMEOUT).into(),
+    };
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", "request_dst_escrow")
+        .add_attribute("src_escrow_address", src_escrow_address)
+        .add_attribute("channel_id", channel_id)
+        .add_attribute("escrow_address", escrow_address))
+}
+
+/// Locks the funds of a destination escrow opened over IBC. Only its taker may call this,
+/// and only until the escrow's withdrawal stage starts.
+pub fn fund_dst_escrow(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    escrow_address: String,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let mut escrow = DST_ESCROWS
+        .may_load(deps.storage, escrow_address.clone())?
+        .ok_or(ContractError::EscrowNotFound {})?;
+    if info.sender != escrow.immutables.taker {
+        return Err(ContractError::Unauthorized {});
+    }
+    if escrow.funded {
+        return Err(ContractError::EscrowAlreadyFunded {});
+    }
+    if escrow.status != EscrowStatus::Active {
+        return Err(ContractError::NotActive {
+            status: escrow.status,
+        });
+    }
+    if env.block.time > funding_deadline(&escrow.immutables, escrow.deployed_at) {
+        return Err(ContractError::FundingDeadlinePassed {});
     }
 
-    // Store the escrow using the provided address
-    DST_ESCROWS.save(deps.storage, escrow_address.clone(), &immutables)?;
+    let config = CONFIG.load(deps.storage)?;
+    let pull = lock_funds(
+        &deps.querier,
+        &env,
+        &info,
+        &escrow.immutables,
+        &config.safety_deposit_denom,
+    )?;
+    escrow.funded = true;
+    DST_ESCROWS.save(deps.storage, escrow_address.clone(), &escrow)?;
+    record_locked(deps.storage, &escrow.immutables)?;
 
-    // Create the event
-    let event = Event::new(EVENT_TYPE_DST_ESCROW_CREATED)
-        .add_attribute("escrow_address", &escrow_address)  // Changed from escrow_key
-        .add_attribute("order_hash", &immutables.order_hash)
-        .add_attribute("hashlock", &immutables.hashlock)
-        .add_attribute("maker", immutables.maker.to_string())
-        .add_attribute("taker", immutables.taker.to_string())
-        .add_attribute("token", immutables.token.to_string())
-        .add_attribute("amount", immutables.amount.to_string())
-        .add_attribute("safety_deposit", immutables.safety_deposit.to_string())
-        .add_attribute("timelocks", immutables.timelocks.to_string())
-        .add_attribute("timestamp", timestamp.to_string())
-        .add_attribute("creator", info.sender.to_string());
-
-    Ok(Response::new()
-        .add_event(event)
-        .add_attribute("action", "create_dst_escrow")
-        .add_attribute("escrow_address", escrow_address))  // Changed from escrow_key
+    let event = EscrowEvent::DstEscrowFunded {
+        escrow_address: escrow_address.clone(),
+        funder: info.sender,
+        amount: escrow.immutables.amount,
+        safety_deposit: escrow.immutables.safety_deposit,
+    };
+
+    Ok(Response::new()
+        .add_messages(pull)
+        .add_event(event.into())
+        .add_attribute("action", "fund_dst_escrow")
+        .add_attribute("escrow_address", escrow_address))
+}
+
+/// Sets the escrow channel revealed secrets are forwarded over, or stops forwarding them.
+/// Only the owner may call this.
+pub fn update_secret_relay(
+    deps: DepsMut,
+    info: MessageInfo,
+    channel_id: Option<String>,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    ensure_owner(deps.storage, &info)?;
+    match &channel_id {
+        Some(channel_id) if !IBC_CHANNELS.has(deps.storage, channel_id.clone()) => {
+            return Err(ContractError::UnknownChannel {
+                channel_id: channel_id.clone(),
+            });
+        }
+        Some(channel_id) => SECRET_RELAY.save(deps.storage, channel_id)?,
+        None => SECRET_RELAY.remove(deps.storage),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "update_secret_relay")
+        .add_attribute("channel_id", channel_id.unwrap_or_default()))
+}
+
+/// Handles a secret relayed from the counterpart leg. The escrow it unlocks is withdrawn
+/// for its beneficiary if the taker could withdraw it now; otherwise the secret is kept
+/// for the beneficiary to use.
+pub(crate) fn receive_secret(
+    deps: DepsMut,
+    env: Env,
+    channel_id: String,
+    hashlock: String,
+    secret: String,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    verify_secret(&secret, &hashlock)?;
+
+    if let Some(escrow_address) = escrow_by_hashlock(deps.storage, &hashlock)? {
+        let escrow = load_escrow(deps.storage, &escrow_address)?;
+        let taker = MessageInfo {
+            sender: escrow.immutables.taker.clone(),
+            funds: vec![],
+        };
+        if escrow.status == EscrowStatus::Active
+            && ensure_callable(deps.storage, &env, &taker, &escrow, EscrowAction::Withdraw).is_ok()
+        {
+            return settle_withdrawal(
+                deps,
+                env,
+                taker,
+                escrow_address,
+                escrow,
+                secret,
+                EscrowAction::Withdraw,
+            );
+        }
+    }
+
+    let relayed = RelayedSecret {
+        secret,
+        channel_id,
+        height: env.block.height,
+        time: env.block.time,
+    };
+    RELAYED_SECRETS.save(deps.storage, hashlock.to_lowercase(), &relayed)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "store_relayed_secret")
+        .add_attribute("hashlock", hashlock))
+}
+
+/// Packet forwarding a revealed secret to the counterpart leg, if a relay is set and its
+/// channel is still open. Sending is best effort: a failure is swallowed by the reply
+/// handler so the withdrawal goes through regardless.
+fn relay_secret(
+    storage: &dyn Storage,
+    env: &Env,
+    hashlock: &str,
+    secret: &str,
+) -> Result<Option<SubMsg<NeutronMsg>>, ContractError> {
+    let Some(channel_id) = SECRET_RELAY.may_load(storage)? else {
+        return Ok(None);
+    };
+    if !IBC_CHANNELS.has(storage, channel_id.clone()) {
+        return Ok(None);
+    }
+
+    let packet = EscrowPacket::RevealSecret {
+        hashlock: hashlock.to_string(),
+        secret: secret.to_string(),
+    };
+    let msg = IbcMsg::SendPacket {
+        channel_id,
+        data: to_json_binary(&packet)?,
+        timeout: env.block.time.plus_seconds(PACKET_TIMEOUT).into(),
+    };
+    Ok(Some(SubMsg::reply_on_error(msg, RELAY_SECRET_REPLY_ID)))
+}
+
+/// Creates an escrow funded by an incoming ICS-20 transfer, as executed by ibc-hooks from
+/// the transfer's memo. If the escrow cannot be created the transferred coins are kept for
+/// `recovery_address` to claim, so the transfer itself still succeeds.
+#[allow(clippy::too_many_arguments)]
+pub fn ibc_hook(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    channel: String,
+    original_sender: String,
+    recovery_address: String,
+    msg: ExecuteMsg,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    // Only the intermediary ibc-hooks derives for this channel and sender may fund escrows
+    let prefix = address_prefix(info.sender.as_str())?;
+    if intermediary_sender(&channel, &original_sender, &prefix)? != info.sender {
+        return Err(ContractError::InvalidHookSender {});
+    }
+    let recovery_address = deps.api.addr_validate(&recovery_address)?;
+
+    // Creation validates everything before it writes, so a failure leaves no escrow behind
+    let created = match msg {
+        ExecuteMsg::CreateDstEscrow {
+            escrow_address,
+            immutables,
+            timestamp,
+        } => create_dst_escrow(
+            deps.branch(),
+            env,
+            info.clone(),
+            escrow_address,
+            immutables,
+            timestamp,
+        ),
+        ExecuteMsg::CreateSrcEscrow {
+            escrow_address,
+            order,
+            extension,
+            order_hash,
+            taker,
+            making_amount,
+            taking_amount,
+            remaining_making_amount,
+            extra_data,
+        } => create_src_escrow(
+            deps.branch(),
+            env,
+            info.clone(),
+            escrow_address,
+            order,
+            extension,
+            order_hash,
+            taker,
+            making_amount,
+            taking_amount,
+            remaining_making_amount,
+            extra_data,
+        ),
+        _ => return Err(ContractError::UnsupportedHookMsg {}),
+    };
+
+    match created {
+        Ok(response) => Ok(response
+            .add_attribute("hook_channel", channel)
+            .add_attribute("hook_sender", original_sender)),
+        Err(err) => {
+            let mut claimable = CLAIMABLE_FUNDS
+                .may_load(deps.storage, recovery_address.clone())?
+                .unwrap_or_default();
+            for coin in info.funds {
+                match claimable.iter_mut().find(|held| held.denom == coin.denom) {
+                    Some(held) => held.amount += coin.amount,
+                    None => claimable.push(coin),
+                }
+            }
+            CLAIMABLE_FUNDS.save(deps.storage, recovery_address.clone(), &claimable)?;
+
+            Ok(Response::new()
+                .add_attribute("action", "ibc_hook_failed")
+                .add_attribute("recovery_address", recovery_address)
+                .add_attribute("error", err.to_string()))
+        }
+    }
+}
+
+/// Sends the sender every coin kept for them from failed hook transfers
+pub fn claim_funds(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let funds = CLAIMABLE_FUNDS
+        .may_load(deps.storage, info.sender.clone())?
+        .ok_or(ContractError::NothingToClaim {})?;
+    CLAIMABLE_FUNDS.remove(deps.storage, info.sender.clone());
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: funds,
+        })
+        .add_attribute("action", "claim_funds")
+        .add_attribute("recipient", info.sender))
+}
+
+/// Registers an interchain query for the counterpart of an escrow, which the factory on a
+/// registered Cosmos chain stores under `counterpart_address`. Its result marks the escrow
+/// counterpart verified. Only the escrow's taker may call this, attaching the query deposit.
+pub fn verify_counterpart(
+    deps: DepsMut,
+    info: MessageInfo,
+    escrow_address: String,
+    chain_id: Uint256,
+    counterpart_address: String,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let escrow = load_escrow(deps.storage, &escrow_address)?;
+    if info.sender != escrow.immutables.taker {
+        return Err(ContractError::Unauthorized {});
+    }
+    if escrow.status != EscrowStatus::Active {
+        return Err(ContractError::NotActive {
+            status: escrow.status,
+        });
+    }
+    if escrow.counterpart_verified {
+        return Err(ContractError::CounterpartAlreadyVerified {});
+    }
+
+    // A source escrow's counterpart lives on the chain its order swaps into
+    let key = match escrow.side {
+        EscrowSide::Src => {
+            let src_escrow = SRC_ESCROWS.load(deps.storage, escrow_address.clone())?;
+            if src_escrow.escrow_data.extra_data.dst_chain_id != chain_id {
+                return Err(ContractError::CounterpartChainMismatch {
+                    chain_id: chain_id.to_string(),
+                });
+            }
+            DST_ESCROWS.key(counterpart_address.clone()).to_vec()
+        }
+        EscrowSide::Dst => SRC_ESCROWS.key(counterpart_address.clone()).to_vec(),
+    };
+
+    // Only factories on Cosmos chains we have a connection to can be queried
+    let chain = SUPPORTED_CHAINS
+        .may_load(deps.storage, chain_key(chain_id))?
+        .ok_or(ContractError::UnsupportedChain {
+            chain_id: chain_id.to_string(),
+        })?;
+    let (Some(connection_id), AddressFormat::Bytes32) = (chain.connection_id, chain.address_format)
+    else {
+        return Err(ContractError::CounterpartNotQueryable {
+            chain_id: chain_id.to_string(),
+        });
+    };
+    let factory = hex::decode(chain.factory.trim_start_matches("0x")).map_err(|_| {
+        ContractError::InvalidCounterpartAddress {
+            address: chain.factory.clone(),
+        }
+    })?;
+
+    let msg = NeutronMsg::RegisterInterchainQuery {
+        query_type: KV_QUERY_TYPE.to_string(),
+        keys: vec![KvKey {
+            path: WASM_STORE.to_string(),
+            key: contract_store_key(&factory, &key).into(),
+        }],
+        transactions_filter: String::new(),
+        connection_id,
+        update_period: COUNTERPART_QUERY_PERIOD,
+    };
+    let query = CounterpartQuery {
+        escrow_address: escrow_address.clone(),
+        chain_id,
+        counterpart_address: counterpart_address.clone(),
+    };
+    PENDING_COUNTERPART_QUERY.save(deps.storage, &query)?;
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(msg, REGISTER_QUERY_REPLY_ID))
+        .add_attribute("action", "verify_counterpart")
+        .add_attribute("escrow_address", escrow_address)
+        .add_attribute("chain_id", chain_id.to_string())
+        .add_attribute("counterpart_address", counterpart_address))
+}
+
+/// Files the query registered by `verify_counterpart` under the id Neutron assigned it
+pub(crate) fn save_counterpart_query(
+    deps: DepsMut,
+    data: Option<Binary>,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let data = data.ok_or_else(|| StdError::generic_err("Missing query registration data"))?;
+    let registered: RegisterQueryResponse = from_json(data)?;
+    let query = PENDING_COUNTERPART_QUERY.load(deps.storage)?;
+    PENDING_COUNTERPART_QUERY.remove(deps.storage);
+    COUNTERPART_QUERIES.save(deps.storage, registered.id, &query)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "register_counterpart_query")
+        .add_attribute("query_id", registered.id.to_string())
+        .add_attribute("escrow_address", query.escrow_address))
+}
+
+/// Handles a new result of a counterpart query. Until the counterpart escrow shows up the
+/// query keeps watching; once it does, the local escrow is marked counterpart verified if
+/// both lock the same swap, and the query is removed to refund its deposit.
+pub(crate) fn counterpart_query_result(
+    deps: DepsMut<NeutronQuery>,
+    query_id: u64,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let query = COUNTERPART_QUERIES
+        .may_load(deps.storage, query_id)?
+        .ok_or(ContractError::UnknownInterchainQuery { query_id })?;
+    let response: QueryResultResponse = deps
+        .querier
+        .query(&NeutronQuery::InterchainQueryResult { query_id }.into())?;
+    let Some(value) = response
+        .result
+        .kv_results
+        .into_iter()
+        .map(|kv| kv.value)
+        .find(|value| !value.is_empty())
+    else {
+        return Ok(Response::new()
+            .add_attribute("action", "counterpart_pending")
+            .add_attribute("query_id", query_id.to_string())
+            .add_attribute("escrow_address", query.escrow_address));
+    };
+
+    // Anything that does not decode as the counterpart escrow cannot verify it, and only a
+    // counterpart that holds its funds and can still be withdrawn backs this leg
+    let escrow = load_escrow(deps.storage, &query.escrow_address)?;
+    let counterpart = match escrow.side {
+        EscrowSide::Src => from_json::<DstEscrow>(&value)
+            .ok()
+            .map(|dst| (dst.immutables, dst.status, dst.funded)),
+        EscrowSide::Dst => from_json::<SrcEscrow>(&value)
+            .ok()
+            .map(|src| (src.escrow_data.immutables(), src.status, src.funded)),
+    };
+    let verified = counterpart.is_some_and(|(counterpart, status, funded)| {
+        funded
+            && status == EscrowStatus::Active
+            && escrow.immutables.matches_counterpart(&counterpart)
+    });
+    if verified {
+        match escrow.side {
+            EscrowSide::Dst => {
+                DST_ESCROWS.update(deps.storage, query.escrow_address.clone(), |stored| {
+                    let mut stored = stored.ok_or(ContractError::EscrowNotFound {})?;
+                    stored.counterpart_verified = true;
+                    Ok::<_, ContractError>(stored)
+                })?;
+            }
+            EscrowSide::Src => {
+                SRC_ESCROWS.update(deps.storage, query.escrow_address.clone(), |stored| {
+                    let mut stored = stored.ok_or(ContractError::EscrowNotFound {})?;
+                    stored.counterpart_verified = true;
+                    Ok::<_, ContractError>(stored)
+                })?;
+            }
+        }
+    }
+    COUNTERPART_QUERIES.remove(deps.storage, query_id);
+
+    Ok(Response::new()
+        .add_message(NeutronMsg::RemoveInterchainQuery { query_id })
+        .add_attribute("action", "counterpart_query_result")
+        .add_attribute("query_id", query_id.to_string())
+        .add_attribute("escrow_address", query.escrow_address)
+        .add_attribute("verified", verified.to_string())
+        .add_attribute("height", response.result.height.to_string()))
+}
+
+/// Sets the fee taken from withdrawals of source escrows created from now on. Only the owner
+/// may call this.
+pub fn update_fee_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    fee_bps: u16,
+    integrator_share_bps: u16,
+    fee_collector: String,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    ensure_owner(deps.storage, &info)?;
+    if fee_bps > MAX_FEE_BPS || u128::from(integrator_share_bps) > BPS_DENOMINATOR {
+        return Err(ContractError::InvalidFee {});
+    }
+
+    let fee_config = FeeConfig {
+        fee_bps,
+        integrator_share_bps,
+        fee_collector: deps.api.addr_validate(&fee_collector)?,
+    };
+    FEE_CONFIG.save(deps.storage, &fee_config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_fee_config")
+        .add_attribute("fee_bps", fee_bps.to_string())
+        .add_attribute("integrator_share_bps", integrator_share_bps.to_string())
+        .add_attribute("fee_collector", fee_collector))
 }
 
-// Alternative function to generate a more cosmos-like address
-#[allow(dead_code)]
-fn generate_escrow_address(sender: &cosmwasm_std::Addr, block: &cosmwasm_std::BlockInfo) -> String {
-    use cosmwasm_std::Binary;
+/// Sets how many escrows each sweep looks at. Only the owner may call this.
+pub fn update_sweep_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    batch_size: u32,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    ensure_owner(deps.storage, &info)?;
+    if batch_size == 0 {
+        return Err(ContractError::InvalidSweepConfig {});
+    }
+    SWEEP_CONFIG.save(deps.storage, &SweepConfig { batch_size })?;
 
-    // Create a deterministic address based on sender and block info
-    let input = format!("{}:{}", sender, block.height);
-    let hash = cosmwasm_std::to_binary(&input).unwrap();
+    Ok(Response::new()
+        .add_attribute("action", "update_sweep_config")
+        .add_attribute("batch_size", batch_size.to_string()))
+}
 
-    // Take first 20 bytes and encode as hex
-    format!("escrow{}", hex::encode(&hash.to_vec()[..20.min(hash.len())]))
-}
\ No newline at end of file
+/// Cancels expired escrows for the cron callback. Each sweep looks at the next batch of
+/// escrows of each side after where the last one stopped, starting over from the first
+/// once it runs past the end, so every escrow is reached while gas stays bounded.
+///
+/// Every expired escrow is cancelled by a `SweepEscrow` call the contract sends itself, so
+/// one that fails, say on a token transfer that reverts, rolls back on its own and stays
+/// open until the sweep comes back around, while the cursor and the rest of the batch go on.
+pub(crate) fn sweep_expired(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let batch_size = SWEEP_CONFIG
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        .batch_size as usize;
+
+    let mut response = Response::new();
+    let mut swept = 0;
+    for side in [EscrowSide::Src, EscrowSide::Dst] {
+        let cursor = SWEEP_CURSORS.may_load(deps.storage, side.as_str())?;
+        let mut batch = escrow_addresses(deps.storage, side, cursor.clone(), batch_size)?;
+        if batch.is_empty() && cursor.is_some() {
+            batch = escrow_addresses(deps.storage, side, None, batch_size)?;
+        }
+        match batch.last() {
+            Some(last) if batch.len() == batch_size => {
+                SWEEP_CURSORS.save(deps.storage, side.as_str(), last)?
+            }
+            _ => SWEEP_CURSORS.remove(deps.storage, side.as_str()),
+        }
+
+        for escrow_address in batch {
+            // An escrow that cannot be read is handed on too, so its failure gets reported
+            if let Ok(escrow) = load_escrow(deps.storage, &escrow_address) {
+                if !sweepable(&escrow, env.block.time) {
+                    continue;
+                }
+            }
+            let msg = WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                msg: to_json_binary(&ExecuteMsg::SweepEscrow { escrow_address })?,
+                funds: vec![],
+            };
+            response = response.add_submessage(SubMsg::reply_on_error(msg, SWEEP_ESCROW_REPLY_ID));
+            swept += 1;
+        }
+    }
+
+    Ok(response
+        .add_attribute("action", "sweep_expired")
+        .add_attribute("swept", swept.to_string()))
+}
+
+/// Cancels an expired escrow for the sweep. Funds go back to whoever locked them, and the
+/// safety deposit to the taker who posted it. A destination escrow that was never funded is
+/// dropped instead, which frees its hashlock. Only the contract itself may call this.
+pub fn sweep_escrow(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    escrow_address: String,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let escrow = load_escrow(deps.storage, &escrow_address)?;
+    if escrow.status != EscrowStatus::Active {
+        return Err(ContractError::NotActive {
+            status: escrow.status,
+        });
+    }
+    if !sweepable(&escrow, env.block.time) {
+        return Err(ContractError::InvalidTime {});
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let immutables = &escrow.immutables;
+    let recipient = match escrow.side {
+        EscrowSide::Src => &immutables.maker,
+        EscrowSide::Dst => &immutables.taker,
+    };
+    let messages = payout(
+        &deps.querier,
+        &escrow,
+        &config.safety_deposit_denom,
+        recipient,
+        &immutables.taker,
+        &[],
+    )?;
+    if escrow.side == EscrowSide::Dst && !escrow.funded {
+        // Nothing was locked, so the record goes and releases its hashlock
+        record_closed(deps.storage, &escrow, EscrowStatus::Cancelled)?;
+        DST_ESCROWS.remove(deps.storage, escrow_address.clone())?;
+    } else {
+        set_status(
+            deps.storage,
+            &escrow_address,
+            &escrow,
+            EscrowStatus::Cancelled,
+        )?;
+    }
+
+    let event = EscrowEvent::Cancelled {
+        escrow_address: escrow_address.clone(),
+        side: escrow.side,
+        recipient: recipient.clone(),
+        amount: if escrow.funded {
+            immutables.amount
+        } else {
+            Uint128::zero()
+        },
+        caller: info.sender,
+    };
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_event(event.into())
+        .add_attribute("action", "sweep_escrow")
+        .add_attribute("escrow_address", escrow_address))
+}
+
+/// Adds a counterpart chain, or updates it if already supported. Only the owner may call this.
+pub fn register_chain(
+    deps: DepsMut,
+    info: MessageInfo,
+    chain_id: Uint256,
+    address_format: AddressFormat,
+    factory: String,
+    connection_id: Option<String>,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    ensure_owner(deps.storage, &info)?;
+    let factory = address_format
+        .normalize(&factory)
+        .ok_or(ContractError::InvalidCounterpartAddress { address: factory })?;
+
+    let chain = SupportedChain {
+        chain_id,
+        address_format,
+        factory,
+        connection_id,
+    };
+    SUPPORTED_CHAINS.save(deps.storage, chain_key(chain_id), &chain)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "register_chain")
+        .add_attribute("chain_id", chain_id.to_string())
+        .add_attribute("factory", chain.factory))
+}
+
+/// Drops a counterpart chain along with its token pairs. Only the owner may call this.
+pub fn remove_chain(
+    deps: DepsMut,
+    info: MessageInfo,
+    chain_id: Uint256,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    ensure_owner(deps.storage, &info)?;
+    if !SUPPORTED_CHAINS.has(deps.storage, chain_key(chain_id)) {
+        return Err(ContractError::UnsupportedChain {
+            chain_id: chain_id.to_string(),
+        });
+    }
+
+    let src_tokens = SUPPORTED_TOKENS
+        .prefix(chain_key(chain_id))
+        .keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for src_token in src_tokens {
+        SUPPORTED_TOKENS.remove(deps.storage, (chain_key(chain_id), src_token));
+    }
+    SUPPORTED_CHAINS.remove(deps.storage, chain_key(chain_id));
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_chain")
+        .add_attribute("chain_id", chain_id.to_string()))
+}
+
+/// Pairs a token with its counterpart on a supported chain. Only the owner may call this.
+pub fn register_token(
+    deps: DepsMut,
+    info: MessageInfo,
+    chain_id: Uint256,
+    src_token: String,
+    dst_token: String,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    ensure_owner(deps.storage, &info)?;
+    let chain = SUPPORTED_CHAINS
+        .may_load(deps.storage, chain_key(chain_id))?
+        .ok_or(ContractError::UnsupportedChain {
+            chain_id: chain_id.to_string(),
+        })?;
+    let dst_token = chain
+        .address_format
+        .normalize(&dst_token)
+        .ok_or(ContractError::InvalidCounterpartAddress { address: dst_token })?;
+
+    let token = SupportedToken {
+        chain_id,
+        src_token: src_token.clone(),
+        dst_token,
+    };
+    SUPPORTED_TOKENS.save(deps.storage, (chain_key(chain_id), src_token), &token)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "register_token")
+        .add_attribute("chain_id", chain_id.to_string())
+        .add_attribute("src_token", token.src_token)
+        .add_attribute("dst_token", token.dst_token))
+}
+
+/// Drops a token pair. Only the owner may call this.
+pub fn remove_token(
+    deps: DepsMut,
+    info: MessageInfo,
+    chain_id: Uint256,
+    src_token: String,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    ensure_owner(deps.storage, &info)?;
+    SUPPORTED_TOKENS.remove(deps.storage, (chain_key(chain_id), src_token.clone()));
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_token")
+        .add_attribute("chain_id", chain_id.to_string())
+        .add_attribute("src_token", src_token))
+}
+
+fn ensure_owner(storage: &dyn Storage, info: &MessageInfo) -> Result<(), ContractError> {
+    let config = CONFIG.load(storage)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// Checks that the counterpart chain and token an order swaps into are supported
+fn validate_counterpart(
+    storage: &dyn Storage,
+    src_token: &Addr,
+    extra_data: &EscrowExtraData,
+) -> Result<(), ContractError> {
+    let chain_id = extra_data.dst_chain_id;
+    let chain = SUPPORTED_CHAINS
+        .may_load(storage, chain_key(chain_id))?
+        .ok_or(ContractError::UnsupportedChain {
+            chain_id: chain_id.to_string(),
+        })?;
+    let dst_token = chain
+        .address_format
+        .from_word(&extra_data.dst_token)
+        .ok_or(ContractError::InvalidCounterpartAddress {
+            address: extra_data.dst_token.clone(),
+        })?;
+
+    let supported =
+        SUPPORTED_TOKENS.may_load(storage, (chain_key(chain_id), src_token.to_string()))?;
+    if supported.is_none_or(|token| token.dst_token != dst_token) {
+        return Err(ContractError::UnsupportedToken {
+            chain_id: chain_id.to_string(),
+            token: src_token.to_string(),
+            dst_token,
+        });
+    }
+    Ok(())
+}
+
+/// Whether the sweep closes an escrow: once it has expired or, for a destination escrow
+/// that was opened unfunded, once its funding deadline has passed
+fn sweepable(escrow: &EscrowState, now: Timestamp) -> bool {
+    let unfunded_past_deadline = escrow.side == EscrowSide::Dst
+        && !escrow.funded
+        && now > funding_deadline(&escrow.immutables, escrow.deployed_at);
+    escrow.status == EscrowStatus::Active
+        && (now >= escrow.timelocks().expiry(escrow.side) || unfunded_past_deadline)
+}
+
+/// Last moment a destination escrow opened over IBC can be funded: the start of its
+/// withdrawal stage, when the maker may start relying on it to reveal the secret
+fn funding_deadline(immutables: &Immutables, deployed_at: Timestamp) -> Timestamp {
+    Timelocks::new(immutables.timelocks, deployed_at).get(Stage::DstWithdrawal)
+}
+
+/// Addresses of up to `limit` escrows of `side` after `start_after`, in order
+fn escrow_addresses(
+    storage: &dyn Storage,
+    side: EscrowSide,
+    start_after: Option<String>,
+    limit: usize,
+) -> StdResult<Vec<String>> {
+    let start = start_after.map(Bound::exclusive);
+    match side {
+        EscrowSide::Src => SRC_ESCROWS
+            .keys(storage, start, None, cosmwasm_std::Order::Ascending)
+            .take(limit)
+            .collect(),
+        EscrowSide::Dst => DST_ESCROWS
+            .keys(storage, start, None, cosmwasm_std::Order::Ascending)
+            .take(limit)
+            .collect(),
+    }
+}
+
+fn escrow_exists(storage: &dyn Storage, escrow_address: &str) -> bool {
+    DST_ESCROWS.has(storage, escrow_address.to_string())
+        || SRC_ESCROWS.has(storage, escrow_address.to_string())
+}
+
+/// Checks the fill against the order. As with the limit order protocol's post interaction,
+/// `remaining_making_amount` is what was left of the order before this fill.
+fn validate_fill_amounts(escrow_data: &SrcEscrowData) -> Result<(), ContractError> {
+    let making_amount = escrow_data.making_amount;
+    let remaining_making_amount = escrow_data.remaining_making_amount;
+    let order_making_amount = escrow_data.order.making_amount;
+
+    if making_amount.is_zero() {
+        return Err(ContractError::EmptyFill {});
+    }
+    if remaining_making_amount > order_making_amount {
+        return Err(ContractError::RemainingExceedsOrder {
+            remaining_making_amount,
+            order_making_amount,
+        });
+    }
+    if making_amount > remaining_making_amount {
+        return Err(ContractError::FillExceedsRemaining {
+            making_amount,
+            remaining_making_amount,
+        });
+    }
+    Ok(())
+}
+
+/// Moves a stored escrow to its next status. This is the only place statuses change.
+fn set_status(
+    storage: &mut dyn Storage,
+    escrow_address: &str,
+    escrow: &EscrowState,
+    next: EscrowStatus,
+) -> Result<(), ContractError> {
+    let status = escrow.status.transition(next)?;
+    // The stats can fail to update, so they go first and a failure leaves the escrow as it was
+    record_closed(storage, escrow, status)?;
+    match escrow.side {
+        EscrowSide::Dst => {
+            DST_ESCROWS.update(storage, escrow_address.to_string(), |stored| {
+                let mut stored = stored.ok_or(ContractError::EscrowNotFound {})?;
+                stored.status = status;
+                Ok::<_, ContractError>(stored)
+            })?;
+        }
+        EscrowSide::Src => {
+            SRC_ESCROWS.update(storage, escrow_address.to_string(), |stored| {
+                let mut stored = stored.ok_or(ContractError::EscrowNotFound {})?;
+                stored.status = status;
+                Ok::<_, ContractError>(stored)
+            })?;
+        }
+    }
+    Ok(())
+}
+
+fn settle_withdrawal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    escrow_address: String,
+    escrow: EscrowState,
+    secret: String,
+    action: EscrowAction,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    verify_secret(&secret, &escrow.immutables.hashlock)?;
+
+    // The source escrow pays the taker, the destination escrow pays the maker
+    let immutables = &escrow.immutables;
+    let recipient = match escrow.side {
+        EscrowSide::Src => &immutables.taker,
+        EscrowSide::Dst => &immutables.maker,
+    };
+    let config = CONFIG.load(deps.storage)?;
+    let fees = withdrawal_fees(deps.storage, &escrow_address, &escrow)?;
+    let messages = payout(
+        &deps.querier,
+        &escrow,
+        &config.safety_deposit_denom,
+        recipient,
+        &info.sender,
+        &fees.as_ref().map(FeeSplit::transfers).unwrap_or_default(),
+    )?;
+
+    // Nothing is written until the payout is known, so a failed withdrawal leaves no state
+    set_status(
+        deps.storage,
+        &escrow_address,
+        &escrow,
+        EscrowStatus::Withdrawn,
+    )?;
+
+    // Keep the secret so the counterpart leg can be unlocked without scraping events
+    let revealed = RevealedSecret {
+        secret: secret.clone(),
+        escrow_address: escrow_address.clone(),
+        height: env.block.height,
+        time: env.block.time,
+    };
+    REVEALED_SECRETS.save(
+        deps.storage,
+        escrow.immutables.hashlock.to_lowercase(),
+        &revealed,
+    )?;
+
+    let event = EscrowEvent::Withdrawn {
+        escrow_address: escrow_address.clone(),
+        side: escrow.side,
+        secret,
+        recipient: recipient.clone(),
+        amount: immutables.amount - fees.as_ref().map_or(Uint128::zero(), FeeSplit::total),
+        caller: info.sender.clone(),
+    };
+    let mut events = settlement_events(event, &escrow_address, &escrow, &info, action);
+    if let Some(fees) = fees {
+        events.push(
+            EscrowEvent::FeesCharged {
+                escrow_address: escrow_address.clone(),
+                token: immutables.token.clone(),
+                protocol_fee: fees.protocol_fee,
+                fee_collector: fees.fee_collector,
+                integrator_fee: fees.integrator_fee,
+                integrator: fees.integrator,
+            }
+            .into(),
+        );
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_events(events)
+        .add_attribute("action", action.as_str())
+        .add_attribute("escrow_address", escrow_address))
+}
+
+/// Fees deducted from a withdrawal, split between the protocol and the order's integrator
+struct FeeSplit {
+    protocol_fee: Uint128,
+    fee_collector: Addr,
+    integrator_fee: Uint128,
+    integrator: Option<Addr>,
+}
+
+impl FeeSplit {
+    fn total(&self) -> Uint128 {
+        self.protocol_fee + self.integrator_fee
+    }
+
+    fn transfers(&self) -> Vec<(Addr, Uint128)> {
+        let mut transfers = vec![(self.fee_collector.clone(), self.protocol_fee)];
+        if let Some(integrator) = &self.integrator {
+            transfers.push((integrator.clone(), self.integrator_fee));
+        }
+        transfers
+    }
+}
+
+/// Fees owed on withdrawing an escrow. Only funded source escrows pay fees, on the terms
+/// of the fee config in force when they were created.
+fn withdrawal_fees(
+    storage: &dyn Storage,
+    escrow_address: &str,
+    escrow: &EscrowState,
+) -> Result<Option<FeeSplit>, ContractError> {
+    if escrow.side != EscrowSide::Src || !escrow.funded {
+        return Ok(None);
+    }
+    let stored = SRC_ESCROWS.load(storage, escrow_address.to_string())?;
+    let Some(fee_config) = stored.fee_config else {
+        return Ok(None);
+    };
+    let fee = escrow
+        .immutables
+        .amount
+        .multiply_ratio(fee_config.fee_bps, BPS_DENOMINATOR);
+    if fee.is_zero() {
+        return Ok(None);
+    }
+
+    // The integrator was validated when the escrow was created
+    let integrator = Extension::decode(&stored.escrow_data.extension)?
+        .integrator()?
+        .map(Addr::unchecked);
+    let integrator_fee = match integrator {
+        Some(_) => fee.multiply_ratio(fee_config.integrator_share_bps, BPS_DENOMINATOR),
+        None => Uint128::zero(),
+    };
+
+    Ok(Some(FeeSplit {
+        protocol_fee: fee - integrator_fee,
+        fee_collector: fee_config.fee_collector,
+        integrator_fee,
+        integrator,
+    }))
+}
+
+fn settle_cancellation(
+    deps: DepsMut,
+    info: MessageInfo,
+    escrow_address: String,
+    escrow: EscrowState,
+    action: EscrowAction,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    // Funds go back to whoever locked them
+    let immutables = &escrow.immutables;
+    let recipient = match escrow.side {
+        EscrowSide::Src => &immutables.maker,
+        EscrowSide::Dst => &immutables.taker,
+    };
+    let config = CONFIG.load(deps.storage)?;
+    let messages = payout(
+        &deps.querier,
+        &escrow,
+        &config.safety_deposit_denom,
+        recipient,
+        &info.sender,
+        &[],
+    )?;
+    set_status(
+        deps.storage,
+        &escrow_address,
+        &escrow,
+        EscrowStatus::Cancelled,
+    )?;
+
+    let event = EscrowEvent::Cancelled {
+        escrow_address: escrow_address.clone(),
+        side: escrow.side,
+        recipient: recipient.clone(),
+        amount: immutables.amount,
+        caller: info.sender.clone(),
+    };
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_events(settlement_events(
+            event,
+            &escrow_address,
+            &escrow,
+            &info,
+            action,
+        ))
+        .add_attribute("action", action.as_str())
+        .add_attribute("escrow_address", escrow_address))
+}
+
+/// Settlement event, followed by a public action event when anyone could have settled
+fn settlement_events(
+    event: EscrowEvent,
+    escrow_address: &str,
+    escrow: &EscrowState,
+    info: &MessageInfo,
+    action: EscrowAction,
+) -> Vec<Event> {
+    let mut events = vec![event.into()];
+    if action.caller() == ActionCaller::Anyone {
+        events.push(
+            EscrowEvent::PublicAction {
+                escrow_address: escrow_address.to_string(),
+                side: escrow.side,
+                action,
+                caller: info.sender.clone(),
+                safety_deposit: escrow.immutables.safety_deposit,
+            }
+            .into(),
+        );
+    }
+    events
+}
+
+/// Checks that the sender may call `action` on the escrow at the current block time
+fn ensure_callable(
+    storage: &dyn Storage,
+    env: &Env,
+    info: &MessageInfo,
+    escrow: &EscrowState,
+    action: EscrowAction,
+) -> Result<(), ContractError> {
+    if action.caller() == ActionCaller::Taker && info.sender != escrow.immutables.taker {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Escrows opened over IBC hold nothing until their taker funds them
+    if !escrow.funded {
+        return Err(ContractError::EscrowNotFunded {});
+    }
+
+    let config = CONFIG.load(storage)?;
+    let window = escrow
+        .timelocks()
+        .window(escrow.side, action, config.rescue_delay)
+        .ok_or(ContractError::UnsupportedAction {})?;
+    if !window.contains(env.block.time) {
+        return Err(ContractError::InvalidTime {});
+    }
+    Ok(())
+}
+
+/// Checks that rescue opens only after the last cancellation stage of `side` has started,
+/// so the taker can never rescue funds that cancellation still owes back
+fn ensure_rescue_after_cancellation(
+    timelocks: Uint256,
+    side: EscrowSide,
+    rescue_delay: u64,
+) -> Result<(), ContractError> {
+    check_rescue_delay(rescue_delay, cancellation_offset(timelocks, side))
+}
+
+/// Checks a configured rescue delay against the latest cancellation of any escrow created
+/// so far. Without any the delay must still be non-zero, otherwise no escrow could ever be
+/// created.
+pub(crate) fn validate_rescue_delay(
+    storage: &dyn Storage,
+    rescue_delay: u64,
+) -> Result<(), ContractError> {
+    let cancellation = MAX_CANCELLATION_OFFSET
+        .may_load(storage)?
+        .unwrap_or_default();
+    check_rescue_delay(rescue_delay, cancellation)
+}
+
+fn cancellation_offset(timelocks: Uint256, side: EscrowSide) -> u64 {
+    Timelocks::new(timelocks, Timestamp::from_seconds(0)).expiry_offset(side) as u64
+}
+
+fn check_rescue_delay(rescue_delay: u64, cancellation: u64) -> Result<(), ContractError> {
+    if rescue_delay <= cancellation {
+        return Err(ContractError::RescueDelayTooShort {
+            rescue_delay,
+            cancellation,
+        });
+    }
+    Ok(())
+}
+
+fn verify_secret(secret: &str, hashlock: &str) -> Result<(), ContractError> {
+    let secret = decode_hex(secret).ok_or(ContractError::InvalidSecret {})?;
+    let hashlock = decode_hex(hashlock).ok_or(ContractError::InvalidSecret {})?;
+    if Keccak256::digest(secret).as_slice() != hashlock.as_slice() {
+        return Err(ContractError::InvalidSecret {});
+    }
+    Ok(())
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    hex::decode(value.strip_prefix("0x").unwrap_or(value)).ok()
+}
+
+/// Coins attached to lock an escrow: the escrowed amount and the safety deposit. A CW20
+/// token is pulled from the sender's allowance instead, leaving only the deposit.
+pub fn locked_funds(immutables: &Immutables, safety_deposit_denom: &str, cw20: bool) -> Vec<Coin> {
+    let mut funds = vec![Coin::new(
+        immutables.safety_deposit.u128(),
+        safety_deposit_denom,
+    )];
+    if !cw20 {
+        funds.insert(
+            0,
+            Coin::new(immutables.amount.u128(), immutables.token.as_str()),
+        );
+    }
+    funds
+}
+
+/// Checks the coins attached to lock an escrow, and returns the transfer pulling its token
+/// into the contract if it is a CW20 one
+fn lock_funds<C: CustomQuery>(
+    querier: &QuerierWrapper<C>,
+    env: &Env,
+    info: &MessageInfo,
+    immutables: &Immutables,
+    safety_deposit_denom: &str,
+) -> Result<Option<WasmMsg>, ContractError> {
+    let cw20 = is_cw20(querier, &immutables.token);
+    assert_funds(info, &locked_funds(immutables, safety_deposit_denom, cw20))?;
+    if !cw20 || immutables.amount.is_zero() {
+        return Ok(None);
+    }
+
+    let msg = Cw20ExecuteMsg::TransferFrom {
+        owner: info.sender.to_string(),
+        recipient: env.contract.address.to_string(),
+        amount: immutables.amount,
+    };
+    Ok(Some(WasmMsg::Execute {
+        contract_addr: immutables.token.to_string(),
+        msg: to_json_binary(&msg)?,
+        funds: vec![],
+    }))
+}
+
+/// Whether an escrowed token is a CW20 contract rather than a native denom, which is never
+/// a contract address
+fn is_cw20<C: CustomQuery>(querier: &QuerierWrapper<C>, token: &Addr) -> bool {
+    querier.query_wasm_contract_info(token).is_ok()
+}
+
+/// Sends `amount` of an escrowed token to `to`
+fn send_token(
+    token: &Addr,
+    cw20: bool,
+    to: &Addr,
+    amount: Uint128,
+) -> StdResult<CosmosMsg<NeutronMsg>> {
+    if !cw20 {
+        return Ok(BankMsg::Send {
+            to_address: to.to_string(),
+            amount: vec![Coin::new(amount.u128(), token.as_str())],
+        }
+        .into());
+    }
+
+    let msg = Cw20ExecuteMsg::Transfer {
+        recipient: to.to_string(),
+        amount,
+    };
+    Ok(WasmMsg::Execute {
+        contract_addr: token.to_string(),
+        msg: to_json_binary(&msg)?,
+        funds: vec![],
+    }
+    .into())
+}
+
+/// Checks that exactly the expected coins were attached, in any order
+fn assert_funds(info: &MessageInfo, expected: &[Coin]) -> Result<(), ContractError> {
+    let mut expected_totals: BTreeMap<&str, Uint128> = BTreeMap::new();
+    for coin in expected.iter().filter(|coin| !coin.amount.is_zero()) {
+        *expected_totals.entry(coin.denom.as_str()).or_default() += coin.amount;
+    }
+    let mut received_totals: BTreeMap<&str, Uint128> = BTreeMap::new();
+    for coin in info.funds.iter().filter(|coin| !coin.amount.is_zero()) {
+        *received_totals.entry(coin.denom.as_str()).or_default() += coin.amount;
+    }
+
+    if expected_totals != received_totals {
+        let expected = expected_totals
+            .iter()
+            .map(|(denom, amount)| format!("{}{}", amount, denom))
+            .collect::<Vec<_>>()
+            .join(",");
+        return Err(ContractError::InvalidFunds { expected });
+    }
+    Ok(())
+}
+
+/// Sends the escrowed amount less `fees` to `recipient`, each fee to its collector and the
+/// safety deposit to `caller`. Escrows that never locked funds pay nothing out.
+fn payout<C: CustomQuery>(
+    querier: &QuerierWrapper<C>,
+    escrow: &EscrowState,
+    safety_deposit_denom: &str,
+    recipient: &Addr,
+    caller: &Addr,
+    fees: &[(Addr, Uint128)],
+) -> StdResult<Vec<CosmosMsg<NeutronMsg>>> {
+    if !escrow.funded {
+        return Ok(vec![]);
+    }
+
+    let immutables = &escrow.immutables;
+    let cw20 = is_cw20(querier, &immutables.token);
+    let total_fees: Uint128 = fees.iter().map(|(_, fee)| *fee).sum();
+    let mut transfers = vec![(recipient, immutables.amount - total_fees)];
+    transfers.extend(fees.iter().map(|(collector, fee)| (collector, *fee)));
+
+    let mut messages = transfers
+        .into_iter()
+        .filter(|(_, amount)| !amount.is_zero())
+        .map(|(to, amount)| send_token(&immutables.token, cw20, to, amount))
+        .collect::<StdResult<Vec<_>>>()?;
+    if !immutables.safety_deposit.is_zero() {
+        messages.push(
+            BankMsg::Send {
+                to_address: caller.to_string(),
+                amount: vec![Coin::new(
+                    immutables.safety_deposit.u128(),
+                    safety_deposit_denom,
+                )],
+            }
+            .into(),
+        );
+    }
+    Ok(messages)
+}
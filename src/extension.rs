@@ -0,0 +1,137 @@
+use cosmwasm_std::Binary;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ContractError;
+
+// Number of offset-addressed fields; custom data follows the last one
+const FIELD_COUNT: usize = 8;
+
+/// Limit order protocol extension of an order, split into its fields.
+///
+/// The encoded extension starts with a 32-byte word packing the end offsets of the eight
+/// dynamic fields, one big-endian `uint32` each with the first field in the lowest bits.
+/// The fields follow back to back, and whatever comes after the last one is custom data.
+/// An empty extension has every field empty.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Extension {
+    pub maker_asset_suffix: Binary,
+    pub taker_asset_suffix: Binary,
+    pub making_amount_data: Binary, // amount getter address, then its arguments
+    pub taking_amount_data: Binary, // same
+    pub predicate: Binary,
+    pub maker_permit: Binary,
+    pub pre_interaction_data: Binary,
+    pub post_interaction_data: Binary,
+    pub custom_data: Binary,
+}
+
+impl Extension {
+    pub fn decode(extension: &[u8]) -> Result<Self, ContractError> {
+        if extension.is_empty() {
+            return Ok(Extension::default());
+        }
+        if extension.len() < 32 {
+            return Err(ContractError::InvalidExtension {});
+        }
+
+        let (offsets, data) = extension.split_at(32);
+        let mut fields = Vec::with_capacity(FIELD_COUNT + 1);
+        let mut start = 0;
+        for index in 0..FIELD_COUNT {
+            let at = 28 - 4 * index;
+            let end = u32::from_be_bytes([
+                offsets[at],
+                offsets[at + 1],
+                offsets[at + 2],
+                offsets[at + 3],
+            ]) as usize;
+            let field = data
+                .get(start..end)
+                .ok_or(ContractError::InvalidExtension {})?;
+            fields.push(Binary::from(field));
+            start = end;
+        }
+        fields.push(Binary::from(&data[start..]));
+
+        let mut fields = fields.into_iter();
+        let mut next = || fields.next().unwrap_or_default();
+        Ok(Extension {
+            maker_asset_suffix: next(),
+            taker_asset_suffix: next(),
+            making_amount_data: next(),
+            taking_amount_data: next(),
+            predicate: next(),
+            maker_permit: next(),
+            pre_interaction_data: next(),
+            post_interaction_data: next(),
+            custom_data: next(),
+        })
+    }
+
+    /// Integrator fee recipient of the order, carried as a UTF-8 address in the custom data
+    pub fn integrator(&self) -> Result<Option<String>, ContractError> {
+        if self.custom_data.is_empty() {
+            return Ok(None);
+        }
+
+        String::from_utf8(self.custom_data.to_vec())
+            .map(Some)
+            .map_err(|_| ContractError::InvalidExtension {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes fields the way the limit order protocol packs them
+    fn encode(fields: &[&[u8]], custom_data: &[u8]) -> Vec<u8> {
+        let mut offsets = [0u8; 32];
+        let mut data = vec![];
+        for index in 0..FIELD_COUNT {
+            data.extend_from_slice(fields.get(index).copied().unwrap_or_default());
+            offsets[28 - 4 * index..32 - 4 * index]
+                .copy_from_slice(&(data.len() as u32).to_be_bytes());
+        }
+        data.extend_from_slice(custom_data);
+        [offsets.to_vec(), data].concat()
+    }
+
+    #[test]
+    fn decodes_every_field() {
+        let fields: [&[u8]; 8] = [b"a", b"bb", b"", b"dddd", b"e", b"", b"gg", b"h"];
+        let extension = Extension::decode(&encode(&fields, b"integrator")).unwrap();
+
+        assert_eq!(extension.maker_asset_suffix, Binary::from(b"a"));
+        assert_eq!(extension.taker_asset_suffix, Binary::from(b"bb"));
+        assert!(extension.making_amount_data.is_empty());
+        assert_eq!(extension.taking_amount_data, Binary::from(b"dddd"));
+        assert_eq!(extension.predicate, Binary::from(b"e"));
+        assert!(extension.maker_permit.is_empty());
+        assert_eq!(extension.pre_interaction_data, Binary::from(b"gg"));
+        assert_eq!(extension.post_interaction_data, Binary::from(b"h"));
+        assert_eq!(
+            extension.integrator().unwrap(),
+            Some("integrator".to_string())
+        );
+        assert_eq!(Extension::decode(&[]).unwrap(), Extension::default());
+    }
+
+    #[test]
+    fn rejects_bad_offsets() {
+        let mut encoded = encode(&[b"aa", b"bb"], b"");
+
+        // Offsets pointing past the end
+        encoded[31] = 0xff;
+        assert!(Extension::decode(&encoded).is_err());
+
+        // Offsets going backwards
+        encoded[31] = 2;
+        encoded[27] = 1;
+        assert!(Extension::decode(&encoded).is_err());
+
+        assert!(Extension::decode(&[0u8; 16]).is_err());
+    }
+}
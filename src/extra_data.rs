@@ -0,0 +1,154 @@
+use cosmwasm_std::{Timestamp, Uint128, Uint256};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ContractError;
+use crate::timelocks::{Stage, Timelocks};
+
+// Hashlock info, destination chain id, destination token, deposits and timelocks
+const EXTRA_DATA_LEN: usize = 5 * 32;
+
+/// Arguments the EVM `EscrowFactory` packs into an order's `extraData` for the source
+/// escrow, decoded from the five 32-byte words of `ExtraDataArgs`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct EscrowExtraData {
+    pub hashlock_info: String, // hashlock of the secret as hex string (e.g., "0x...")
+    pub dst_chain_id: Uint256,
+    pub dst_token: String, // 32-byte word as hex string, EVM addresses in the low 20 bytes
+    pub src_safety_deposit: Uint128,
+    pub dst_safety_deposit: Uint128,
+    pub timelocks: Uint256,
+}
+
+impl EscrowExtraData {
+    pub fn decode(extra_data: &[u8]) -> Result<Self, ContractError> {
+        if extra_data.len() != EXTRA_DATA_LEN {
+            return Err(ContractError::InvalidExtraDataLength {
+                expected: EXTRA_DATA_LEN,
+                actual: extra_data.len(),
+            });
+        }
+        let word = |index: usize| -> [u8; 32] {
+            let mut word = [0u8; 32];
+            word.copy_from_slice(&extra_data[32 * index..32 * (index + 1)]);
+            word
+        };
+
+        let hashlock_info = word(0);
+        if hashlock_info == [0u8; 32] {
+            return Err(ContractError::EmptyHashlock {});
+        }
+
+        // Source deposit in the high 128 bits, destination deposit in the low ones
+        let deposits = word(3);
+        let mut src_safety_deposit = [0u8; 16];
+        src_safety_deposit.copy_from_slice(&deposits[..16]);
+        let mut dst_safety_deposit = [0u8; 16];
+        dst_safety_deposit.copy_from_slice(&deposits[16..]);
+
+        let timelocks = Uint256::from_be_bytes(word(4));
+        validate_timelocks(timelocks)?;
+
+        Ok(EscrowExtraData {
+            hashlock_info: format!("0x{}", hex::encode(hashlock_info)),
+            dst_chain_id: Uint256::from_be_bytes(word(1)),
+            dst_token: format!("0x{}", hex::encode(word(2))),
+            src_safety_deposit: Uint128::new(u128::from_be_bytes(src_safety_deposit)),
+            dst_safety_deposit: Uint128::new(u128::from_be_bytes(dst_safety_deposit)),
+            timelocks,
+        })
+    }
+}
+
+/// Checks that the stages of each side start in order
+fn validate_timelocks(timelocks: Uint256) -> Result<(), ContractError> {
+    let timelocks = Timelocks::new(timelocks, Timestamp::from_seconds(0));
+    let sides: [&[Stage]; 2] = [
+        &[
+            Stage::SrcWithdrawal,
+            Stage::SrcPublicWithdrawal,
+            Stage::SrcCancellation,
+            Stage::SrcPublicCancellation,
+        ],
+        &[
+            Stage::DstWithdrawal,
+            Stage::DstPublicWithdrawal,
+            Stage::DstCancellation,
+        ],
+    ];
+
+    for stages in sides {
+        for pair in stages.windows(2) {
+            if timelocks.offset(pair[1]) < timelocks.offset(pair[0]) {
+                return Err(ContractError::InvalidTimelocks {
+                    earlier: pair[0],
+                    later: pair[1],
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn decodes_extra_data_args() {
+        let extra_data = testing::extra_data(
+            [0xab; 32],
+            (100, 50),
+            Timelocks::pack([10, 20, 30, 40, 5, 15, 25]),
+        );
+        let decoded = EscrowExtraData::decode(&extra_data).unwrap();
+
+        assert_eq!(decoded.hashlock_info, format!("0x{}", "ab".repeat(32)));
+        assert_eq!(decoded.dst_chain_id, Uint256::from(56u128));
+        assert_eq!(
+            decoded.dst_token,
+            format!("0x{}{}", "00".repeat(12), "dd".repeat(20))
+        );
+        assert_eq!(decoded.src_safety_deposit, Uint128::new(100));
+        assert_eq!(decoded.dst_safety_deposit, Uint128::new(50));
+        assert_eq!(
+            Timelocks::new(decoded.timelocks, Timestamp::from_seconds(0))
+                .offset(Stage::DstCancellation),
+            25
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_extra_data() {
+        let extra_data = testing::extra_data([0xab; 32], (100, 50), Timelocks::pack([0; 7]));
+        assert!(matches!(
+            EscrowExtraData::decode(&extra_data[..128]),
+            Err(ContractError::InvalidExtraDataLength {
+                expected: 160,
+                actual: 128
+            })
+        ));
+
+        let extra_data = testing::extra_data([0; 32], (100, 50), Timelocks::pack([0; 7]));
+        assert!(matches!(
+            EscrowExtraData::decode(&extra_data),
+            Err(ContractError::EmptyHashlock {})
+        ));
+
+        // Public cancellation before private cancellation
+        let extra_data = testing::extra_data(
+            [0xab; 32],
+            (100, 50),
+            Timelocks::pack([10, 20, 30, 25, 0, 0, 0]),
+        );
+        assert!(matches!(
+            EscrowExtraData::decode(&extra_data),
+            Err(ContractError::InvalidTimelocks {
+                earlier: Stage::SrcCancellation,
+                later: Stage::SrcPublicCancellation,
+            })
+        ));
+    }
+}
@@ -1,47 +1,618 @@
 use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{
-    to_json_binary, Addr, CosmosMsg, CustomQuery, Querier, QuerierWrapper, StdResult, WasmMsg,
-    WasmQuery,
+    to_json_binary, Addr, Binary, Coin, CustomQuery, QuerierWrapper, StdResult, Uint128, Uint256,
+    WasmMsg,
 };
 
-use crate::msg::{ExecuteMsg, GetCountResponse, QueryMsg};
+use crate::msg::{
+    AuctionQuoteResponse, BatchDstEscrow, BatchMode, BatchWithdrawal, ClaimableFundsResponse,
+    DstEscrowListResponse, DstEscrowResponse, EscrowStageResponse, EscrowsResponse, ExecuteMsg,
+    ExtensionResponse, FeeConfigResponse, HashlockEscrowResponse, OrderBy, QueryMsg,
+    RelayedSecretResponse, RevealedSecretResponse, SecretRelayResponse, SrcEscrowListResponse,
+    SrcEscrowResponse, StatsResponse, SupportedChainsResponse, SupportedTokensResponse,
+    SweepConfigResponse,
+};
+use crate::state::{AddressFormat, EscrowSide, EscrowStatus, Immutables, Order};
 
-/// CwTemplateContract is a wrapper around Addr that provides a lot of helpers
-/// for working with this.
+/// Typed client of a deployed escrow contract, for other contracts and multi-test suites.
+/// Execute builders return the `WasmMsg` to send, with the given funds attached; queries
+/// go through any querier.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
-pub struct CwTemplateContract(pub Addr);
+pub struct EscrowContract(pub Addr);
 
-impl CwTemplateContract {
+impl EscrowContract {
     pub fn addr(&self) -> Addr {
         self.0.clone()
     }
 
-    pub fn call<T: Into<ExecuteMsg>>(&self, msg: T) -> StdResult<CosmosMsg> {
-        let msg = to_json_binary(&msg.into())?;
+    /// Executes any message, with `funds` attached
+    pub fn call(&self, msg: ExecuteMsg, funds: Vec<Coin>) -> StdResult<WasmMsg> {
         Ok(WasmMsg::Execute {
-            contract_addr: self.addr().into(),
-            msg,
-            funds: vec![],
-        }
-        .into())
-    }
-
-    /// Get Count
-    pub fn count<Q, T, CQ>(&self, querier: &Q) -> StdResult<GetCountResponse>
-    where
-        Q: Querier,
-        T: Into<String>,
-        CQ: CustomQuery,
-    {
-        let msg = QueryMsg::GetCount {};
-        let query = WasmQuery::Smart {
             contract_addr: self.addr().into(),
             msg: to_json_binary(&msg)?,
-        }
-        .into();
-        let res: GetCountResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
-        Ok(res)
+            funds,
+        })
+    }
+
+    fn query<C: CustomQuery, T: DeserializeOwned>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        msg: &QueryMsg,
+    ) -> StdResult<T> {
+        querier.query_wasm_smart(self.addr(), msg)
+    }
+
+    // Escrow lifecycle
+
+    /// `funds` are the escrowed amount of the token plus the safety deposit
+    pub fn create_dst_escrow(
+        &self,
+        escrow_address: impl Into<String>,
+        immutables: Immutables,
+        timestamp: Uint128,
+        funds: Vec<Coin>,
+    ) -> StdResult<WasmMsg> {
+        let msg = ExecuteMsg::CreateDstEscrow {
+            escrow_address: escrow_address.into(),
+            immutables,
+            timestamp,
+        };
+        self.call(msg, funds)
+    }
+
+    /// `funds` are the making amount of the maker asset plus the source safety deposit
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_src_escrow(
+        &self,
+        escrow_address: impl Into<String>,
+        order: Order,
+        extension: Binary,
+        order_hash: impl Into<String>,
+        taker: impl Into<String>,
+        making_amount: Uint128,
+        taking_amount: Uint128,
+        remaining_making_amount: Uint128,
+        extra_data: Binary,
+        funds: Vec<Coin>,
+    ) -> StdResult<WasmMsg> {
+        let msg = ExecuteMsg::CreateSrcEscrow {
+            escrow_address: escrow_address.into(),
+            order,
+            extension,
+            order_hash: order_hash.into(),
+            taker: taker.into(),
+            making_amount,
+            taking_amount,
+            remaining_making_amount,
+            extra_data,
+        };
+        self.call(msg, funds)
+    }
+
+    pub fn withdraw(
+        &self,
+        escrow_address: impl Into<String>,
+        secret: impl Into<String>,
+    ) -> StdResult<WasmMsg> {
+        let msg = ExecuteMsg::Withdraw {
+            escrow_address: escrow_address.into(),
+            secret: secret.into(),
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn public_withdraw(
+        &self,
+        escrow_address: impl Into<String>,
+        secret: impl Into<String>,
+    ) -> StdResult<WasmMsg> {
+        let msg = ExecuteMsg::PublicWithdraw {
+            escrow_address: escrow_address.into(),
+            secret: secret.into(),
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn cancel(&self, escrow_address: impl Into<String>) -> StdResult<WasmMsg> {
+        let msg = ExecuteMsg::Cancel {
+            escrow_address: escrow_address.into(),
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn public_cancel(&self, escrow_address: impl Into<String>) -> StdResult<WasmMsg> {
+        let msg = ExecuteMsg::PublicCancel {
+            escrow_address: escrow_address.into(),
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn rescue(&self, escrow_address: impl Into<String>) -> StdResult<WasmMsg> {
+        let msg = ExecuteMsg::Rescue {
+            escrow_address: escrow_address.into(),
+        };
+        self.call(msg, vec![])
+    }
+
+    /// `funds` cover every escrow of the batch
+    pub fn batch_create_dst_escrows(
+        &self,
+        escrows: Vec<BatchDstEscrow>,
+        mode: BatchMode,
+        funds: Vec<Coin>,
+    ) -> StdResult<WasmMsg> {
+        self.call(ExecuteMsg::BatchCreateDstEscrows { escrows, mode }, funds)
+    }
+
+    pub fn batch_withdraw(
+        &self,
+        withdrawals: Vec<BatchWithdrawal>,
+        mode: BatchMode,
+    ) -> StdResult<WasmMsg> {
+        self.call(ExecuteMsg::BatchWithdraw { withdrawals, mode }, vec![])
+    }
+
+    pub fn batch_cancel(
+        &self,
+        escrow_addresses: Vec<String>,
+        mode: BatchMode,
+    ) -> StdResult<WasmMsg> {
+        let msg = ExecuteMsg::BatchCancel {
+            escrow_addresses,
+            mode,
+        };
+        self.call(msg, vec![])
+    }
+
+    // Owner administration
+
+    pub fn update_fee_config(
+        &self,
+        fee_bps: u16,
+        integrator_share_bps: u16,
+        fee_collector: impl Into<String>,
+    ) -> StdResult<WasmMsg> {
+        let msg = ExecuteMsg::UpdateFeeConfig {
+            fee_bps,
+            integrator_share_bps,
+            fee_collector: fee_collector.into(),
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn update_sweep_config(&self, batch_size: u32) -> StdResult<WasmMsg> {
+        self.call(ExecuteMsg::UpdateSweepConfig { batch_size }, vec![])
+    }
+
+    pub fn register_chain(
+        &self,
+        chain_id: Uint256,
+        address_format: AddressFormat,
+        factory: impl Into<String>,
+        connection_id: Option<String>,
+    ) -> StdResult<WasmMsg> {
+        let msg = ExecuteMsg::RegisterChain {
+            chain_id,
+            address_format,
+            factory: factory.into(),
+            connection_id,
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn remove_chain(&self, chain_id: Uint256) -> StdResult<WasmMsg> {
+        self.call(ExecuteMsg::RemoveChain { chain_id }, vec![])
+    }
+
+    pub fn register_token(
+        &self,
+        chain_id: Uint256,
+        src_token: impl Into<String>,
+        dst_token: impl Into<String>,
+    ) -> StdResult<WasmMsg> {
+        let msg = ExecuteMsg::RegisterToken {
+            chain_id,
+            src_token: src_token.into(),
+            dst_token: dst_token.into(),
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn remove_token(
+        &self,
+        chain_id: Uint256,
+        src_token: impl Into<String>,
+    ) -> StdResult<WasmMsg> {
+        let msg = ExecuteMsg::RemoveToken {
+            chain_id,
+            src_token: src_token.into(),
+        };
+        self.call(msg, vec![])
+    }
+
+    pub fn update_secret_relay(&self, channel_id: Option<String>) -> StdResult<WasmMsg> {
+        self.call(ExecuteMsg::UpdateSecretRelay { channel_id }, vec![])
+    }
+
+    // Cross-chain
+
+    pub fn request_dst_escrow(
+        &self,
+        src_escrow_address: impl Into<String>,
+        channel_id: impl Into<String>,
+        escrow_address: impl Into<String>,
+        immutables: Immutables,
+    ) -> StdResult<WasmMsg> {
+        let msg = ExecuteMsg::RequestDstEscrow {
+            src_escrow_address: src_escrow_address.into(),
+            channel_id: channel_id.into(),
+            escrow_address: escrow_address.into(),
+            immutables,
+        };
+        self.call(msg, vec![])
+    }
+
+    /// `funds` are the escrowed amount of the token plus the safety deposit
+    pub fn fund_dst_escrow(
+        &self,
+        escrow_address: impl Into<String>,
+        funds: Vec<Coin>,
+    ) -> StdResult<WasmMsg> {
+        let msg = ExecuteMsg::FundDstEscrow {
+            escrow_address: escrow_address.into(),
+        };
+        self.call(msg, funds)
+    }
+
+    pub fn verify_counterpart(
+        &self,
+        escrow_address: impl Into<String>,
+        chain_id: Uint256,
+        counterpart_address: impl Into<String>,
+    ) -> StdResult<WasmMsg> {
+        let msg = ExecuteMsg::VerifyCounterpart {
+            escrow_address: escrow_address.into(),
+            chain_id,
+            counterpart_address: counterpart_address.into(),
+        };
+        self.call(msg, vec![])
+    }
+
+    /// What ibc-hooks executes for a transfer whose memo wraps `msg`; `funds` are the
+    /// transferred coins
+    pub fn ibc_hook(
+        &self,
+        channel: impl Into<String>,
+        original_sender: impl Into<String>,
+        recovery_address: impl Into<String>,
+        msg: ExecuteMsg,
+        funds: Vec<Coin>,
+    ) -> StdResult<WasmMsg> {
+        let msg = ExecuteMsg::IbcHook {
+            channel: channel.into(),
+            original_sender: original_sender.into(),
+            recovery_address: recovery_address.into(),
+            msg: Box::new(msg),
+        };
+        self.call(msg, funds)
+    }
+
+    pub fn claim_funds(&self) -> StdResult<WasmMsg> {
+        self.call(ExecuteMsg::ClaimFunds {}, vec![])
+    }
+
+    // Queries
+
+    pub fn dst_escrow<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        escrow_address: impl Into<String>,
+    ) -> StdResult<DstEscrowResponse> {
+        let msg = QueryMsg::GetDstEscrow {
+            escrow_address: escrow_address.into(),
+        };
+        self.query(querier, &msg)
+    }
+
+    pub fn src_escrow<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        escrow_address: impl Into<String>,
+    ) -> StdResult<SrcEscrowResponse> {
+        let msg = QueryMsg::GetSrcEscrow {
+            escrow_address: escrow_address.into(),
+        };
+        self.query(querier, &msg)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn list_dst_escrows<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        start_after: Option<String>,
+        end_before: Option<String>,
+        limit: Option<u32>,
+        order_by: Option<OrderBy>,
+        status: Option<EscrowStatus>,
+        token: Option<String>,
+        maker: Option<String>,
+    ) -> StdResult<DstEscrowListResponse> {
+        let msg = QueryMsg::ListDstEscrows {
+            start_after,
+            end_before,
+            limit,
+            order_by,
+            status,
+            token,
+            maker,
+        };
+        self.query(querier, &msg)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn list_src_escrows<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        start_after: Option<String>,
+        end_before: Option<String>,
+        limit: Option<u32>,
+        order_by: Option<OrderBy>,
+        status: Option<EscrowStatus>,
+        token: Option<String>,
+        maker: Option<String>,
+    ) -> StdResult<SrcEscrowListResponse> {
+        let msg = QueryMsg::ListSrcEscrows {
+            start_after,
+            end_before,
+            limit,
+            order_by,
+            status,
+            token,
+            maker,
+        };
+        self.query(querier, &msg)
+    }
+
+    pub fn escrows_by_maker<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        side: EscrowSide,
+        maker: impl Into<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<EscrowsResponse> {
+        let msg = QueryMsg::EscrowsByMaker {
+            side,
+            maker: maker.into(),
+            start_after,
+            limit,
+        };
+        self.query(querier, &msg)
+    }
+
+    pub fn escrows_by_taker<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        side: EscrowSide,
+        taker: impl Into<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<EscrowsResponse> {
+        let msg = QueryMsg::EscrowsByTaker {
+            side,
+            taker: taker.into(),
+            start_after,
+            limit,
+        };
+        self.query(querier, &msg)
+    }
+
+    pub fn escrows_by_order_hash<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        side: EscrowSide,
+        order_hash: impl Into<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<EscrowsResponse> {
+        let msg = QueryMsg::EscrowByOrderHash {
+            side,
+            order_hash: order_hash.into(),
+            start_after,
+            limit,
+        };
+        self.query(querier, &msg)
+    }
+
+    pub fn escrow_by_hashlock<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        hashlock: impl Into<String>,
+    ) -> StdResult<HashlockEscrowResponse> {
+        let msg = QueryMsg::EscrowByHashlock {
+            hashlock: hashlock.into(),
+        };
+        self.query(querier, &msg)
+    }
+
+    pub fn escrow_stage<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        escrow_address: impl Into<String>,
+    ) -> StdResult<EscrowStageResponse> {
+        let msg = QueryMsg::GetEscrowStage {
+            escrow_address: escrow_address.into(),
+        };
+        self.query(querier, &msg)
+    }
+
+    pub fn revealed_secret<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        hashlock: impl Into<String>,
+    ) -> StdResult<RevealedSecretResponse> {
+        let msg = QueryMsg::GetRevealedSecret {
+            hashlock: hashlock.into(),
+        };
+        self.query(querier, &msg)
+    }
+
+    pub fn fee_config<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+    ) -> StdResult<FeeConfigResponse> {
+        self.query(querier, &QueryMsg::GetFeeConfig {})
+    }
+
+    pub fn sweep_config<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+    ) -> StdResult<SweepConfigResponse> {
+        self.query(querier, &QueryMsg::GetSweepConfig {})
+    }
+
+    pub fn quote_auction_rate<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        order: Order,
+        extension: Binary,
+    ) -> StdResult<AuctionQuoteResponse> {
+        self.query(querier, &QueryMsg::QuoteAuctionRate { order, extension })
+    }
+
+    pub fn decode_extension<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        extension: Binary,
+    ) -> StdResult<ExtensionResponse> {
+        self.query(querier, &QueryMsg::DecodeExtension { extension })
+    }
+
+    pub fn supported_chains<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        start_after: Option<Uint256>,
+        limit: Option<u32>,
+    ) -> StdResult<SupportedChainsResponse> {
+        self.query(
+            querier,
+            &QueryMsg::ListSupportedChains { start_after, limit },
+        )
+    }
+
+    pub fn supported_tokens<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        chain_id: Uint256,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<SupportedTokensResponse> {
+        let msg = QueryMsg::ListSupportedTokens {
+            chain_id,
+            start_after,
+            limit,
+        };
+        self.query(querier, &msg)
+    }
+
+    pub fn secret_relay<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+    ) -> StdResult<SecretRelayResponse> {
+        self.query(querier, &QueryMsg::GetSecretRelay {})
+    }
+
+    pub fn relayed_secret<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        hashlock: impl Into<String>,
+    ) -> StdResult<RelayedSecretResponse> {
+        let msg = QueryMsg::GetRelayedSecret {
+            hashlock: hashlock.into(),
+        };
+        self.query(querier, &msg)
+    }
+
+    pub fn claimable_funds<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        address: impl Into<String>,
+    ) -> StdResult<ClaimableFundsResponse> {
+        let msg = QueryMsg::GetClaimableFunds {
+            address: address.into(),
+        };
+        self.query(querier, &msg)
+    }
+
+    pub fn stats<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        asset: Option<String>,
+    ) -> StdResult<StatsResponse> {
+        self.query(querier, &QueryMsg::GetStats { asset })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockQuerier;
+    use cosmwasm_std::{
+        coins, from_json, ContractResult, Empty, SystemError, SystemResult, WasmQuery,
+    };
+
+    use crate::state::SweepConfig;
+
+    #[test]
+    fn builds_messages_with_funds() {
+        let contract = EscrowContract(Addr::unchecked("escrow_contract"));
+
+        let msg = contract
+            .fund_dst_escrow("cosmos1escrow", coins(1000, "token"))
+            .unwrap();
+        let WasmMsg::Execute {
+            contract_addr,
+            msg,
+            funds,
+        } = msg
+        else {
+            panic!("expected an execute message");
+        };
+        assert_eq!(contract_addr, "escrow_contract");
+        assert_eq!(funds, coins(1000, "token"));
+        assert_eq!(
+            from_json::<ExecuteMsg>(&msg).unwrap(),
+            ExecuteMsg::FundDstEscrow {
+                escrow_address: "cosmos1escrow".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn queries_the_contract() {
+        let mut querier = MockQuerier::<Empty>::new(&[]);
+        querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "escrow_contract" => {
+                assert_eq!(
+                    from_json::<QueryMsg>(msg).unwrap(),
+                    QueryMsg::GetSweepConfig {}
+                );
+                let response = SweepConfigResponse {
+                    sweep_config: SweepConfig { batch_size: 5 },
+                };
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&response).unwrap()))
+            }
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "wasm".to_string(),
+            }),
+        });
+
+        let contract = EscrowContract(Addr::unchecked("escrow_contract"));
+        let querier = QuerierWrapper::<Empty>::new(&querier);
+        let res = contract.sweep_config(&querier).unwrap();
+        assert_eq!(res.sweep_config.batch_size, 5);
     }
 }
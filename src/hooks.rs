@@ -0,0 +1,54 @@
+use bech32::{ToBase32, Variant};
+use sha2::{Digest, Sha256};
+
+use crate::error::ContractError;
+
+// Type prefix ibc-hooks hashes intermediary sender addresses under
+const SENDER_PREFIX: &str = "ibc-wasm-hook-intermediary";
+
+/// Address ibc-hooks executes a transfer's memo from: a module-style address derived from
+/// the receiving channel and the sender on the source chain, with the local prefix
+pub fn intermediary_sender(
+    channel: &str,
+    original_sender: &str,
+    prefix: &str,
+) -> Result<String, ContractError> {
+    let type_hash = Sha256::digest(SENDER_PREFIX.as_bytes());
+    let address = Sha256::new()
+        .chain_update(type_hash)
+        .chain_update(format!("{}/{}", channel, original_sender))
+        .finalize();
+
+    bech32::encode(prefix, address.to_base32(), Variant::Bech32)
+        .map_err(|_| ContractError::InvalidHookSender {})
+}
+
+/// Bech32 prefix of an address, so the sender can be derived for whichever chain we run on
+pub fn address_prefix(address: &str) -> Result<String, ContractError> {
+    bech32::decode(address)
+        .map(|(prefix, _, _)| prefix)
+        .map_err(|_| ContractError::InvalidHookSender {})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_intermediary_sender() {
+        let sender = intermediary_sender("channel-0", "cosmos1maker", "neutron").unwrap();
+        assert!(sender.starts_with("neutron1"));
+        assert_eq!(address_prefix(&sender).unwrap(), "neutron");
+
+        // Either input changes the address
+        assert_ne!(
+            intermediary_sender("channel-1", "cosmos1maker", "neutron").unwrap(),
+            sender
+        );
+        assert_ne!(
+            intermediary_sender("channel-0", "cosmos1other", "neutron").unwrap(),
+            sender
+        );
+        assert!(address_prefix("not an address").is_err());
+    }
+}
@@ -0,0 +1,858 @@
+use bech32::FromBase32;
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    from_json, to_json_binary, Addr, Binary, DepsMut, Env, Ibc3ChannelOpenResponse,
+    IbcBasicResponse, IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg,
+    IbcChannelOpenResponse, IbcOrder, IbcPacket, IbcPacketAckMsg, IbcPacketReceiveMsg,
+    IbcPacketTimeoutMsg, IbcReceiveResponse, Order, StdResult, Storage, Uint128,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ContractError;
+use crate::events::EscrowEvent;
+use crate::execute::{receive_secret, store_dst_escrow};
+use crate::neutron::NeutronMsg;
+use crate::state::{AddressFormat, Immutables, IBC_CHANNELS, SUPPORTED_CHAINS};
+
+/// Channel version both ends of an escrow channel must agree on
+pub const IBC_VERSION: &str = "escrow-factory-1";
+
+/// Reply id of the best-effort packet relaying a revealed secret
+pub const RELAY_SECRET_REPLY_ID: u64 = 1;
+
+// Counterparts are escrow factories deployed as CosmWasm contracts
+const WASM_PORT_PREFIX: &str = "wasm.";
+
+/// Packets exchanged between escrow factories on two Cosmos chains
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EscrowPacket {
+    /// Opens the destination escrow matching a source escrow. The escrow is created
+    /// unfunded; the taker funds it with `FundDstEscrow` before its withdrawal stage starts,
+    /// after which the sweep drops it.
+    CreateDstEscrow {
+        escrow_address: String,
+        immutables: Immutables,
+    },
+    /// Forwards a secret revealed by a withdrawal to the counterpart leg
+    RevealSecret { hashlock: String, secret: String },
+}
+
+/// Acknowledgement written for every received packet, in the usual ICS result/error shape
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EscrowAck {
+    Result(Binary),
+    Error(String),
+}
+
+impl EscrowAck {
+    pub fn success(data: &impl Serialize) -> StdResult<Binary> {
+        to_json_binary(&EscrowAck::Result(to_json_binary(data)?))
+    }
+
+    pub fn error(err: impl ToString) -> StdResult<Binary> {
+        to_json_binary(&EscrowAck::Error(err.to_string()))
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_open(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelOpenMsg,
+) -> Result<IbcChannelOpenResponse, ContractError> {
+    validate_channel(deps.storage, msg.channel(), msg.counterparty_version())?;
+
+    Ok(Some(Ibc3ChannelOpenResponse {
+        version: IBC_VERSION.to_string(),
+    }))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_connect(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelConnectMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    validate_channel(deps.storage, channel, msg.counterparty_version())?;
+    IBC_CHANNELS.save(deps.storage, channel.endpoint.channel_id.clone(), channel)?;
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_channel_connect")
+        .add_attribute("channel_id", &channel.endpoint.channel_id))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_close(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    IBC_CHANNELS.remove(deps.storage, channel.endpoint.channel_id.clone());
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_channel_close")
+        .add_attribute("channel_id", &channel.endpoint.channel_id))
+}
+
+/// Handles a packet from the counterpart factory. Failures are written to the
+/// acknowledgement rather than aborting, so the sender learns what went wrong. Nothing
+/// rolls back what a failed packet wrote, so `receive_packet` must not write before its
+/// last fallible step.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_receive(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse<NeutronMsg>, ContractError> {
+    match receive_packet(deps, env, &msg.packet) {
+        Ok(response) => Ok(response),
+        Err(err) => Ok(IbcReceiveResponse::new()
+            .set_ack(EscrowAck::error(&err)?)
+            .add_attribute("action", "ibc_packet_receive")
+            .add_attribute("error", err.to_string())),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_ack(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcPacketAckMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let packet: EscrowPacket = from_json(&msg.original_packet.data)?;
+    let ack: EscrowAck = from_json(&msg.acknowledgement.data)?;
+
+    // Nothing local depends on the outcome, it is only reported
+    let response = packet_attributes(IbcBasicResponse::new(), "ibc_packet_ack", packet);
+    Ok(match ack {
+        EscrowAck::Result(_) => response.add_attribute("success", "true"),
+        EscrowAck::Error(err) => response
+            .add_attribute("success", "false")
+            .add_attribute("error", err),
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_timeout(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcPacketTimeoutMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let packet: EscrowPacket = from_json(&msg.packet.data)?;
+
+    Ok(packet_attributes(
+        IbcBasicResponse::new(),
+        "ibc_packet_timeout",
+        packet,
+    ))
+}
+
+fn packet_attributes(
+    response: IbcBasicResponse,
+    action: &str,
+    packet: EscrowPacket,
+) -> IbcBasicResponse {
+    let response = response.add_attribute("action", action);
+    match packet {
+        EscrowPacket::CreateDstEscrow { escrow_address, .. } => {
+            response.add_attribute("escrow_address", escrow_address)
+        }
+        EscrowPacket::RevealSecret { hashlock, .. } => response.add_attribute("hashlock", hashlock),
+    }
+}
+
+fn receive_packet(
+    deps: DepsMut,
+    env: Env,
+    packet: &IbcPacket,
+) -> Result<IbcReceiveResponse<NeutronMsg>, ContractError> {
+    // The counterpart may have been deregistered since the channel opened
+    let channel = IBC_CHANNELS.load(deps.storage, packet.dest.channel_id.clone())?;
+    ensure_registered_counterparty(deps.storage, &channel)?;
+
+    match from_json(&packet.data)? {
+        EscrowPacket::CreateDstEscrow {
+            escrow_address,
+            immutables,
+        } => {
+            let ack = EscrowAck::success(&escrow_address)?;
+            let escrow = store_dst_escrow(deps, &env, &escrow_address, immutables, false)?;
+
+            // Opened by the counterpart factory, which the channel's port belongs to
+            let port_id = &channel.counterparty_endpoint.port_id;
+            let event = EscrowEvent::DstEscrowCreated {
+                escrow_address: escrow_address.clone(),
+                immutables: escrow.immutables,
+                timestamp: Uint128::from(env.block.time.seconds()),
+                creator: Addr::unchecked(port_id.trim_start_matches(WASM_PORT_PREFIX)),
+            };
+
+            Ok(IbcReceiveResponse::new()
+                .set_ack(ack)
+                .add_event(event.into())
+                .add_attribute("action", "ibc_create_dst_escrow")
+                .add_attribute("escrow_address", escrow_address))
+        }
+        EscrowPacket::RevealSecret { hashlock, secret } => {
+            let ack = EscrowAck::success(&true)?;
+            let channel_id = packet.dest.channel_id.clone();
+            let response = receive_secret(deps, env, channel_id, hashlock, secret)?;
+
+            Ok(IbcReceiveResponse::new()
+                .set_ack(ack)
+                .add_submessages(response.messages)
+                .add_events(response.events)
+                .add_attributes(response.attributes))
+        }
+    }
+}
+
+/// Escrow channels are unordered, speak `IBC_VERSION` and connect to the escrow factory of
+/// a registered counterpart chain
+fn validate_channel(
+    storage: &dyn Storage,
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+) -> Result<(), ContractError> {
+    if channel.order != IbcOrder::Unordered {
+        return Err(ContractError::InvalidIbcOrder {});
+    }
+    if channel.version != IBC_VERSION {
+        return Err(ContractError::InvalidIbcVersion {
+            version: channel.version.clone(),
+        });
+    }
+    if let Some(version) = counterparty_version {
+        if version != IBC_VERSION {
+            return Err(ContractError::InvalidIbcVersion {
+                version: version.to_string(),
+            });
+        }
+    }
+    ensure_registered_counterparty(storage, channel)
+}
+
+/// Checks that the channel runs over the connection of a registered counterpart chain and
+/// that its counterparty port belongs to that chain's escrow factory
+fn ensure_registered_counterparty(
+    storage: &dyn Storage,
+    channel: &IbcChannel,
+) -> Result<(), ContractError> {
+    let port_id = &channel.counterparty_endpoint.port_id;
+    let contract = port_id.strip_prefix(WASM_PORT_PREFIX).ok_or_else(|| {
+        ContractError::InvalidCounterpartyPort {
+            port_id: port_id.clone(),
+        }
+    })?;
+
+    // Cosmos factories are registered by the 32 bytes of their contract address
+    let factory = bech32::decode(contract)
+        .ok()
+        .and_then(|(_, data, _)| Vec::<u8>::from_base32(&data).ok())
+        .map(|bytes| format!("0x{}", hex::encode(bytes)));
+    for item in SUPPORTED_CHAINS.range(storage, None, None, Order::Ascending) {
+        let (_, chain) = item?;
+        if chain.address_format == AddressFormat::Bytes32
+            && chain.connection_id.as_ref() == Some(&channel.connection_id)
+            && factory.as_ref() == Some(&chain.factory)
+        {
+            return Ok(());
+        }
+    }
+    Err(ContractError::UnregisteredCounterparty {
+        connection_id: channel.connection_id.clone(),
+        port_id: port_id.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bech32::{ToBase32, Variant};
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_ibc_channel, mock_ibc_packet_ack, mock_ibc_packet_recv,
+        mock_info, MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{
+        coins, Addr, BankMsg, CosmosMsg, IbcAcknowledgement, IbcMsg, OwnedDeps, Reply, ReplyOn,
+        SubMsgResult, Uint128, Uint256,
+    };
+
+    use crate::contract::{execute, instantiate, reply};
+    use crate::msg::{ExecuteMsg, InstantiateMsg};
+    use crate::state::{EscrowStatus, DST_ESCROWS, RELAYED_SECRETS, REVEALED_SECRETS};
+    use crate::testing::{dst_immutables, hashlock_of};
+    use crate::timelocks::Timelocks;
+
+    fn channel(version: &str, counterparty_port: &str) -> IbcChannel {
+        let mut channel = mock_ibc_channel("channel-1", IbcOrder::Unordered, version);
+        channel.counterparty_endpoint.port_id = counterparty_port.to_string();
+        channel
+    }
+
+    /// Port of a wasm contract whose address holds `bytes`
+    fn contract_port(bytes: [u8; 32]) -> String {
+        let address = bech32::encode("cosmos", bytes.to_base32(), Variant::Bech32).unwrap();
+        format!("{}{}", WASM_PORT_PREFIX, address)
+    }
+
+    fn factory_port() -> String {
+        contract_port([0x11; 32])
+    }
+
+    /// Instantiated with a counterpart chain whose factory is reached over `connection-2`,
+    /// the connection of mock channels
+    fn registered() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            safety_deposit_denom: "ustake".to_string(),
+            rescue_delay: 86400,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::RegisterChain {
+            chain_id: Uint256::from(2u128),
+            address_format: AddressFormat::Bytes32,
+            factory: format!("0x{}", "11".repeat(32)),
+            connection_id: Some("connection-2".to_string()),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        deps
+    }
+
+    fn connected() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+        let mut deps = registered();
+        let msg = IbcChannelConnectMsg::new_ack(channel(IBC_VERSION, &factory_port()), IBC_VERSION);
+        ibc_channel_connect(deps.as_mut(), mock_env(), msg).unwrap();
+        deps
+    }
+
+    #[test]
+    fn handshake_checks_order_version_and_port() {
+        let mut deps = registered();
+        let open = |channel: IbcChannel, counterparty_version: &str| {
+            IbcChannelOpenMsg::new_try(channel, counterparty_version)
+        };
+
+        let res = ibc_channel_open(
+            deps.as_mut(),
+            mock_env(),
+            open(channel(IBC_VERSION, &factory_port()), IBC_VERSION),
+        )
+        .unwrap();
+        assert_eq!(res.unwrap().version, IBC_VERSION);
+
+        let mut ordered = channel(IBC_VERSION, &factory_port());
+        ordered.order = IbcOrder::Ordered;
+        let err = ibc_channel_open(deps.as_mut(), mock_env(), open(ordered, IBC_VERSION));
+        assert!(matches!(err, Err(ContractError::InvalidIbcOrder {})));
+
+        let err = ibc_channel_open(
+            deps.as_mut(),
+            mock_env(),
+            open(channel(IBC_VERSION, &factory_port()), "ics20-1"),
+        );
+        assert!(matches!(err, Err(ContractError::InvalidIbcVersion { .. })));
+
+        let err = ibc_channel_open(
+            deps.as_mut(),
+            mock_env(),
+            open(channel(IBC_VERSION, "transfer"), IBC_VERSION),
+        );
+        assert!(matches!(
+            err,
+            Err(ContractError::InvalidCounterpartyPort { .. })
+        ));
+
+        // Closing forgets the channel
+        let mut deps = connected();
+        assert!(IBC_CHANNELS.has(deps.as_ref().storage, "channel-1".to_string()));
+        let msg = IbcChannelCloseMsg::new_init(channel(IBC_VERSION, &factory_port()));
+        ibc_channel_close(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(!IBC_CHANNELS.has(deps.as_ref().storage, "channel-1".to_string()));
+    }
+
+    #[test]
+    fn handshake_rejects_unregistered_counterparty() {
+        let mut deps = registered();
+        let open = |channel: IbcChannel| IbcChannelOpenMsg::new_try(channel, IBC_VERSION);
+
+        // Any other contract on the counterpart chain
+        let err = ibc_channel_open(
+            deps.as_mut(),
+            mock_env(),
+            open(channel(IBC_VERSION, &contract_port([0x22; 32]))),
+        );
+        assert!(matches!(
+            err,
+            Err(ContractError::UnregisteredCounterparty { .. })
+        ));
+        let err = ibc_channel_open(
+            deps.as_mut(),
+            mock_env(),
+            open(channel(IBC_VERSION, "wasm.factory")),
+        );
+        assert!(matches!(
+            err,
+            Err(ContractError::UnregisteredCounterparty { .. })
+        ));
+
+        // The factory's port on a chain behind another connection
+        let mut other_connection = channel(IBC_VERSION, &factory_port());
+        other_connection.connection_id = "connection-9".to_string();
+        let msg = IbcChannelConnectMsg::new_ack(other_connection, IBC_VERSION);
+        let err = ibc_channel_connect(deps.as_mut(), mock_env(), msg);
+        assert!(matches!(
+            err,
+            Err(ContractError::UnregisteredCounterparty { .. })
+        ));
+        assert!(!IBC_CHANNELS.has(deps.as_ref().storage, "channel-1".to_string()));
+
+        // Packets stop being accepted once the chain is deregistered
+        let mut deps = connected();
+        let msg = ExecuteMsg::RemoveChain {
+            chain_id: Uint256::from(2u128),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let packet = EscrowPacket::CreateDstEscrow {
+            escrow_address: "cosmos1escrowdst".to_string(),
+            immutables: dst_immutables("maker_address", "0xorder1", "0xhash1"),
+        };
+        let msg = mock_ibc_packet_recv("channel-1", &packet).unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        let err = ContractError::UnregisteredCounterparty {
+            connection_id: "connection-2".to_string(),
+            port_id: factory_port(),
+        };
+        assert_eq!(res.acknowledgement, EscrowAck::error(err).unwrap());
+        assert!(!DST_ESCROWS.has(deps.as_ref().storage, "cosmos1escrowdst".to_string()));
+    }
+
+    #[test]
+    fn packet_opens_unfunded_dst_escrow() {
+        let mut deps = connected();
+        let packet = EscrowPacket::CreateDstEscrow {
+            escrow_address: "cosmos1escrowdst".to_string(),
+            immutables: dst_immutables("maker_address", "0xorder1", "0xhash1"),
+        };
+
+        let msg = mock_ibc_packet_recv("channel-1", &packet).unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(
+            res.acknowledgement,
+            EscrowAck::success(&"cosmos1escrowdst").unwrap()
+        );
+        let escrow = DST_ESCROWS
+            .load(deps.as_ref().storage, "cosmos1escrowdst".to_string())
+            .unwrap();
+        assert!(!escrow.funded);
+        let factory = factory_port()
+            .trim_start_matches(WASM_PORT_PREFIX)
+            .to_string();
+        assert_eq!(
+            EscrowEvent::try_from(&res.events[0]).unwrap(),
+            EscrowEvent::DstEscrowCreated {
+                escrow_address: "cosmos1escrowdst".to_string(),
+                immutables: dst_immutables("maker_address", "0xorder1", "0xhash1"),
+                timestamp: Uint128::from(mock_env().block.time.seconds()),
+                creator: Addr::unchecked(factory),
+            }
+        );
+
+        // A second packet for the same hashlock is refused in the acknowledgement
+        let msg = mock_ibc_packet_recv("channel-1", &packet).unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(matches!(
+            from_json(&res.acknowledgement).unwrap(),
+            EscrowAck::Error(_)
+        ));
+
+        // Only the taker can fund it, with the escrowed tokens and the safety deposit
+        let fund = ExecuteMsg::FundDstEscrow {
+            escrow_address: "cosmos1escrowdst".to_string(),
+        };
+        let funds = [coins(1000, "token_address"), coins(100, "ustake")].concat();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &funds),
+            fund.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("taker_address", &funds),
+            fund.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            EscrowEvent::try_from(&res.events[0]).unwrap(),
+            EscrowEvent::DstEscrowFunded {
+                escrow_address: "cosmos1escrowdst".to_string(),
+                funder: Addr::unchecked("taker_address"),
+                amount: Uint128::new(1000),
+                safety_deposit: Uint128::new(100),
+            }
+        );
+        let escrow = DST_ESCROWS
+            .load(deps.as_ref().storage, "cosmos1escrowdst".to_string())
+            .unwrap();
+        assert!(escrow.funded);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("taker_address", &funds),
+            fund,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::EscrowAlreadyFunded {}));
+
+        // An escrow closed before it was funded cannot be funded any more
+        let mut packet_immutables = dst_immutables("maker_address", "0xorder1", "0xhash1");
+        packet_immutables.hashlock = "0xhash2".to_string();
+        let packet = EscrowPacket::CreateDstEscrow {
+            escrow_address: "cosmos1closed".to_string(),
+            immutables: packet_immutables,
+        };
+        let msg = mock_ibc_packet_recv("channel-1", &packet).unwrap();
+        ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        DST_ESCROWS
+            .update(
+                deps.as_mut().storage,
+                "cosmos1closed".to_string(),
+                |escrow| {
+                    let mut escrow = escrow.unwrap();
+                    escrow.status = EscrowStatus::Cancelled;
+                    StdResult::Ok(escrow)
+                },
+            )
+            .unwrap();
+        let fund = ExecuteMsg::FundDstEscrow {
+            escrow_address: "cosmos1closed".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("taker_address", &funds),
+            fund,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::NotActive {
+                status: EscrowStatus::Cancelled
+            }
+        ));
+    }
+
+    #[test]
+    fn unfunded_escrow_is_dropped_after_funding_deadline() {
+        let mut deps = connected();
+        let mut packet_immutables = dst_immutables("maker_address", "0xorder1", "0xhash1");
+        packet_immutables.timelocks = Timelocks::pack([0, 0, 0, 0, 10, 100, 1000]);
+        let packet = EscrowPacket::CreateDstEscrow {
+            escrow_address: "cosmos1escrowdst".to_string(),
+            immutables: packet_immutables,
+        };
+        let msg = mock_ibc_packet_recv("channel-1", &packet).unwrap();
+        ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+
+        // Funding closes once the withdrawal stage starts
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(11);
+        let fund = ExecuteMsg::FundDstEscrow {
+            escrow_address: "cosmos1escrowdst".to_string(),
+        };
+        let funds = [coins(1000, "token_address"), coins(100, "ustake")].concat();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("taker_address", &funds),
+            fund,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::FundingDeadlinePassed {}));
+
+        // The sweep drops the escrow with nothing to pay out, long before it would expire
+        let sweep = ExecuteMsg::SweepEscrow {
+            escrow_address: "cosmos1escrowdst".to_string(),
+        };
+        let contract = mock_info(env.contract.address.as_str(), &[]);
+        let res = execute(deps.as_mut(), env.clone(), contract, sweep).unwrap();
+        assert!(res.messages.is_empty());
+        assert!(!DST_ESCROWS.has(deps.as_ref().storage, "cosmos1escrowdst".to_string()));
+
+        // Which frees its hashlock for a new escrow
+        let msg = mock_ibc_packet_recv("channel-1", &packet).unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), env, msg).unwrap();
+        assert_eq!(
+            res.acknowledgement,
+            EscrowAck::success(&"cosmos1escrowdst").unwrap()
+        );
+    }
+
+    #[test]
+    fn ack_reports_outcome() {
+        let mut deps = connected();
+        let packet = EscrowPacket::CreateDstEscrow {
+            escrow_address: "cosmos1escrowdst".to_string(),
+            immutables: dst_immutables("maker_address", "0xorder1", "0xhash1"),
+        };
+
+        let ack = IbcAcknowledgement::new(EscrowAck::error("Hashlock already exists").unwrap());
+        let msg = mock_ibc_packet_ack("channel-1", &packet, ack).unwrap();
+        let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "success" && attr.value == "false"));
+
+        let ack = IbcAcknowledgement::new(EscrowAck::success(&"cosmos1escrowdst").unwrap());
+        let msg = mock_ibc_packet_ack("channel-1", &packet, ack).unwrap();
+        let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "success" && attr.value == "true"));
+    }
+
+    fn dst_escrow_with_secret(
+        deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+        secret: [u8; 32],
+        withdrawal_offset: u32,
+    ) {
+        let mut immutables = dst_immutables("maker_address", "0xorder1", &hashlock_of(&secret));
+        immutables.timelocks = Timelocks::pack([0, 0, 0, 0, withdrawal_offset, 100, 1000]);
+
+        let msg = ExecuteMsg::CreateDstEscrow {
+            escrow_address: "cosmos1escrowdst".to_string(),
+            immutables,
+            timestamp: Uint128::from(mock_env().block.time.seconds()),
+        };
+        let funds = [coins(1000, "token_address"), coins(100, "ustake")].concat();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("taker_address", &funds),
+            msg,
+        )
+        .unwrap();
+    }
+
+    fn reveal(secret: [u8; 32]) -> EscrowPacket {
+        EscrowPacket::RevealSecret {
+            hashlock: hashlock_of(&secret),
+            secret: format!("0x{}", hex::encode(secret)),
+        }
+    }
+
+    #[test]
+    fn relayed_secret_withdraws_for_beneficiary() {
+        let mut deps = connected();
+        dst_escrow_with_secret(&mut deps, [7u8; 32], 0);
+
+        let msg = mock_ibc_packet_recv("channel-1", &reveal([7u8; 32])).unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(res.acknowledgement, EscrowAck::success(&true).unwrap());
+
+        // The maker is paid and the taker gets the safety deposit, as on a private withdrawal
+        let messages: Vec<_> = res.messages.into_iter().map(|sub| sub.msg).collect();
+        assert_eq!(
+            messages,
+            vec![
+                BankMsg::Send {
+                    to_address: "maker_address".to_string(),
+                    amount: coins(1000, "token_address"),
+                }
+                .into(),
+                BankMsg::Send {
+                    to_address: "taker_address".to_string(),
+                    amount: coins(100, "ustake"),
+                }
+                .into(),
+            ]
+        );
+        let escrow = DST_ESCROWS
+            .load(deps.as_ref().storage, "cosmos1escrowdst".to_string())
+            .unwrap();
+        assert_eq!(escrow.status, EscrowStatus::Withdrawn);
+    }
+
+    #[test]
+    fn relayed_secret_is_kept_until_usable() {
+        let mut deps = connected();
+        dst_escrow_with_secret(&mut deps, [7u8; 32], 10);
+
+        // A secret that does not open its hashlock is refused
+        let mut wrong = reveal([7u8; 32]);
+        if let EscrowPacket::RevealSecret { secret, .. } = &mut wrong {
+            *secret = format!("0x{}", hex::encode([8u8; 32]));
+        }
+        let msg = mock_ibc_packet_recv("channel-1", &wrong).unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(matches!(
+            from_json(&res.acknowledgement).unwrap(),
+            EscrowAck::Error(_)
+        ));
+
+        // Still in the finality lock, so the secret is stored for the beneficiary
+        let msg = mock_ibc_packet_recv("channel-1", &reveal([7u8; 32])).unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(res.messages.is_empty());
+        let hashlock = hashlock_of(&[7u8; 32]);
+        let relayed = RELAYED_SECRETS
+            .load(deps.as_ref().storage, hashlock)
+            .unwrap();
+        assert_eq!(relayed.secret, format!("0x{}", hex::encode([7u8; 32])));
+        assert_eq!(relayed.channel_id, "channel-1");
+        let escrow = DST_ESCROWS
+            .load(deps.as_ref().storage, "cosmos1escrowdst".to_string())
+            .unwrap();
+        assert_eq!(escrow.status, EscrowStatus::Active);
+    }
+
+    /// Destination escrow opened over IBC and never funded, with every window open by
+    /// the returned cancellation time
+    fn unfunded_escrow_with_secret(
+        deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+        secret: [u8; 32],
+    ) -> Env {
+        let mut immutables = dst_immutables("maker_address", "0xorder1", &hashlock_of(&secret));
+        immutables.timelocks = Timelocks::pack([0, 0, 0, 0, 0, 100, 1000]);
+        let packet = EscrowPacket::CreateDstEscrow {
+            escrow_address: "cosmos1escrowdst".to_string(),
+            immutables,
+        };
+        let msg = mock_ibc_packet_recv("channel-1", &packet).unwrap();
+        ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(1000);
+        env
+    }
+
+    #[test]
+    fn unfunded_escrow_refuses_withdrawal() {
+        let mut deps = connected();
+        unfunded_escrow_with_secret(&mut deps, [7u8; 32]);
+        let secret = format!("0x{}", hex::encode([7u8; 32]));
+
+        let withdraw = ExecuteMsg::Withdraw {
+            escrow_address: "cosmos1escrowdst".to_string(),
+            secret: secret.clone(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("taker_address", &[]),
+            withdraw,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::EscrowNotFunded {}));
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        let public_withdraw = ExecuteMsg::PublicWithdraw {
+            escrow_address: "cosmos1escrowdst".to_string(),
+            secret,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            public_withdraw,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::EscrowNotFunded {}));
+
+        // Nothing was settled or revealed
+        let escrow = DST_ESCROWS
+            .load(deps.as_ref().storage, "cosmos1escrowdst".to_string())
+            .unwrap();
+        assert_eq!(escrow.status, EscrowStatus::Active);
+        assert!(REVEALED_SECRETS.is_empty(deps.as_ref().storage));
+    }
+
+    #[test]
+    fn unfunded_escrow_refuses_cancellation() {
+        let mut deps = connected();
+        let env = unfunded_escrow_with_secret(&mut deps, [7u8; 32]);
+
+        let cancel = ExecuteMsg::Cancel {
+            escrow_address: "cosmos1escrowdst".to_string(),
+        };
+        let err = execute(deps.as_mut(), env, mock_info("taker_address", &[]), cancel).unwrap_err();
+        assert!(matches!(err, ContractError::EscrowNotFunded {}));
+        let escrow = DST_ESCROWS
+            .load(deps.as_ref().storage, "cosmos1escrowdst".to_string())
+            .unwrap();
+        assert_eq!(escrow.status, EscrowStatus::Active);
+    }
+
+    #[test]
+    fn relayed_secret_does_not_withdraw_unfunded_escrow() {
+        let mut deps = connected();
+        unfunded_escrow_with_secret(&mut deps, [7u8; 32]);
+
+        // The secret is only kept, for once the taker has funded the escrow
+        let msg = mock_ibc_packet_recv("channel-1", &reveal([7u8; 32])).unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(res.acknowledgement, EscrowAck::success(&true).unwrap());
+        assert!(res.messages.is_empty());
+        let hashlock = hashlock_of(&[7u8; 32]);
+        assert!(RELAYED_SECRETS.has(deps.as_ref().storage, hashlock.clone()));
+        assert!(!REVEALED_SECRETS.has(deps.as_ref().storage, hashlock));
+        let escrow = DST_ESCROWS
+            .load(deps.as_ref().storage, "cosmos1escrowdst".to_string())
+            .unwrap();
+        assert_eq!(escrow.status, EscrowStatus::Active);
+    }
+
+    #[test]
+    fn withdrawal_relays_secret_best_effort() {
+        let mut deps = connected();
+        dst_escrow_with_secret(&mut deps, [7u8; 32], 0);
+
+        let relay = |channel_id: &str| ExecuteMsg::UpdateSecretRelay {
+            channel_id: Some(channel_id.to_string()),
+        };
+        let owner = mock_info("creator", &[]);
+        let err =
+            execute(deps.as_mut(), mock_env(), owner.clone(), relay("channel-9")).unwrap_err();
+        assert!(matches!(err, ContractError::UnknownChannel { .. }));
+        execute(deps.as_mut(), mock_env(), owner, relay("channel-1")).unwrap();
+
+        let withdraw = ExecuteMsg::Withdraw {
+            escrow_address: "cosmos1escrowdst".to_string(),
+            secret: format!("0x{}", hex::encode([7u8; 32])),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("taker_address", &[]),
+            withdraw,
+        )
+        .unwrap();
+        let relay = res.messages.last().unwrap();
+        assert_eq!(relay.reply_on, ReplyOn::Error);
+        let CosmosMsg::Ibc(IbcMsg::SendPacket { data, .. }) = &relay.msg else {
+            panic!("expected an IBC packet");
+        };
+        assert_eq!(from_json::<EscrowPacket>(data).unwrap(), reveal([7u8; 32]));
+
+        // A failed send leaves the withdrawal in place
+        let failed = Reply {
+            id: RELAY_SECRET_REPLY_ID,
+            result: SubMsgResult::Err("channel closed".to_string()),
+        };
+        reply(deps.as_mut(), mock_env(), failed).unwrap();
+    }
+}
@@ -1,78 +1,814 @@
-#[cfg(test)]
-mod tests {
-    use crate::helpers::CwTemplateContract;
-    use crate::msg::InstantiateMsg;
-    use cosmwasm_std::testing::MockApi;
-    use cosmwasm_std::{Addr, Coin, Empty, Uint128};
-    use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
-
-    pub fn contract_template() -> Box<dyn Contract<Empty>> {
-        let contract = ContractWrapper::new(
-            crate::contract::execute,
-            crate::contract::instantiate,
-            crate::contract::query,
-        );
-        Box::new(contract)
-    }
-
-    const USER: &str = "USER";
-    const ADMIN: &str = "ADMIN";
-    const NATIVE_DENOM: &str = "denom";
-
-    fn mock_app() -> App {
-        AppBuilder::new().build(|router, _, storage| {
-            router
-                .bank
-                .init_balance(
-                    storage,
-                    &MockApi::default().addr_make(USER),
-                    vec![Coin {
-                        denom: NATIVE_DENOM.to_string(),
-                        amount: Uint128::new(1),
-                    }],
-                )
-                .unwrap();
-        })
+use cosmwasm_std::{coin, Addr, Binary, Coin, Empty, Uint128, Uint256, WasmMsg};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
+use cw_multi_test::{AppResponse, BasicApp, BasicAppBuilder, ContractWrapper, Executor};
+use proptest::prelude::*;
+
+use crate::contract::{execute, instantiate, query, reply};
+use crate::error::ContractError;
+use crate::helpers::EscrowContract;
+use crate::msg::InstantiateMsg;
+use crate::neutron::NeutronMsg;
+use crate::sdk::{self, ImmutablesBuilder, OrderBuilder};
+use crate::state::{AddressFormat, EscrowSide, EscrowStatus, Immutables, Order};
+use crate::testing::{extra_data, hashlock_of, keccak, DST_CHAIN_ID};
+use crate::timelocks::{EscrowAction, Stage, Timelocks};
+
+const OWNER: &str = "owner";
+const MAKER: &str = "maker";
+const TAKER: &str = "taker";
+const PUBLIC: &str = "public_caller";
+
+const DEPOSIT_DENOM: &str = "untrn";
+const NATIVE_DENOM: &str = "uatom";
+const RESCUE_DELAY: u64 = 86_400;
+
+const AMOUNT: u128 = 1_000;
+const DEPOSIT: u128 = 100;
+const INITIAL_BALANCE: u128 = 1_000_000;
+
+/// Minimal CW20 token: balances, allowances and the messages the escrow contract sends.
+/// cw20-base does not build against our cosmwasm-std, so the suite brings its own.
+mod token {
+    use cosmwasm_std::{
+        to_json_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError,
+        StdResult, Uint128,
+    };
+    use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg};
+    use cw_storage_plus::Map;
+    use schemars::JsonSchema;
+    use serde::{Deserialize, Serialize};
+
+    const BALANCES: Map<&Addr, Uint128> = Map::new("balances");
+    const ALLOWANCES: Map<(&Addr, &Addr), Uint128> = Map::new("allowances");
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+    pub struct InstantiateMsg {
+        pub initial_balances: Vec<Cw20Coin>,
     }
 
-    fn proper_instantiate() -> (App, CwTemplateContract) {
-        let mut app = mock_app();
-        let cw_template_id = app.store_code(contract_template());
+    pub fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: InstantiateMsg,
+    ) -> StdResult<Response> {
+        for Cw20Coin { address, amount } in msg.initial_balances {
+            BALANCES.save(deps.storage, &Addr::unchecked(address), &amount)?;
+        }
+        Ok(Response::new())
+    }
 
-        let user = app.api().addr_make(USER);
-        assert_eq!(
-            app.wrap().query_balance(user, NATIVE_DENOM).unwrap().amount,
-            Uint128::new(1)
-        );
+    pub fn execute(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        msg: Cw20ExecuteMsg,
+    ) -> StdResult<Response> {
+        match msg {
+            Cw20ExecuteMsg::Transfer { recipient, amount } => {
+                move_balance(deps, &info.sender, &Addr::unchecked(recipient), amount)?;
+            }
+            Cw20ExecuteMsg::TransferFrom {
+                owner,
+                recipient,
+                amount,
+            } => {
+                let owner = Addr::unchecked(owner);
+                ALLOWANCES.update(deps.storage, (&owner, &info.sender), |allowance| {
+                    Ok::<_, StdError>(allowance.unwrap_or_default().checked_sub(amount)?)
+                })?;
+                move_balance(deps, &owner, &Addr::unchecked(recipient), amount)?;
+            }
+            Cw20ExecuteMsg::IncreaseAllowance {
+                spender, amount, ..
+            } => {
+                let key = (&info.sender, &Addr::unchecked(spender));
+                ALLOWANCES.update(deps.storage, key, |allowance| -> StdResult<_> {
+                    Ok(allowance.unwrap_or_default() + amount)
+                })?;
+            }
+            _ => return Err(StdError::generic_err("unsupported")),
+        }
+        Ok(Response::new())
+    }
+
+    pub fn query(deps: Deps, _env: Env, msg: Cw20QueryMsg) -> StdResult<Binary> {
+        match msg {
+            Cw20QueryMsg::Balance { address } => {
+                let balance = BALANCES
+                    .may_load(deps.storage, &Addr::unchecked(address))?
+                    .unwrap_or_default();
+                to_json_binary(&BalanceResponse { balance })
+            }
+            _ => Err(StdError::generic_err("unsupported")),
+        }
+    }
+
+    fn move_balance(deps: DepsMut, from: &Addr, to: &Addr, amount: Uint128) -> StdResult<()> {
+        BALANCES.update(deps.storage, from, |balance| {
+            Ok::<_, StdError>(balance.unwrap_or_default().checked_sub(amount)?)
+        })?;
+        BALANCES.update(deps.storage, to, |balance| -> StdResult<_> {
+            Ok(balance.unwrap_or_default() + amount)
+        })?;
+        Ok(())
+    }
+}
+
+/// An app running the escrow contract and a CW20 token, with the maker and taker holding
+/// native coins and tokens
+struct Suite {
+    app: BasicApp<NeutronMsg, Empty>,
+    escrow: EscrowContract,
+    token: Addr,
+}
+
+impl Suite {
+    fn new() -> Self {
+        let mut app =
+            BasicAppBuilder::<NeutronMsg, Empty>::new_custom().build(|router, _, storage| {
+                for account in [MAKER, TAKER] {
+                    let funds = vec![
+                        coin(INITIAL_BALANCE, DEPOSIT_DENOM),
+                        coin(INITIAL_BALANCE, NATIVE_DENOM),
+                    ];
+                    router
+                        .bank
+                        .init_balance(storage, &Addr::unchecked(account), funds)
+                        .unwrap();
+                }
+            });
+
+        let escrow_code = ContractWrapper::new(execute, instantiate, query).with_reply(reply);
+        let escrow_code_id = app.store_code(Box::new(escrow_code));
+        let token_code =
+            ContractWrapper::new_with_empty(token::execute, token::instantiate, token::query);
+        let token_code_id = app.store_code(Box::new(token_code));
 
-        let msg = InstantiateMsg { count: 1i32 };
-        let cw_template_contract_addr = app
+        let msg = InstantiateMsg {
+            safety_deposit_denom: DEPOSIT_DENOM.to_string(),
+            rescue_delay: RESCUE_DELAY,
+        };
+        let escrow = app
             .instantiate_contract(
-                cw_template_id,
-                Addr::unchecked(ADMIN),
+                escrow_code_id,
+                Addr::unchecked(OWNER),
                 &msg,
                 &[],
-                "test",
+                "escrow",
+                None,
+            )
+            .unwrap();
+        let msg = token::InstantiateMsg {
+            initial_balances: [MAKER, TAKER]
+                .iter()
+                .map(|account| cw20::Cw20Coin {
+                    address: account.to_string(),
+                    amount: Uint128::from(INITIAL_BALANCE),
+                })
+                .collect(),
+        };
+        let token = app
+            .instantiate_contract(
+                token_code_id,
+                Addr::unchecked(OWNER),
+                &msg,
+                &[],
+                "token",
                 None,
             )
             .unwrap();
 
-        let cw_template_contract = CwTemplateContract(cw_template_contract_addr);
+        let mut suite = Suite {
+            app,
+            escrow: EscrowContract(escrow),
+            token,
+        };
+        let factory = format!("0x{}", "11".repeat(20));
+        let msg = suite
+            .escrow
+            .register_chain(
+                Uint256::from(DST_CHAIN_ID),
+                AddressFormat::Evm,
+                factory,
+                None,
+            )
+            .unwrap();
+        suite.execute(OWNER, msg).unwrap();
+        let dst_token = format!("0x{}", "dd".repeat(20));
+        let msg = suite
+            .escrow
+            .register_token(Uint256::from(DST_CHAIN_ID), suite.token.clone(), dst_token)
+            .unwrap();
+        suite.execute(OWNER, msg).unwrap();
+        suite
+    }
+
+    fn execute(&mut self, sender: &str, msg: WasmMsg) -> Result<AppResponse, ContractError> {
+        self.app
+            .execute(Addr::unchecked(sender), msg.into())
+            .map_err(|err| err.downcast().unwrap())
+    }
+
+    /// Withdraws an escrow whose secret is its own address, as every escrow here is set up
+    fn withdraw(
+        &mut self,
+        sender: &str,
+        escrow_address: &str,
+    ) -> Result<AppResponse, ContractError> {
+        let msg = self
+            .escrow
+            .withdraw(escrow_address, hex::encode(escrow_address))
+            .unwrap();
+        self.execute(sender, msg)
+    }
 
-        (app, cw_template_contract)
+    fn advance(&mut self, seconds: u64) {
+        self.app.update_block(|block| {
+            block.time = block.time.plus_seconds(seconds);
+            block.height += 1;
+        });
+    }
+
+    fn approve(&mut self, owner: &str, amount: u128) {
+        let msg = Cw20ExecuteMsg::IncreaseAllowance {
+            spender: self.escrow.addr().to_string(),
+            amount: Uint128::from(amount),
+            expires: None,
+        };
+        self.app
+            .execute_contract(Addr::unchecked(owner), self.token.clone(), &msg, &[])
+            .unwrap();
+    }
+
+    fn balance(&self, account: &str, denom: &str) -> u128 {
+        self.app
+            .wrap()
+            .query_balance(account, denom)
+            .unwrap()
+            .amount
+            .u128()
+    }
+
+    fn token_balance(&self, account: &str) -> u128 {
+        let msg = Cw20QueryMsg::Balance {
+            address: account.to_string(),
+        };
+        let res: BalanceResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(self.token.clone(), &msg)
+            .unwrap();
+        res.balance.u128()
     }
 
-    mod count {
-        use super::*;
-        use crate::msg::ExecuteMsg;
+    fn status(&self, escrow_address: &str) -> Option<EscrowStatus> {
+        let querier = self.app.wrap();
+        let dst = self.escrow.dst_escrow(&querier, escrow_address).unwrap();
+        let src = self.escrow.src_escrow(&querier, escrow_address).unwrap();
+        dst.status.or(src.status)
+    }
+}
+
+fn deposit(count: u128) -> Coin {
+    coin(count * DEPOSIT, DEPOSIT_DENOM)
+}
+
+#[test]
+fn dst_escrow_lifecycle() {
+    let mut suite = Suite::new();
+    let timelocks = Timelocks::pack([0, 0, 0, 0, 10, 100, 1000]);
+    let deployed_at = suite.app.block_info().time.seconds();
+
+    // The taker locks the maker's native coins in one escrow per way of settling it
+    let escrows = [
+        "cosmos1withdrawn",
+        "cosmos1public",
+        "cosmos1cancelled",
+        "cosmos1rescued",
+    ];
+    for escrow_address in escrows {
+        let immutables = Immutables {
+            order_hash: format!("0x{}", "ab".repeat(32)),
+            hashlock: hashlock_of(escrow_address.as_bytes()),
+            maker: Addr::unchecked(MAKER),
+            taker: Addr::unchecked(TAKER),
+            token: Addr::unchecked(NATIVE_DENOM),
+            amount: Uint128::from(AMOUNT),
+            safety_deposit: Uint128::from(DEPOSIT),
+            timelocks,
+        };
+        let funds = vec![coin(AMOUNT, NATIVE_DENOM), deposit(1)];
+        let timestamp = Uint128::from(deployed_at + 3600);
+        let msg = suite
+            .escrow
+            .create_dst_escrow(escrow_address, immutables, timestamp, funds)
+            .unwrap();
+        suite.execute(TAKER, msg).unwrap();
+    }
+    assert_eq!(
+        suite.balance(suite.escrow.addr().as_str(), NATIVE_DENOM),
+        4 * AMOUNT
+    );
+
+    // Nothing settles before the withdrawal period
+    let err = suite.withdraw(TAKER, "cosmos1withdrawn").unwrap_err();
+    assert!(matches!(err, ContractError::InvalidTime {}));
+
+    // Private withdrawal: only the taker, who takes the deposit
+    suite.advance(10);
+    let err = suite.withdraw(PUBLIC, "cosmos1withdrawn").unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let public_withdraw = suite
+        .escrow
+        .public_withdraw("cosmos1public", hex::encode("cosmos1public"))
+        .unwrap();
+    let err = suite.execute(PUBLIC, public_withdraw.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidTime {}));
+    suite.withdraw(TAKER, "cosmos1withdrawn").unwrap();
+
+    // Public withdrawal: anyone with the secret, who takes the deposit
+    suite.advance(90);
+    suite.execute(PUBLIC, public_withdraw).unwrap();
+
+    // Cancellation: withdrawals close and the taker takes everything back
+    suite.advance(900);
+    let err = suite.withdraw(TAKER, "cosmos1cancelled").unwrap_err();
+    assert!(matches!(err, ContractError::InvalidTime {}));
+    let msg = suite.escrow.cancel("cosmos1cancelled").unwrap();
+    suite.execute(TAKER, msg).unwrap();
+
+    // Rescue: whatever is left after the rescue delay goes to the taker
+    let msg = suite.escrow.rescue("cosmos1rescued").unwrap();
+    let err = suite.execute(TAKER, msg.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidTime {}));
+    suite.advance(RESCUE_DELAY);
+    suite.execute(TAKER, msg).unwrap();
+
+    assert_eq!(
+        suite.status("cosmos1withdrawn"),
+        Some(EscrowStatus::Withdrawn)
+    );
+    assert_eq!(suite.status("cosmos1public"), Some(EscrowStatus::Withdrawn));
+    assert_eq!(
+        suite.status("cosmos1cancelled"),
+        Some(EscrowStatus::Cancelled)
+    );
+    assert_eq!(suite.status("cosmos1rescued"), Some(EscrowStatus::Rescued));
+
+    // The maker was paid twice; the taker got back all but the public caller's deposit
+    assert_eq!(
+        suite.balance(MAKER, NATIVE_DENOM),
+        INITIAL_BALANCE + 2 * AMOUNT
+    );
+    assert_eq!(
+        suite.balance(TAKER, NATIVE_DENOM),
+        INITIAL_BALANCE - 2 * AMOUNT
+    );
+    assert_eq!(
+        suite.balance(TAKER, DEPOSIT_DENOM),
+        INITIAL_BALANCE - DEPOSIT
+    );
+    assert_eq!(suite.balance(PUBLIC, DEPOSIT_DENOM), DEPOSIT);
+    assert_eq!(suite.balance(suite.escrow.addr().as_str(), NATIVE_DENOM), 0);
+    assert_eq!(
+        suite.balance(suite.escrow.addr().as_str(), DEPOSIT_DENOM),
+        0
+    );
+}
+
+#[test]
+fn src_escrow_lifecycle_with_cw20() {
+    let mut suite = Suite::new();
+    let timelocks = Timelocks::pack([10, 100, 1000, 2000, 0, 0, 0]);
+
+    // The maker locks tokens from its allowance, attaching only the safety deposits
+    let escrows = [
+        "cosmos1withdrawn",
+        "cosmos1public",
+        "cosmos1cancelled",
+        "cosmos1expired",
+    ];
+    suite.approve(MAKER, 4 * AMOUNT);
+    for escrow_address in escrows {
+        let msg = create_src_escrow(&suite, escrow_address, timelocks, AMOUNT, DEPOSIT);
+        suite.execute(MAKER, msg).unwrap();
+    }
+    assert_eq!(suite.token_balance(MAKER), INITIAL_BALANCE - 4 * AMOUNT);
+    assert_eq!(
+        suite.token_balance(suite.escrow.addr().as_str()),
+        4 * AMOUNT
+    );
+
+    // Tokens cannot be locked past the allowance, which reverts the whole creation
+    let msg = create_src_escrow(&suite, "cosmos1unfunded", timelocks, AMOUNT, DEPOSIT);
+    let res = suite.app.execute(Addr::unchecked(MAKER), msg.into());
+    assert!(res.is_err());
+    assert_eq!(suite.status("cosmos1unfunded"), None);
+
+    let err = suite.withdraw(TAKER, "cosmos1withdrawn").unwrap_err();
+    assert!(matches!(err, ContractError::InvalidTime {}));
+
+    // Private withdrawal pays the taker the maker's tokens
+    suite.advance(10);
+    suite.withdraw(TAKER, "cosmos1withdrawn").unwrap();
+
+    // Public withdrawal still pays the taker, the caller takes the deposit
+    suite.advance(90);
+    let msg = suite
+        .escrow
+        .public_withdraw("cosmos1public", hex::encode("cosmos1public"))
+        .unwrap();
+    suite.execute(PUBLIC, msg).unwrap();
+
+    // Private cancellation returns the tokens to the maker
+    suite.advance(900);
+    let err = suite.withdraw(TAKER, "cosmos1cancelled").unwrap_err();
+    assert!(matches!(err, ContractError::InvalidTime {}));
+    let msg = suite.escrow.cancel("cosmos1cancelled").unwrap();
+    suite.execute(TAKER, msg).unwrap();
+
+    // Public cancellation lets anyone return them once the taker stops caring
+    let msg = suite.escrow.public_cancel("cosmos1expired").unwrap();
+    let err = suite.execute(PUBLIC, msg.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidTime {}));
+    suite.advance(1000);
+    suite.execute(PUBLIC, msg).unwrap();
+
+    assert_eq!(
+        suite.status("cosmos1withdrawn"),
+        Some(EscrowStatus::Withdrawn)
+    );
+    assert_eq!(suite.status("cosmos1public"), Some(EscrowStatus::Withdrawn));
+    assert_eq!(
+        suite.status("cosmos1cancelled"),
+        Some(EscrowStatus::Cancelled)
+    );
+    assert_eq!(
+        suite.status("cosmos1expired"),
+        Some(EscrowStatus::Cancelled)
+    );
+
+    // Two fills went to the taker and two orders were refunded to the maker
+    assert_eq!(suite.token_balance(MAKER), INITIAL_BALANCE - 2 * AMOUNT);
+    assert_eq!(suite.token_balance(TAKER), INITIAL_BALANCE + 2 * AMOUNT);
+    assert_eq!(suite.token_balance(suite.escrow.addr().as_str()), 0);
+    assert_eq!(
+        suite.balance(MAKER, DEPOSIT_DENOM),
+        INITIAL_BALANCE - 4 * DEPOSIT
+    );
+    assert_eq!(
+        suite.balance(TAKER, DEPOSIT_DENOM),
+        INITIAL_BALANCE + 2 * DEPOSIT
+    );
+    assert_eq!(suite.balance(PUBLIC, DEPOSIT_DENOM), 2 * DEPOSIT);
+}
+
+#[test]
+fn sdk_builds_what_the_contract_stores() {
+    let mut suite = Suite::new();
+    let order = OrderBuilder::new(MAKER, suite.token.as_str(), NATIVE_DENOM)
+        .salt(1)
+        .amounts(AMOUNT, 2 * AMOUNT)
+        .build();
+    let secret = b"resolver secret";
+    let immutables = ImmutablesBuilder::dst(&order, TAKER)
+        .secret(secret)
+        .safety_deposit(DEPOSIT)
+        .timelock(Stage::DstWithdrawal, 10)
+        .timelock(Stage::DstCancellation, 1000)
+        .build()
+        .unwrap();
+    let escrow_address = "escrow_sdk";
+
+    // The taker opens the destination leg with exactly the funds the contract expects
+    let funds = sdk::locked_funds(&immutables, DEPOSIT_DENOM, false);
+    let timestamp = Uint128::from(suite.app.block_info().time.seconds() + 3600);
+    let msg = suite
+        .escrow
+        .create_dst_escrow(escrow_address, immutables.clone(), timestamp, funds)
+        .unwrap();
+    suite.execute(TAKER, msg).unwrap();
+    let res = suite
+        .escrow
+        .dst_escrow(&suite.app.wrap(), escrow_address)
+        .unwrap();
+    assert_eq!(res.immutables, Some(immutables));
+
+    // The secret behind the builder's hashlock unlocks it for the order's receiver
+    suite.advance(10);
+    let msg = suite
+        .escrow
+        .withdraw(escrow_address, hex::encode(secret))
+        .unwrap();
+    suite.execute(TAKER, msg).unwrap();
+    assert_eq!(
+        suite.balance(MAKER, NATIVE_DENOM),
+        INITIAL_BALANCE + 2 * AMOUNT
+    );
+}
+
+/// A source escrow of the maker's CW20 tokens, with the escrow address as its secret
+fn create_src_escrow(
+    suite: &Suite,
+    escrow_address: &str,
+    timelocks: Uint256,
+    amount: u128,
+    safety_deposit: u128,
+) -> WasmMsg {
+    let order = Order {
+        salt: Uint128::from(1u128),
+        maker: Addr::unchecked(MAKER),
+        receiver: Addr::unchecked(MAKER),
+        maker_asset: suite.token.clone(),
+        taker_asset: Addr::unchecked("taker_asset"),
+        making_amount: Uint128::from(amount),
+        taking_amount: Uint128::from(2 * amount),
+    };
+
+    let extra_data = extra_data(
+        keccak(escrow_address.as_bytes()),
+        (safety_deposit, 0),
+        timelocks,
+    );
+
+    let funds = [coin(safety_deposit, DEPOSIT_DENOM)]
+        .into_iter()
+        .filter(|coin| !coin.amount.is_zero())
+        .collect();
+    suite
+        .escrow
+        .create_src_escrow(
+            escrow_address,
+            order,
+            Binary::default(),
+            hashlock_of(escrow_address.as_bytes()),
+            TAKER,
+            Uint128::from(amount),
+            Uint128::from(2 * amount),
+            Uint128::from(amount),
+            extra_data,
+            funds,
+        )
+        .unwrap()
+}
+
+const ESCROW: &str = "cosmos1escrow";
+
+/// Why the model expects an action to be rejected
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Rejection {
+    Unauthorized,
+    Unsupported,
+    OutsideWindow,
+    Closed,
+}
+
+impl Rejection {
+    fn matches(self, err: &ContractError) -> bool {
+        match self {
+            Rejection::Unauthorized => matches!(err, ContractError::Unauthorized {}),
+            Rejection::Unsupported => matches!(err, ContractError::UnsupportedAction {}),
+            Rejection::OutsideWindow => matches!(err, ContractError::InvalidTime {}),
+            Rejection::Closed => matches!(err, ContractError::InvalidStatusTransition { .. }),
+        }
+    }
+}
+
+/// A single escrow as the timelocks spec describes it, written out stage by stage rather
+/// than through `Timelocks` so the contract is checked against an independent reading
+struct Model {
+    side: EscrowSide,
+    offsets: [u32; 7],
+    deployed_at: u64,
+    amount: u128,
+    safety_deposit: u128,
+    settled: Option<(EscrowAction, &'static str)>,
+}
+
+impl Model {
+    fn check(&self, action: EscrowAction, caller: &str, now: u64) -> Result<(), Rejection> {
+        let taker_only = matches!(
+            action,
+            EscrowAction::Withdraw | EscrowAction::Cancel | EscrowAction::Rescue
+        );
+        if taker_only && caller != TAKER {
+            return Err(Rejection::Unauthorized);
+        }
+
+        let at = |stage: usize| self.deployed_at + self.offsets[stage] as u64;
+        let (start, end) = match (self.side, action) {
+            (EscrowSide::Src, EscrowAction::Withdraw) => (at(0), Some(at(2))),
+            (EscrowSide::Src, EscrowAction::PublicWithdraw) => (at(1), Some(at(2))),
+            (EscrowSide::Src, EscrowAction::Cancel) => (at(2), None),
+            (EscrowSide::Src, EscrowAction::PublicCancel) => (at(3), None),
+            (EscrowSide::Dst, EscrowAction::Withdraw) => (at(4), Some(at(6))),
+            (EscrowSide::Dst, EscrowAction::PublicWithdraw) => (at(5), Some(at(6))),
+            (EscrowSide::Dst, EscrowAction::Cancel) => (at(6), None),
+            (EscrowSide::Dst, EscrowAction::PublicCancel) => return Err(Rejection::Unsupported),
+            (_, EscrowAction::Rescue) => (self.deployed_at + RESCUE_DELAY, None),
+        };
+        if now < start || end.is_some_and(|end| now >= end) {
+            return Err(Rejection::OutsideWindow);
+        }
+
+        if self.settled.is_some() {
+            return Err(Rejection::Closed);
+        }
+        Ok(())
+    }
+
+    /// Escrowed asset and deposit denom balances `account` should hold by now
+    fn holdings(&self, account: &str) -> (u128, u128) {
+        let (mut asset, mut deposit) = match account {
+            PUBLIC => (0, 0),
+            _ => (INITIAL_BALANCE, INITIAL_BALANCE),
+        };
+        let locker = match self.side {
+            EscrowSide::Src => MAKER,
+            EscrowSide::Dst => TAKER,
+        };
+        if account == locker {
+            asset -= self.amount;
+            deposit -= self.safety_deposit;
+        }
+
+        match self.settled {
+            // A rescue hands everything back to the taker
+            Some((EscrowAction::Rescue, _)) if account == TAKER => {
+                asset += self.amount;
+                deposit += self.safety_deposit;
+            }
+            None | Some((EscrowAction::Rescue, _)) => {}
+            Some((action, caller)) => {
+                let withdrawn = matches!(
+                    action,
+                    EscrowAction::Withdraw | EscrowAction::PublicWithdraw
+                );
+                let recipient = match (self.side, withdrawn) {
+                    (EscrowSide::Src, true) | (EscrowSide::Dst, false) => TAKER,
+                    (EscrowSide::Src, false) | (EscrowSide::Dst, true) => MAKER,
+                };
+                if account == recipient {
+                    asset += self.amount;
+                }
+                if account == caller {
+                    deposit += self.safety_deposit;
+                }
+            }
+        }
+        (asset, deposit)
+    }
+
+    fn status(&self) -> EscrowStatus {
+        match self.settled {
+            None => EscrowStatus::Active,
+            Some((EscrowAction::Withdraw | EscrowAction::PublicWithdraw, _)) => {
+                EscrowStatus::Withdrawn
+            }
+            Some((EscrowAction::Cancel | EscrowAction::PublicCancel, _)) => EscrowStatus::Cancelled,
+            Some((EscrowAction::Rescue, _)) => EscrowStatus::Rescued,
+        }
+    }
+}
+
+/// Balances of the escrowed asset of `side` and of the deposit denom
+fn holdings(suite: &Suite, side: EscrowSide, account: &str) -> (u128, u128) {
+    let asset = match side {
+        EscrowSide::Src => suite.token_balance(account),
+        EscrowSide::Dst => suite.balance(account, NATIVE_DENOM),
+    };
+    (asset, suite.balance(account, DEPOSIT_DENOM))
+}
+
+fn call(
+    suite: &mut Suite,
+    action: EscrowAction,
+    caller: &str,
+) -> Result<AppResponse, ContractError> {
+    let secret = hex::encode(ESCROW);
+    let msg = match action {
+        EscrowAction::Withdraw => suite.escrow.withdraw(ESCROW, secret),
+        EscrowAction::PublicWithdraw => suite.escrow.public_withdraw(ESCROW, secret),
+        EscrowAction::Cancel => suite.escrow.cancel(ESCROW),
+        EscrowAction::PublicCancel => suite.escrow.public_cancel(ESCROW),
+        EscrowAction::Rescue => suite.escrow.rescue(ESCROW),
+    }
+    .unwrap();
+    suite.execute(caller, msg)
+}
+
+/// Time to let pass, the action and who calls it. Some steps jump past the rescue delay.
+fn step() -> impl Strategy<Value = (u64, EscrowAction, &'static str)> {
+    (
+        prop_oneof![4 => 0..400u64, 1 => Just(RESCUE_DELAY)],
+        proptest::sample::select(EscrowAction::ALL.to_vec()),
+        proptest::sample::select(vec![MAKER, TAKER, PUBLIC]),
+    )
+}
+
+/// Per-side offsets in stage order, as `ExtraDataArgs` requires them to be
+fn offsets() -> impl Strategy<Value = [u32; 7]> {
+    (
+        proptest::array::uniform4(0..1_500u32),
+        proptest::array::uniform3(0..1_500u32),
+    )
+        .prop_map(|(mut src, mut dst)| {
+            src.sort_unstable();
+            dst.sort_unstable();
+            [src[0], src[1], src[2], src[3], dst[0], dst[1], dst[2]]
+        })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn escrow_state_machine(
+        side in prop_oneof![Just(EscrowSide::Src), Just(EscrowSide::Dst)],
+        offsets in offsets(),
+        amount in 1..=INITIAL_BALANCE / 2,
+        safety_deposit in 0..=10 * DEPOSIT,
+        steps in proptest::collection::vec(step(), 1..12),
+    ) {
+        let mut suite = Suite::new();
+        let deployed_at = suite.app.block_info().time.seconds();
+        let timelocks = Timelocks::pack(offsets);
+
+        match side {
+            EscrowSide::Src => {
+                suite.approve(MAKER, amount);
+                let msg = create_src_escrow(&suite, ESCROW, timelocks, amount, safety_deposit);
+                suite.execute(MAKER, msg).unwrap();
+            }
+            EscrowSide::Dst => {
+                let immutables = Immutables {
+                    order_hash: format!("0x{}", "ab".repeat(32)),
+                    hashlock: hashlock_of(ESCROW.as_bytes()),
+                    maker: Addr::unchecked(MAKER),
+                    taker: Addr::unchecked(TAKER),
+                    token: Addr::unchecked(NATIVE_DENOM),
+                    amount: Uint128::from(amount),
+                    safety_deposit: Uint128::from(safety_deposit),
+                    timelocks,
+                };
+                let funds = [coin(amount, NATIVE_DENOM), coin(safety_deposit, DEPOSIT_DENOM)]
+                    .into_iter()
+                    .filter(|coin| !coin.amount.is_zero())
+                    .collect();
+                let timestamp = Uint128::from(deployed_at + 3600);
+                let msg = suite
+                    .escrow
+                    .create_dst_escrow(ESCROW, immutables, timestamp, funds)
+                    .unwrap();
+                suite.execute(TAKER, msg).unwrap();
+            }
+        }
+
+        let mut model = Model {
+            side,
+            offsets,
+            deployed_at,
+            amount,
+            safety_deposit,
+            settled: None,
+        };
+        let contract = suite.escrow.addr().to_string();
+        for (seconds, action, caller) in steps {
+            suite.advance(seconds);
+            let now = suite.app.block_info().time.seconds();
 
-        #[test]
-        fn count() {
-            let (mut app, cw_template_contract) = proper_instantiate();
+            // Only the allowed actor, inside the action's window, on an escrow that has not
+            // settled yet: a second withdrawal or cancellation is always refused
+            match (call(&mut suite, action, caller), model.check(action, caller, now)) {
+                (Ok(_), Ok(())) => model.settled = Some((action, caller)),
+                (Err(err), Err(rejection)) => prop_assert!(
+                    rejection.matches(&err),
+                    "{:?} by {} was rejected with {:?}, expected {:?}",
+                    action, caller, err, rejection
+                ),
+                (res, expected) => prop_assert!(
+                    false,
+                    "{:?} by {} at +{}s returned {:?}, expected {:?}",
+                    action, caller, now - deployed_at, res.map(|_| ()), expected
+                ),
+            }
 
-            let msg = ExecuteMsg::Increment {};
-            let cosmos_msg = cw_template_contract.call(msg).unwrap();
-            app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+            // Funds are conserved, the contract holds exactly the locked funds until the
+            // escrow settles and every account holds what the settlement says
+            let accounts = [MAKER, TAKER, PUBLIC, contract.as_str()];
+            let (assets, deposits) = accounts
+                .iter()
+                .map(|account| holdings(&suite, side, account))
+                .fold((0, 0), |(assets, deposits), (asset, deposit)| {
+                    (assets + asset, deposits + deposit)
+                });
+            prop_assert_eq!(assets, 2 * INITIAL_BALANCE);
+            prop_assert_eq!(deposits, 2 * INITIAL_BALANCE);
+            for account in [MAKER, TAKER, PUBLIC] {
+                prop_assert_eq!(holdings(&suite, side, account), model.holdings(account));
+            }
+            prop_assert_eq!(suite.status(ESCROW), Some(model.status()));
         }
     }
 }
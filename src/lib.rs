@@ -1,9 +1,25 @@
+pub mod auction;
 pub mod contract;
 pub mod error;
+pub mod events;
 pub mod execute;
+pub mod extension;
+pub mod extra_data;
+pub mod helpers;
+pub mod hooks;
+pub mod ibc;
+#[cfg(test)]
+mod integration_tests;
+pub mod migrations;
 pub mod msg;
+pub mod neutron;
 pub mod query;
+#[cfg(any(feature = "library", test))]
+pub mod sdk;
 pub mod state;
+#[cfg(test)]
+mod testing;
+pub mod timelocks;
 // pub use msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
 // pub use state::Immutables;
 
@@ -0,0 +1,274 @@
+use cosmwasm_std::{to_json_binary, DepsMut, Env, Order, StdError, StdResult, Storage};
+use semver::Version;
+use serde::Serialize;
+
+use crate::contract::CONTRACT_NAME;
+use crate::error::ContractError;
+use crate::execute::validate_rescue_delay;
+use crate::msg::MigrateMsg;
+use crate::state::{
+    Config, DstEscrow, EscrowSide, EscrowStatus, SideStats, SrcEscrow, SrcEscrowData,
+    UnmigratedEscrow, UnmigratedReason, ASSET_STATS, CONFIG, DST_ESCROWS, SIDE_STATS, SRC_ESCROWS,
+    UNMIGRATED_ESCROWS,
+};
+
+/// Storage layout of v0.1, which kept bare records with no status, indexes or config
+pub mod v0_1 {
+    use cosmwasm_std::{Addr, Binary, Uint128};
+    use cw_storage_plus::Map;
+    use schemars::JsonSchema;
+    use serde::{Deserialize, Serialize};
+
+    use crate::error::ContractError;
+    use crate::extra_data::EscrowExtraData;
+    use crate::state::{self, Immutables, Order};
+
+    /// Source escrow data as stored in v0.1, with the extra data still encoded
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+    #[serde(rename_all = "snake_case")]
+    pub struct SrcEscrowData {
+        pub order: Order,
+        pub extension: Binary,
+        pub order_hash: String,
+        pub taker: Addr,
+        pub making_amount: Uint128,
+        pub taking_amount: Uint128,
+        pub remaining_making_amount: Uint128,
+        pub extra_data: Binary,
+    }
+
+    impl SrcEscrowData {
+        pub fn decode(self) -> Result<state::SrcEscrowData, ContractError> {
+            Ok(state::SrcEscrowData {
+                order: self.order,
+                extension: self.extension,
+                order_hash: self.order_hash,
+                taker: self.taker,
+                making_amount: self.making_amount,
+                taking_amount: self.taking_amount,
+                remaining_making_amount: self.remaining_making_amount,
+                extra_data: EscrowExtraData::decode(&self.extra_data)?,
+            })
+        }
+    }
+
+    pub const DST_ESCROWS: Map<String, Immutables> = Map::new("dst_escrows");
+    pub const SRC_ESCROWS: Map<String, SrcEscrowData> = Map::new("src_escrows");
+}
+
+/// Version of the code that last wrote the contract's state. v0.1 predates cw2 and
+/// recorded nothing.
+pub fn stored_version(storage: &dyn Storage) -> Result<Version, ContractError> {
+    match cw2::CONTRACT.may_load(storage)? {
+        Some(stored) if stored.contract != CONTRACT_NAME => {
+            Err(ContractError::InvalidContractName {
+                name: stored.contract,
+            })
+        }
+        Some(stored) => parse_version(&stored.version),
+        None => Ok(Version::new(0, 1, 0)),
+    }
+}
+
+pub fn parse_version(version: &str) -> Result<Version, ContractError> {
+    version
+        .parse()
+        .map_err(|_| StdError::generic_err(format!("Invalid version {}", version)).into())
+}
+
+/// Re-saves v0.1 escrows as indexed records with a lifecycle status, and returns how many
+/// were set aside instead.
+///
+/// v0.1 recorded neither deployment times nor locked funds, so migrated escrows are
+/// anchored to the migration block and never pay anything out. Its extra data was free-form
+/// and its hashlocks were not unique, so escrows whose extra data does not decode, or whose
+/// hashlock an earlier escrow of the same side already holds, are kept in
+/// `UNMIGRATED_ESCROWS` as stored.
+pub fn migrate_from_v0_1(storage: &mut dyn Storage, env: &Env) -> Result<u32, ContractError> {
+    let mut unmigrated = 0;
+    let dst_escrows = v0_1::DST_ESCROWS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (escrow_address, immutables) in dst_escrows {
+        // Drop the bare record first, the indexed map would fail to read it back
+        v0_1::DST_ESCROWS.remove(storage, escrow_address.clone());
+        // Uniqueness is per side: both legs of a swap share a hashlock
+        let hashlock = immutables.hashlock.clone();
+        if DST_ESCROWS.idx.hashlock.item(storage, hashlock)?.is_some() {
+            let reason = UnmigratedReason::DuplicateHashlock;
+            set_aside(
+                storage,
+                EscrowSide::Dst,
+                escrow_address,
+                reason,
+                &immutables,
+            )?;
+            unmigrated += 1;
+            continue;
+        }
+        let escrow = DstEscrow {
+            immutables,
+            status: EscrowStatus::Active,
+            deployed_at: env.block.time,
+            funded: false,
+            counterpart_verified: false,
+        };
+        DST_ESCROWS.save(storage, escrow_address, &escrow)?;
+    }
+
+    let src_escrows = v0_1::SRC_ESCROWS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (escrow_address, escrow_data) in src_escrows {
+        v0_1::SRC_ESCROWS.remove(storage, escrow_address.clone());
+        let escrow_data = match decode_src_escrow_data(storage, &escrow_data)? {
+            Ok(decoded) => decoded,
+            Err(reason) => {
+                set_aside(
+                    storage,
+                    EscrowSide::Src,
+                    escrow_address,
+                    reason,
+                    &escrow_data,
+                )?;
+                unmigrated += 1;
+                continue;
+            }
+        };
+        let escrow = SrcEscrow {
+            escrow_data,
+            status: EscrowStatus::Active,
+            deployed_at: env.block.time,
+            funded: false,
+            counterpart_verified: false,
+            fee_config: None,
+        };
+        SRC_ESCROWS.save(storage, escrow_address, &escrow)?;
+    }
+
+    Ok(unmigrated)
+}
+
+/// Decodes legacy source escrow data, or gives the reason it cannot be migrated
+fn decode_src_escrow_data(
+    storage: &dyn Storage,
+    escrow_data: &v0_1::SrcEscrowData,
+) -> Result<Result<SrcEscrowData, UnmigratedReason>, ContractError> {
+    let Ok(decoded) = escrow_data.clone().decode() else {
+        return Ok(Err(UnmigratedReason::UndecodableExtraData));
+    };
+    let hashlock = decoded.extra_data.hashlock_info.clone();
+    if SRC_ESCROWS.idx.hashlock.item(storage, hashlock)?.is_some() {
+        return Ok(Err(UnmigratedReason::DuplicateHashlock));
+    }
+    Ok(Ok(decoded))
+}
+
+/// Keeps a legacy escrow that could not be migrated as it was stored
+fn set_aside<T: Serialize>(
+    storage: &mut dyn Storage,
+    side: EscrowSide,
+    escrow_address: String,
+    reason: UnmigratedReason,
+    record: &T,
+) -> Result<(), ContractError> {
+    let escrow = UnmigratedEscrow {
+        reason,
+        record: to_json_binary(record)?,
+    };
+    UNMIGRATED_ESCROWS.save(storage, (side.as_str(), escrow_address), &escrow)?;
+    Ok(())
+}
+
+/// Builds the stats of each side that has none yet, which is every side when migrating
+/// from a version that did not keep them. Later transitions keep the stats up to date.
+pub fn seed_stats(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    for side in [EscrowSide::Dst, EscrowSide::Src] {
+        if SIDE_STATS.has(storage, side.as_str()) {
+            continue;
+        }
+        let escrows = match side {
+            EscrowSide::Dst => DST_ESCROWS
+                .range(storage, None, None, Order::Ascending)
+                .map(|item| {
+                    item.map(|(_, escrow)| (escrow.status, escrow.funded, escrow.immutables))
+                })
+                .collect::<StdResult<Vec<_>>>()?,
+            EscrowSide::Src => SRC_ESCROWS
+                .range(storage, None, None, Order::Ascending)
+                .map(|item| {
+                    item.map(|(_, escrow)| {
+                        let immutables = escrow.escrow_data.immutables();
+                        (escrow.status, escrow.funded, immutables)
+                    })
+                })
+                .collect::<StdResult<Vec<_>>>()?,
+        };
+
+        let mut stats = SideStats::default();
+        for (status, funded, immutables) in escrows {
+            stats.created += 1;
+            match status {
+                EscrowStatus::Withdrawn => stats.withdrawn += 1,
+                EscrowStatus::Cancelled => stats.cancelled += 1,
+                EscrowStatus::Active | EscrowStatus::Rescued => {}
+            }
+            if !funded {
+                continue;
+            }
+            ASSET_STATS.update(
+                storage,
+                immutables.token.as_str(),
+                |asset| -> StdResult<_> {
+                    let mut asset = asset.unwrap_or_default();
+                    match status {
+                        EscrowStatus::Active => asset.locked += immutables.amount,
+                        EscrowStatus::Withdrawn => asset.settled += immutables.amount,
+                        EscrowStatus::Cancelled | EscrowStatus::Rescued => {}
+                    }
+                    Ok(asset)
+                },
+            )?;
+        }
+        SIDE_STATS.save(storage, side.as_str(), &stats)?;
+    }
+    Ok(())
+}
+
+/// Applies the config fields given in the migrate message on top of the stored config
+pub fn migrate_config(deps: DepsMut, msg: &MigrateMsg) -> Result<(), ContractError> {
+    let current = CONFIG.may_load(deps.storage)?;
+    let missing = |field: &str| ContractError::MissingMigrationField {
+        field: field.to_string(),
+    };
+
+    let owner = match &msg.owner {
+        Some(owner) => deps.api.addr_validate(owner)?,
+        None => current
+            .as_ref()
+            .map(|config| config.owner.clone())
+            .ok_or_else(|| missing("owner"))?,
+    };
+    let safety_deposit_denom = msg
+        .safety_deposit_denom
+        .clone()
+        .or_else(|| {
+            current
+                .as_ref()
+                .map(|config| config.safety_deposit_denom.clone())
+        })
+        .ok_or_else(|| missing("safety_deposit_denom"))?;
+    let rescue_delay = msg
+        .rescue_delay
+        .or_else(|| current.as_ref().map(|config| config.rescue_delay))
+        .ok_or_else(|| missing("rescue_delay"))?;
+    validate_rescue_delay(deps.storage, rescue_delay)?;
+
+    let config = Config {
+        owner,
+        safety_deposit_denom,
+        rescue_delay,
+    };
+    CONFIG.save(deps.storage, &config)?;
+    Ok(())
+}
@@ -1,26 +1,22 @@
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-pub struct SrcEscrowResponse {
-    pub escrow_address: String,
-    pub escrow_data: Option<SrcEscrowData>,
-}
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Binary, Coin, Timestamp, Uint128, Uint256};
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-pub struct SrcEscrowListResponse {
-    pub escrows: Vec<(String, SrcEscrowData)>,
-}use cosmwasm_std::{Uint128, Binary};
-use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
-
-use crate::state::{Immutables, Order, SrcEscrowData};
+use crate::auction::AuctionDetails;
+use crate::extension::Extension;
+use crate::state::{
+    AddressFormat, AssetStats, DstEscrow, EscrowSide, EscrowStatus, FeeConfig, Immutables, Order,
+    RelayedSecret, RevealedSecret, SideStats, SrcEscrow, SrcEscrowData, SupportedChain,
+    SupportedToken, SweepConfig,
+};
+use crate::timelocks::{ActionCaller, EscrowAction, EscrowStage};
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-pub struct InstantiateMsg {}
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub safety_deposit_denom: String,
+    pub rescue_delay: u64,
+}
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
+#[cw_serde]
 pub enum ExecuteMsg {
     CreateDstEscrow {
         escrow_address: String,  // User-provided cosmos address
@@ -38,40 +34,373 @@ pub enum ExecuteMsg {
         remaining_making_amount: Uint128,
         extra_data: Binary,
     },
+    Withdraw {
+        escrow_address: String,
+        secret: String, // bytes32 as hex string
+    },
+    PublicWithdraw {
+        escrow_address: String,
+        secret: String,
+    },
+    Cancel {
+        escrow_address: String,
+    },
+    PublicCancel {
+        escrow_address: String,
+    },
+    Rescue {
+        escrow_address: String,
+    },
+    BatchCreateDstEscrows {
+        escrows: Vec<BatchDstEscrow>, // the attached funds cover all of them
+        mode: BatchMode,
+    },
+    BatchWithdraw {
+        withdrawals: Vec<BatchWithdrawal>,
+        mode: BatchMode,
+    },
+    BatchCancel {
+        escrow_addresses: Vec<String>,
+        mode: BatchMode,
+    },
+    UpdateFeeConfig {
+        fee_bps: u16,
+        integrator_share_bps: u16,
+        fee_collector: String,
+    },
+    UpdateSweepConfig {
+        batch_size: u32,
+    },
+    /// Sent by the contract to itself to cancel one expired escrow of a sweep
+    SweepEscrow {
+        escrow_address: String,
+    },
+    RegisterChain {
+        chain_id: Uint256,
+        address_format: AddressFormat,
+        factory: String,               // escrow factory on the counterpart chain
+        connection_id: Option<String>, // IBC connection to query it over, for Cosmos chains
+    },
+    RemoveChain {
+        chain_id: Uint256,
+    },
+    RegisterToken {
+        chain_id: Uint256,
+        src_token: String,
+        dst_token: String, // in the counterpart chain's address format
+    },
+    RemoveToken {
+        chain_id: Uint256,
+        src_token: String,
+    },
+    RequestDstEscrow {
+        src_escrow_address: String,
+        channel_id: String,     // escrow channel to the destination chain
+        escrow_address: String, // address of the escrow on the destination chain
+        immutables: Immutables, // as the destination chain sees them
+    },
+    FundDstEscrow {
+        escrow_address: String,
+    },
+    VerifyCounterpart {
+        escrow_address: String,
+        chain_id: Uint256,           // chain the counterpart escrow is on
+        counterpart_address: String, // its address in that chain's escrow factory
+    },
+    UpdateSecretRelay {
+        channel_id: Option<String>, // none stops forwarding revealed secrets
+    },
+    /// Sent in the `wasm` memo of an ICS-20 transfer; ibc-hooks executes it with the
+    /// transferred coins as funds
+    IbcHook {
+        channel: String,          // channel the transfer arrived on
+        original_sender: String,  // sender on the source chain
+        recovery_address: String, // may claim the coins if the escrow cannot be created
+        msg: Box<ExecuteMsg>,     // CreateDstEscrow or CreateSrcEscrow
+    },
+    ClaimFunds {},
+}
+
+/// How a batch handles items that fail
+#[cw_serde]
+#[derive(Copy, Eq)]
+pub enum BatchMode {
+    AllOrNothing, // any failing item fails the whole batch
+    BestEffort,   // failing items are skipped and reported in the response data
+}
+
+/// A destination escrow to create in a batch, as `CreateDstEscrow` takes it
+#[cw_serde]
+pub struct BatchDstEscrow {
+    pub escrow_address: String,
+    pub immutables: Immutables,
+    pub timestamp: Uint128,
+}
+
+/// An escrow to withdraw in a batch, as `Withdraw` takes it
+#[cw_serde]
+pub struct BatchWithdrawal {
+    pub escrow_address: String,
+    pub secret: String,
+}
+
+/// Direction to list escrows in. `start_after` and `end_before` follow it, so descending
+/// pages continue below `start_after`.
+#[cw_serde]
+#[derive(Copy, Eq)]
+pub enum OrderBy {
+    Ascending,
+    Descending,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
+impl From<OrderBy> for cosmwasm_std::Order {
+    fn from(order_by: OrderBy) -> Self {
+        match order_by {
+            OrderBy::Ascending => cosmwasm_std::Order::Ascending,
+            OrderBy::Descending => cosmwasm_std::Order::Descending,
+        }
+    }
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
 pub enum QueryMsg {
+    #[returns(DstEscrowResponse)]
     GetDstEscrow {
         escrow_address: String,  // Changed from escrow_key to escrow_address
     },
+    #[returns(SrcEscrowResponse)]
     GetSrcEscrow {
         escrow_address: String,
     },
+    #[returns(DstEscrowListResponse)]
     ListDstEscrows {
         start_after: Option<String>,
+        end_before: Option<String>,
         limit: Option<u32>,
+        order_by: Option<OrderBy>, // ascending by escrow address if unset
+        status: Option<EscrowStatus>,
+        token: Option<String>,
+        maker: Option<String>,
     },
+    #[returns(SrcEscrowListResponse)]
     ListSrcEscrows {
+        start_after: Option<String>,
+        end_before: Option<String>,
+        limit: Option<u32>,
+        order_by: Option<OrderBy>, // ascending by escrow address if unset
+        status: Option<EscrowStatus>,
+        token: Option<String>,
+        maker: Option<String>,
+    },
+    #[returns(EscrowsResponse)]
+    EscrowsByMaker {
+        side: EscrowSide,
+        maker: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(EscrowsResponse)]
+    EscrowsByTaker {
+        side: EscrowSide,
+        taker: String,
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    #[returns(EscrowsResponse)]
+    EscrowByOrderHash {
+        side: EscrowSide,
+        order_hash: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(HashlockEscrowResponse)]
+    EscrowByHashlock { hashlock: String },
+    #[returns(EscrowStageResponse)]
+    GetEscrowStage {
+        escrow_address: String,
+    },
+    #[returns(RevealedSecretResponse)]
+    GetRevealedSecret { hashlock: String },
+    #[returns(FeeConfigResponse)]
+    GetFeeConfig {},
+    #[returns(SweepConfigResponse)]
+    GetSweepConfig {},
+    #[returns(AuctionQuoteResponse)]
+    QuoteAuctionRate {
+        order: Order,
+        extension: Binary,
+    },
+    #[returns(ExtensionResponse)]
+    DecodeExtension {
+        extension: Binary,
+    },
+    #[returns(SupportedChainsResponse)]
+    ListSupportedChains {
+        start_after: Option<Uint256>,
+        limit: Option<u32>,
+    },
+    #[returns(SupportedTokensResponse)]
+    ListSupportedTokens {
+        chain_id: Uint256,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(SecretRelayResponse)]
+    GetSecretRelay {},
+    #[returns(RelayedSecretResponse)]
+    GetRelayedSecret { hashlock: String },
+    #[returns(ClaimableFundsResponse)]
+    GetClaimableFunds { address: String },
+    #[returns(StatsResponse)]
+    GetStats {
+        asset: Option<String>, // every asset if unset
+    },
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
+#[cw_serde]
 pub struct DstEscrowResponse {
     pub escrow_address: String,  // Changed from escrow_key
     pub immutables: Option<Immutables>,
+    pub status: Option<EscrowStatus>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
+#[cw_serde]
 pub struct DstEscrowListResponse {
-    pub escrows: Vec<(String, Immutables)>,
+    pub escrows: Vec<(String, DstEscrow)>,
+    pub next_start_after: Option<String>, // none on the last page; filtered pages can be short
+    pub total: u64,                       // every escrow created on this side, not just matches
+}
+
+#[cw_serde]
+pub struct SrcEscrowResponse {
+    pub escrow_address: String,
+    pub escrow_data: Option<SrcEscrowData>,
+    pub status: Option<EscrowStatus>,
+}
+
+#[cw_serde]
+pub struct SrcEscrowListResponse {
+    pub escrows: Vec<(String, SrcEscrow)>,
+    pub next_start_after: Option<String>, // none on the last page; filtered pages can be short
+    pub total: u64,                       // every escrow created on this side, not just matches
+}
+
+/// An escrow of either side, as returned by the index queries
+#[cw_serde]
+#[allow(clippy::large_enum_variant)]
+pub enum Escrow {
+    Dst(DstEscrow),
+    Src(SrcEscrow),
+}
+
+#[cw_serde]
+pub struct EscrowsResponse {
+    pub escrows: Vec<(String, Escrow)>,
+}
+
+#[cw_serde]
+pub struct HashlockEscrowResponse {
+    pub hashlock: String,
+    pub escrow: Option<(String, Escrow)>,
+}
+
+#[cw_serde]
+pub struct AvailableAction {
+    pub action: EscrowAction,
+    pub caller: ActionCaller,
+}
+
+#[cw_serde]
+pub struct EscrowStageResponse {
+    pub escrow_address: String,
+    pub side: EscrowSide,
+    pub status: EscrowStatus,
+    pub stage: EscrowStage,
+    pub actions: Vec<AvailableAction>, // callable at the queried block time
+    pub next_transition: Option<Timestamp>, // start of the next stage, if any
+}
+
+#[cw_serde]
+pub struct RevealedSecretResponse {
+    pub hashlock: String,
+    pub revealed: Option<RevealedSecret>,
+}
+
+#[cw_serde]
+pub struct FeeConfigResponse {
+    pub fee_config: Option<FeeConfig>, // none until the owner sets one
+}
+
+#[cw_serde]
+pub struct SweepConfigResponse {
+    pub sweep_config: SweepConfig, // the default until the owner sets one
+}
+
+#[cw_serde]
+pub struct AuctionQuoteResponse {
+    pub auction: Option<AuctionDetails>, // none if the order is not auctioned
+    pub rate_bump: u32,                  // in units of 1e-7, at the queried block time
+    pub taking_amount: Uint128,          // least a taker must pay to fill the whole order
+}
+
+#[cw_serde]
+pub struct ExtensionResponse {
+    pub extension: Extension,
+}
+
+#[cw_serde]
+pub struct SupportedChainsResponse {
+    pub chains: Vec<SupportedChain>,
+}
+
+#[cw_serde]
+pub struct SupportedTokensResponse {
+    pub tokens: Vec<SupportedToken>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-pub enum MigrateMsg {}
\ No newline at end of file
+#[cw_serde]
+pub struct SecretRelayResponse {
+    pub channel_id: Option<String>, // none if revealed secrets are not forwarded
+}
+
+#[cw_serde]
+pub struct RelayedSecretResponse {
+    pub hashlock: String,
+    pub relayed: Option<RelayedSecret>,
+}
+
+#[cw_serde]
+pub struct ClaimableFundsResponse {
+    pub address: String,
+    pub funds: Vec<Coin>,
+}
+
+/// Aggregate counters of both sides and escrowed volume per asset
+#[cw_serde]
+pub struct StatsResponse {
+    pub src: SideStats,
+    pub dst: SideStats,
+    pub assets: Vec<(String, AssetStats)>,
+}
+
+/// Data of a batch execution: the outcome of each item, in order
+#[cw_serde]
+pub struct BatchResponse {
+    pub results: Vec<BatchItemResult>,
+}
+
+#[cw_serde]
+pub struct BatchItemResult {
+    pub escrow_address: String,
+    pub error: Option<String>, // none if the item succeeded
+}
+
+/// Config to set while migrating. Fields left out keep their current value; migrating
+/// from v0.1, which had no config, requires all of them.
+#[cw_serde]
+pub struct MigrateMsg {
+    pub owner: Option<String>,
+    pub safety_deposit_denom: Option<String>,
+    pub rescue_delay: Option<u64>,
+}
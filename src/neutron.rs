@@ -0,0 +1,132 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Binary, CosmosMsg, CustomMsg, CustomQuery};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Reply id of an interchain query registration, whose response carries the query's id
+pub const REGISTER_QUERY_REPLY_ID: u64 = 2;
+
+// Query type of key-value interchain queries
+pub const KV_QUERY_TYPE: &str = "kv";
+
+// Store the wasm module keeps contract state in
+pub const WASM_STORE: &str = "wasm";
+
+// Prefix of a contract's state in the wasm store, followed by its canonical address
+const CONTRACT_STORE_PREFIX: u8 = 0x03;
+
+/// Messages of Neutron's interchain queries module, sent as custom messages
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NeutronMsg {
+    RegisterInterchainQuery {
+        query_type: String,
+        keys: Vec<KvKey>,
+        transactions_filter: String, // only used by transaction queries
+        connection_id: String,
+        update_period: u64, // in blocks
+    },
+    RemoveInterchainQuery {
+        query_id: u64,
+    },
+}
+
+impl CustomMsg for NeutronMsg {}
+
+impl From<NeutronMsg> for CosmosMsg<NeutronMsg> {
+    fn from(msg: NeutronMsg) -> Self {
+        CosmosMsg::Custom(msg)
+    }
+}
+
+/// A key to read from a store on the remote chain
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct KvKey {
+    pub path: String, // store name
+    pub key: Binary,
+}
+
+/// Custom queries answered by Neutron's interchain queries module
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NeutronQuery {
+    InterchainQueryResult { query_id: u64 },
+}
+
+impl CustomQuery for NeutronQuery {}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryResultResponse {
+    pub result: InterchainQueryResult,
+}
+
+/// Latest proven values of a key-value query's keys
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct InterchainQueryResult {
+    pub kv_results: Vec<StorageValue>,
+    pub height: u64, // remote chain height the values were proven at
+    pub revision: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StorageValue {
+    pub storage_prefix: String,
+    pub key: Binary,
+    pub value: Binary, // empty if the key is not set
+}
+
+/// Data of a successful `RegisterInterchainQuery`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RegisterQueryResponse {
+    pub id: u64,
+}
+
+/// Callbacks Neutron makes into the contract
+#[cw_serde]
+pub enum SudoMsg {
+    KvQueryResult {
+        query_id: u64,
+    },
+    /// Sent by the cron module on every block of a schedule registered with this message
+    SweepExpired {},
+}
+
+/// Key of `key` in the wasm store, within the state of the contract whose canonical
+/// address is `contract`
+pub fn contract_store_key(contract: &[u8], key: &[u8]) -> Vec<u8> {
+    let mut store_key = vec![CONTRACT_STORE_PREFIX];
+    store_key.extend_from_slice(contract);
+    store_key.extend_from_slice(key);
+    store_key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{from_json, to_json_binary};
+
+    #[test]
+    fn prefixes_contract_state() {
+        let key = contract_store_key(&[0x22; 32], b"dst_escrows");
+        assert_eq!(key[0], 0x03);
+        assert_eq!(&key[1..33], &[0x22; 32]);
+        assert_eq!(&key[33..], b"dst_escrows");
+    }
+
+    #[test]
+    fn serializes_like_neutron() {
+        let sudo: SudoMsg = from_json(br#"{"kv_query_result":{"query_id":3}}"#).unwrap();
+        assert_eq!(sudo, SudoMsg::KvQueryResult { query_id: 3 });
+
+        let query = to_json_binary(&NeutronQuery::InterchainQueryResult { query_id: 3 }).unwrap();
+        assert_eq!(
+            query.as_slice(),
+            br#"{"interchain_query_result":{"query_id":3}}"#
+        );
+    }
+}
@@ -1,67 +1,519 @@
+use cosmwasm_std::{Addr, Binary, Deps, Env, Order, StdError, StdResult, Storage, Uint256};
+use cw_storage_plus::{Bound, Prefix};
+
+use crate::auction::{min_taking_amount, AuctionDetails};
+use crate::extension::Extension;
+use crate::msg::{
+    AuctionQuoteResponse, AvailableAction, ClaimableFundsResponse, DstEscrowListResponse,
+    DstEscrowResponse, Escrow, EscrowStageResponse, EscrowsResponse, ExtensionResponse,
+    FeeConfigResponse, HashlockEscrowResponse, OrderBy, RelayedSecretResponse,
+    RevealedSecretResponse, SecretRelayResponse, SrcEscrowListResponse, SrcEscrowResponse,
+    StatsResponse, SupportedChainsResponse, SupportedTokensResponse, SweepConfigResponse,
+};
+use crate::state::{
+    chain_key, load_escrow, DstEscrow, EscrowSide, EscrowStatus, SideStats, SrcEscrow, ASSET_STATS,
+    CLAIMABLE_FUNDS, CONFIG, DST_ESCROWS, FEE_CONFIG, RELAYED_SECRETS, REVEALED_SECRETS,
+    SECRET_RELAY, SIDE_STATS, SRC_ESCROWS, SUPPORTED_CHAINS, SUPPORTED_TOKENS, SWEEP_CONFIG,
+};
+use crate::timelocks::{EscrowAction, EscrowStage};
+
+const DEFAULT_LIMIT: u32 = 30;
+const MAX_LIMIT: u32 = 100;
+// Most escrows a filtered listing reads for one page
+pub(crate) const MAX_SCAN: usize = 1_000;
+
+/// Query a specific destination escrow
+pub fn query_dst_escrow(deps: Deps, escrow_address: String) -> StdResult<DstEscrowResponse> {
+    let escrow = DST_ESCROWS.may_load(deps.storage, escrow_address.clone())?;
+
+    Ok(DstEscrowResponse {
+        escrow_address,  // Changed from escrow_key
+        status: escrow.as_ref().map(|escrow| escrow.status),
+        immutables: escrow.map(|escrow| escrow.immutables),
+    })
+}
+
+/// Query destination escrows page by page, in either direction and optionally filtered.
+/// A page that reads `MAX_SCAN` escrows stops there, so a selective filter may give short
+/// or empty pages before the last. `total` is not narrowed by the filters.
+pub fn query_all_dst_escrows(
+    deps: Deps,
+    start_after: Option<String>,
+    end_before: Option<String>,
+    limit: Option<u32>,
+    order_by: Option<OrderBy>,
+    filter: EscrowFilter,
+) -> StdResult<DstEscrowListResponse> {
+    let (min, max, order) = list_bounds(start_after, end_before, order_by);
+    // Walk the maker or token index when filtering on them, the whole map otherwise
+    let escrows: Box<dyn Iterator<Item = StdResult<(String, DstEscrow)>>> =
+        match (&filter.maker, &filter.token) {
+            (Some(maker), _) => {
+                DST_ESCROWS
+                    .idx
+                    .maker
+                    .prefix(maker.clone())
+                    .range(deps.storage, min, max, order)
+            }
+            (None, Some(token)) => {
+                DST_ESCROWS
+                    .idx
+                    .token
+                    .prefix(token.clone())
+                    .range(deps.storage, min, max, order)
+            }
+            (None, None) => DST_ESCROWS.range(deps.storage, min, max, order),
+        };
+    let (escrows, next_start_after) = paginate(escrows, limit, |escrow| {
+        filter.matches(
+            escrow.status,
+            &escrow.immutables.token,
+            &escrow.immutables.maker,
+        )
+    })?;
+
+    Ok(DstEscrowListResponse {
+        escrows,
+        next_start_after,
+        total: side_stats(deps.storage, EscrowSide::Dst)?.created,
+    })
+}
+
 /// Query a specific source escrow
 pub fn query_src_escrow(deps: Deps, escrow_address: String) -> StdResult<SrcEscrowResponse> {
-    let escrow_data = SRC_ESCROWS.may_load(deps.storage, escrow_address.clone())?;
+    let escrow = SRC_ESCROWS.may_load(deps.storage, escrow_address.clone())?;
 
     Ok(SrcEscrowResponse {
         escrow_address,
-        escrow_data,
+        status: escrow.as_ref().map(|escrow| escrow.status),
+        escrow_data: escrow.map(|escrow| escrow.escrow_data),
     })
 }
 
-/// Query all source escrows with pagination
+/// Query source escrows page by page, in either direction and optionally filtered.
+/// A page that reads `MAX_SCAN` escrows stops there, so a selective filter may give short
+/// or empty pages before the last. `total` is not narrowed by the filters.
 pub fn query_all_src_escrows(
     deps: Deps,
     start_after: Option<String>,
+    end_before: Option<String>,
     limit: Option<u32>,
+    order_by: Option<OrderBy>,
+    filter: EscrowFilter,
 ) -> StdResult<SrcEscrowListResponse> {
-    let limit = limit.unwrap_or(30).min(100) as usize;
+    let (min, max, order) = list_bounds(start_after, end_before, order_by);
+    let escrows: Box<dyn Iterator<Item = StdResult<(String, SrcEscrow)>>> =
+        match (&filter.maker, &filter.token) {
+            (Some(maker), _) => {
+                SRC_ESCROWS
+                    .idx
+                    .maker
+                    .prefix(maker.clone())
+                    .range(deps.storage, min, max, order)
+            }
+            (None, Some(token)) => {
+                SRC_ESCROWS
+                    .idx
+                    .token
+                    .prefix(token.clone())
+                    .range(deps.storage, min, max, order)
+            }
+            (None, None) => SRC_ESCROWS.range(deps.storage, min, max, order),
+        };
+    let (escrows, next_start_after) = paginate(escrows, limit, |escrow| {
+        filter.matches(
+            escrow.status,
+            &escrow.escrow_data.order.maker_asset,
+            &escrow.escrow_data.order.maker,
+        )
+    })?;
 
-    let start = start_after
-        .as_deref()
-        .map(|s| Bound::exclusive(s));
+    Ok(SrcEscrowListResponse {
+        escrows,
+        next_start_after,
+        total: side_stats(deps.storage, EscrowSide::Src)?.created,
+    })
+}
 
-    let escrows: StdResult<Vec<(String, SrcEscrowData)>> = SRC_ESCROWS
-        .range(deps.storage, start, None, Order::Ascending)
-        .take(limit)
+/// Query escrows of one side created for a maker, with pagination
+pub fn query_escrows_by_maker(
+    deps: Deps,
+    side: EscrowSide,
+    maker: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<EscrowsResponse> {
+    let escrows = match side {
+        EscrowSide::Dst => page_dst_escrows(
+            deps.storage,
+            DST_ESCROWS.idx.maker.prefix(maker),
+            start_after,
+            limit,
+        )?,
+        EscrowSide::Src => page_src_escrows(
+            deps.storage,
+            SRC_ESCROWS.idx.maker.prefix(maker),
+            start_after,
+            limit,
+        )?,
+    };
+
+    Ok(EscrowsResponse { escrows })
+}
+
+/// Query escrows of one side created for a taker, with pagination
+pub fn query_escrows_by_taker(
+    deps: Deps,
+    side: EscrowSide,
+    taker: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<EscrowsResponse> {
+    let escrows = match side {
+        EscrowSide::Dst => page_dst_escrows(
+            deps.storage,
+            DST_ESCROWS.idx.taker.prefix(taker),
+            start_after,
+            limit,
+        )?,
+        EscrowSide::Src => page_src_escrows(
+            deps.storage,
+            SRC_ESCROWS.idx.taker.prefix(taker),
+            start_after,
+            limit,
+        )?,
+    };
+
+    Ok(EscrowsResponse { escrows })
+}
+
+/// Query escrows of one side belonging to an order, with pagination
+pub fn query_escrows_by_order_hash(
+    deps: Deps,
+    side: EscrowSide,
+    order_hash: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<EscrowsResponse> {
+    let escrows = match side {
+        EscrowSide::Dst => page_dst_escrows(
+            deps.storage,
+            DST_ESCROWS.idx.order_hash.prefix(order_hash),
+            start_after,
+            limit,
+        )?,
+        EscrowSide::Src => page_src_escrows(
+            deps.storage,
+            SRC_ESCROWS.idx.order_hash.prefix(order_hash),
+            start_after,
+            limit,
+        )?,
+    };
+
+    Ok(EscrowsResponse { escrows })
+}
+
+/// Query the escrow locked by a hashlock, looking at the destination side first
+pub fn query_escrow_by_hashlock(deps: Deps, hashlock: String) -> StdResult<HashlockEscrowResponse> {
+    let dst_escrow = DST_ESCROWS
+        .idx
+        .hashlock
+        .item(deps.storage, hashlock.clone())?
+        .map(|(pk, escrow)| (pk, Escrow::Dst(escrow)));
+    let escrow = match dst_escrow {
+        Some(escrow) => Some(escrow),
+        None => SRC_ESCROWS
+            .idx
+            .hashlock
+            .item(deps.storage, hashlock.clone())?
+            .map(|(pk, escrow)| (pk, Escrow::Src(escrow))),
+    }
+    .map(|(pk, escrow)| -> StdResult<_> { Ok((String::from_utf8(pk)?, escrow)) })
+    .transpose()?;
+
+    Ok(HashlockEscrowResponse { hashlock, escrow })
+}
+
+/// Query the current stage of an escrow and what can be done with it right now
+pub fn query_escrow_stage(
+    deps: Deps,
+    env: Env,
+    escrow_address: String,
+) -> StdResult<EscrowStageResponse> {
+    let escrow = load_escrow(deps.storage, &escrow_address)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    let config = CONFIG.load(deps.storage)?;
+    let now = env.block.time;
+
+    // Only active escrows move through the stages
+    if escrow.status != EscrowStatus::Active {
+        return Ok(EscrowStageResponse {
+            escrow_address,
+            side: escrow.side,
+            status: escrow.status,
+            stage: EscrowStage::Closed,
+            actions: vec![],
+            next_transition: None,
+        });
+    }
+
+    // Nothing can be settled before the escrow's funds are locked
+    let timelocks = escrow.timelocks();
+    let actions = EscrowAction::ALL
+        .into_iter()
+        .filter(|action| {
+            escrow.funded
+                && timelocks
+                    .window(escrow.side, *action, config.rescue_delay)
+                    .is_some_and(|window| window.contains(now))
+        })
+        .map(|action| AvailableAction {
+            action,
+            caller: action.caller(),
+        })
         .collect();
 
-    Ok(SrcEscrowListResponse {
-        escrows: escrows?,
+    Ok(EscrowStageResponse {
+        escrow_address,
+        side: escrow.side,
+        status: escrow.status,
+        stage: timelocks.stage(escrow.side, now),
+        actions,
+        next_transition: timelocks.next_transition(escrow.side, now),
     })
-}use cosmwasm_std::{Deps, Order, StdResult};
-use cw_storage_plus::Bound;
+}
 
-use crate::msg::{DstEscrowListResponse, DstEscrowResponse, SrcEscrowResponse, SrcEscrowListResponse};
-use crate::state::{Immutables, SrcEscrowData, DST_ESCROWS, SRC_ESCROWS};
+/// Query the secret revealed for a hashlock, if its escrow has been withdrawn
+pub fn query_revealed_secret(deps: Deps, hashlock: String) -> StdResult<RevealedSecretResponse> {
+    let revealed = REVEALED_SECRETS.may_load(deps.storage, hashlock.to_lowercase())?;
 
-/// Query a specific destination escrow
-pub fn query_dst_escrow(deps: Deps, escrow_address: String) -> StdResult<DstEscrowResponse> {
-    let immutables = DST_ESCROWS.may_load(deps.storage, escrow_address.clone())?;
+    Ok(RevealedSecretResponse { hashlock, revealed })
+}
 
-    Ok(DstEscrowResponse {
-        escrow_address,  // Changed from escrow_key
-        immutables,
+/// Query the escrow channel revealed secrets are forwarded over
+pub fn query_secret_relay(deps: Deps) -> StdResult<SecretRelayResponse> {
+    let channel_id = SECRET_RELAY.may_load(deps.storage)?;
+
+    Ok(SecretRelayResponse { channel_id })
+}
+
+/// Query a secret relayed from the counterpart leg and kept for its beneficiary
+pub fn query_relayed_secret(deps: Deps, hashlock: String) -> StdResult<RelayedSecretResponse> {
+    let relayed = RELAYED_SECRETS.may_load(deps.storage, hashlock.to_lowercase())?;
+
+    Ok(RelayedSecretResponse { hashlock, relayed })
+}
+
+/// Query the coins an address can claim from failed hook transfers
+pub fn query_claimable_funds(deps: Deps, address: String) -> StdResult<ClaimableFundsResponse> {
+    let funds = CLAIMABLE_FUNDS
+        .may_load(deps.storage, deps.api.addr_validate(&address)?)?
+        .unwrap_or_default();
+
+    Ok(ClaimableFundsResponse { address, funds })
+}
+
+/// Query the fee taken from source escrow withdrawals
+pub fn query_fee_config(deps: Deps) -> StdResult<FeeConfigResponse> {
+    let fee_config = FEE_CONFIG.may_load(deps.storage)?;
+
+    Ok(FeeConfigResponse { fee_config })
+}
+
+/// Query how expired escrows are swept
+pub fn query_sweep_config(deps: Deps) -> StdResult<SweepConfigResponse> {
+    let sweep_config = SWEEP_CONFIG.may_load(deps.storage)?.unwrap_or_default();
+
+    Ok(SweepConfigResponse { sweep_config })
+}
+
+/// Query lifecycle counters of both sides and escrowed volume of one or every asset
+pub fn query_stats(deps: Deps, asset: Option<String>) -> StdResult<StatsResponse> {
+    let assets = match asset {
+        Some(asset) => {
+            let stats = ASSET_STATS.may_load(deps.storage, &asset)?;
+            vec![(asset, stats.unwrap_or_default())]
+        }
+        None => ASSET_STATS
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()?,
+    };
+
+    Ok(StatsResponse {
+        src: side_stats(deps.storage, EscrowSide::Src)?,
+        dst: side_stats(deps.storage, EscrowSide::Dst)?,
+        assets,
     })
 }
 
-/// Query all destination escrows with pagination
-pub fn query_all_dst_escrows(
+/// Query the current auction rate of an order and the taking amount it implies
+pub fn query_auction_rate(
+    env: Env,
+    order: crate::state::Order,
+    extension: Binary,
+) -> StdResult<AuctionQuoteResponse> {
+    let quote = || -> Result<_, crate::error::ContractError> {
+        let auction = AuctionDetails::from_extension(&extension)?;
+        let now = env.block.time;
+        let taking_amount = min_taking_amount(&order, order.making_amount, auction.as_ref(), now)?;
+        Ok(AuctionQuoteResponse {
+            rate_bump: auction.as_ref().map_or(0, |auction| auction.rate_bump(now)),
+            auction,
+            taking_amount,
+        })
+    };
+
+    quote().map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+/// Query the fields of an encoded order extension
+pub fn query_decode_extension(extension: Binary) -> StdResult<ExtensionResponse> {
+    let extension =
+        Extension::decode(&extension).map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    Ok(ExtensionResponse { extension })
+}
+
+/// Query the counterpart chains source escrows may be created for, with pagination
+pub fn query_supported_chains(
     deps: Deps,
-    start_after: Option<String>,
+    start_after: Option<Uint256>,
     limit: Option<u32>,
-) -> StdResult<DstEscrowListResponse> {
-    let limit = limit.unwrap_or(30).min(100) as usize;
+) -> StdResult<SupportedChainsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|chain_id| Bound::exclusive(chain_key(chain_id)));
 
-    let start = start_after
-        .as_deref()
-        .map(|s| Bound::exclusive(s));
+    let chains = SUPPORTED_CHAINS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, chain)| chain))
+        .collect::<StdResult<_>>()?;
+
+    Ok(SupportedChainsResponse { chains })
+}
+
+/// Query the token pairs supported on a counterpart chain, with pagination
+pub fn query_supported_tokens(
+    deps: Deps,
+    chain_id: Uint256,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SupportedTokensResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
 
-    let escrows: StdResult<Vec<(String, Immutables)>> = DST_ESCROWS
+    let tokens = SUPPORTED_TOKENS
+        .prefix(chain_key(chain_id))
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
-        .collect();
+        .map(|item| item.map(|(_, token)| token))
+        .collect::<StdResult<_>>()?;
 
-    Ok(DstEscrowListResponse {
-        escrows: escrows?,
-    })
-}
\ No newline at end of file
+    Ok(SupportedTokensResponse { tokens })
+}
+
+/// Filters of the escrow list queries; unset fields match every escrow
+pub struct EscrowFilter {
+    pub status: Option<EscrowStatus>,
+    pub token: Option<String>,
+    pub maker: Option<String>,
+}
+
+impl EscrowFilter {
+    fn matches(&self, status: EscrowStatus, token: &Addr, maker: &Addr) -> bool {
+        self.status.is_none_or(|wanted| status == wanted)
+            && self.token.as_ref().is_none_or(|wanted| token == wanted)
+            && self.maker.as_ref().is_none_or(|wanted| maker == wanted)
+    }
+}
+
+/// Range bounds and order of a listing. Both bounds are exclusive and relative to the
+/// listing's direction, so a descending listing starts below `start_after`.
+fn list_bounds(
+    start_after: Option<String>,
+    end_before: Option<String>,
+    order_by: Option<OrderBy>,
+) -> (
+    Option<Bound<'static, String>>,
+    Option<Bound<'static, String>>,
+    Order,
+) {
+    let start_after = start_after.map(Bound::exclusive);
+    let end_before = end_before.map(Bound::exclusive);
+    match order_by.unwrap_or(OrderBy::Ascending) {
+        OrderBy::Ascending => (start_after, end_before, Order::Ascending),
+        OrderBy::Descending => (end_before, start_after, Order::Descending),
+    }
+}
+
+// Items of a page with the cursor of the next one
+type Page<T> = (Vec<(String, T)>, Option<String>);
+
+/// Takes a page of the items `keep` accepts off a listing, with the cursor of the next page
+/// if there is one. Reads one match past the page to tell, and at most `MAX_SCAN` items, so
+/// a filter that skips most of them cannot run the query out of gas: a page cut short by
+/// that cap hands out the last item read as its cursor.
+fn paginate<T>(
+    items: impl Iterator<Item = StdResult<(String, T)>>,
+    limit: Option<u32>,
+    keep: impl Fn(&T) -> bool,
+) -> StdResult<Page<T>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let mut page = Vec::with_capacity(limit);
+    let mut last_read = None;
+    for (read, item) in items.enumerate() {
+        if read == MAX_SCAN {
+            return Ok((page, last_read));
+        }
+        let (address, item) = item?;
+        if !keep(&item) {
+            last_read = Some(address);
+            continue;
+        }
+        if page.len() == limit {
+            let next_start_after = page.last().map(|(address, _)| address.clone());
+            return Ok((page, next_start_after));
+        }
+        last_read = Some(address.clone());
+        page.push((address, item));
+    }
+    Ok((page, None))
+}
+
+fn side_stats(storage: &dyn Storage, side: EscrowSide) -> StdResult<SideStats> {
+    Ok(SIDE_STATS
+        .may_load(storage, side.as_str())?
+        .unwrap_or_default())
+}
+
+fn page_dst_escrows(
+    storage: &dyn Storage,
+    prefix: Prefix<String, DstEscrow, String>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(String, Escrow)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    prefix
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(address, escrow)| (address, Escrow::Dst(escrow))))
+        .collect()
+}
+
+fn page_src_escrows(
+    storage: &dyn Storage,
+    prefix: Prefix<String, SrcEscrow, String>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(String, Escrow)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    prefix
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(address, escrow)| (address, Escrow::Src(escrow))))
+        .collect()
+}
@@ -0,0 +1,294 @@
+use cosmwasm_std::{Addr, StdError, StdResult, Uint128, Uint256};
+use sha3::{Digest, Keccak256};
+
+use crate::state::{Immutables, Order};
+use crate::timelocks::{Stage, Timelocks};
+
+pub use crate::execute::locked_funds;
+pub use crate::helpers::EscrowContract;
+
+// EIP-712 style type strings the order and immutables hashes are computed over
+const ORDER_TYPE: &str = "Order(uint256 salt,string maker,string receiver,string makerAsset,\
+    string takerAsset,uint256 makingAmount,uint256 takingAmount)";
+const IMMUTABLES_TYPE: &str = "Immutables(bytes32 orderHash,bytes32 hashlock,string maker,\
+    string taker,string token,uint256 amount,uint256 safetyDeposit,uint256 timelocks)";
+
+/// Builds an `Order`. The receiver defaults to the maker and the salt to zero.
+#[derive(Clone, Debug)]
+pub struct OrderBuilder {
+    order: Order,
+}
+
+impl OrderBuilder {
+    pub fn new(
+        maker: impl Into<String>,
+        maker_asset: impl Into<String>,
+        taker_asset: impl Into<String>,
+    ) -> Self {
+        let maker = Addr::unchecked(maker);
+        OrderBuilder {
+            order: Order {
+                salt: Uint128::zero(),
+                receiver: maker.clone(),
+                maker,
+                maker_asset: Addr::unchecked(maker_asset),
+                taker_asset: Addr::unchecked(taker_asset),
+                making_amount: Uint128::zero(),
+                taking_amount: Uint128::zero(),
+            },
+        }
+    }
+
+    pub fn salt(mut self, salt: u128) -> Self {
+        self.order.salt = Uint128::new(salt);
+        self
+    }
+
+    pub fn receiver(mut self, receiver: impl Into<String>) -> Self {
+        self.order.receiver = Addr::unchecked(receiver);
+        self
+    }
+
+    pub fn amounts(mut self, making_amount: u128, taking_amount: u128) -> Self {
+        self.order.making_amount = Uint128::new(making_amount);
+        self.order.taking_amount = Uint128::new(taking_amount);
+        self
+    }
+
+    pub fn build(self) -> Order {
+        self.order
+    }
+}
+
+/// Builds the `Immutables` of either leg of an order. The hashlock has to be set, through
+/// the secret or directly; the safety deposit and every timelock default to zero.
+#[derive(Clone, Debug)]
+pub struct ImmutablesBuilder {
+    immutables: Immutables,
+    offsets: [u32; 7],
+}
+
+impl ImmutablesBuilder {
+    /// Source leg: the maker's tokens, paid to the taker on withdrawal
+    pub fn src(order: &Order, taker: impl Into<String>) -> Self {
+        Self::with(
+            order,
+            order.maker.clone(),
+            taker,
+            order.maker_asset.clone(),
+            order.making_amount,
+        )
+    }
+
+    /// Destination leg: the taker's tokens, paid to the order's receiver on withdrawal
+    pub fn dst(order: &Order, taker: impl Into<String>) -> Self {
+        Self::with(
+            order,
+            order.receiver.clone(),
+            taker,
+            order.taker_asset.clone(),
+            order.taking_amount,
+        )
+    }
+
+    fn with(
+        order: &Order,
+        maker: Addr,
+        taker: impl Into<String>,
+        token: Addr,
+        amount: Uint128,
+    ) -> Self {
+        ImmutablesBuilder {
+            immutables: Immutables {
+                order_hash: order_hash(order),
+                hashlock: String::new(),
+                maker,
+                taker: Addr::unchecked(taker),
+                token,
+                amount,
+                safety_deposit: Uint128::zero(),
+                timelocks: Uint256::zero(),
+            },
+            offsets: [0; 7],
+        }
+    }
+
+    /// Amount of a partial fill, in place of the order's full amount
+    pub fn amount(mut self, amount: u128) -> Self {
+        self.immutables.amount = Uint128::new(amount);
+        self
+    }
+
+    pub fn safety_deposit(mut self, safety_deposit: u128) -> Self {
+        self.immutables.safety_deposit = Uint128::new(safety_deposit);
+        self
+    }
+
+    pub fn secret(self, secret: &[u8]) -> Self {
+        self.hashlock(hashlock(secret))
+    }
+
+    pub fn hashlock(mut self, hashlock: impl Into<String>) -> Self {
+        self.immutables.hashlock = hashlock.into();
+        self
+    }
+
+    /// Offset of `stage` in seconds from the escrow's deployment
+    pub fn timelock(mut self, stage: Stage, offset: u32) -> Self {
+        self.offsets[stage as usize] = offset;
+        self
+    }
+
+    pub fn build(self) -> StdResult<Immutables> {
+        if self.immutables.hashlock.is_empty() {
+            return Err(StdError::generic_err("hashlock not set"));
+        }
+        Ok(Immutables {
+            timelocks: Timelocks::pack(self.offsets),
+            ..self.immutables
+        })
+    }
+}
+
+/// Hashlock of a secret, in the hex form escrows store it in
+pub fn hashlock(secret: &[u8]) -> String {
+    format!("0x{}", hex::encode(Keccak256::digest(secret)))
+}
+
+/// Hash identifying an order: an EIP-712 style struct hash of its fields, in the hex form
+/// escrows store it in
+pub fn order_hash(order: &Order) -> String {
+    let hash = struct_hash(
+        ORDER_TYPE,
+        &[
+            uint(order.salt.into()),
+            string(order.maker.as_str()),
+            string(order.receiver.as_str()),
+            string(order.maker_asset.as_str()),
+            string(order.taker_asset.as_str()),
+            uint(order.making_amount.into()),
+            uint(order.taking_amount.into()),
+        ],
+    );
+    format!("0x{}", hex::encode(hash))
+}
+
+/// Hash of everything an escrow locks
+pub fn immutables_hash(immutables: &Immutables) -> StdResult<[u8; 32]> {
+    Ok(struct_hash(
+        IMMUTABLES_TYPE,
+        &[
+            bytes32(&immutables.order_hash)?,
+            bytes32(&immutables.hashlock)?,
+            string(immutables.maker.as_str()),
+            string(immutables.taker.as_str()),
+            string(immutables.token.as_str()),
+            uint(immutables.amount.into()),
+            uint(immutables.safety_deposit.into()),
+            immutables.timelocks.to_be_bytes(),
+        ],
+    ))
+}
+
+fn struct_hash(type_string: &str, words: &[[u8; 32]]) -> [u8; 32] {
+    let mut hasher = Keccak256::new().chain_update(Keccak256::digest(type_string.as_bytes()));
+    for word in words {
+        hasher.update(word);
+    }
+    hasher.finalize().into()
+}
+
+fn uint(value: Uint256) -> [u8; 32] {
+    value.to_be_bytes()
+}
+
+fn string(value: &str) -> [u8; 32] {
+    Keccak256::digest(value.as_bytes()).into()
+}
+
+fn bytes32(value: &str) -> StdResult<[u8; 32]> {
+    hex::decode(value.strip_prefix("0x").unwrap_or(value))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| StdError::generic_err(format!("invalid bytes32: {}", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::Timestamp;
+
+    use super::*;
+
+    fn order() -> Order {
+        OrderBuilder::new("cosmos1maker", "uatom", "0xtoken")
+            .salt(7)
+            .amounts(1_000, 2_000)
+            .build()
+    }
+
+    #[test]
+    fn builds_both_legs() {
+        let order = order();
+        assert_eq!(order.receiver, order.maker);
+        let timelocks = |builder: ImmutablesBuilder| {
+            builder
+                .timelock(Stage::SrcWithdrawal, 10)
+                .timelock(Stage::SrcCancellation, 1_000)
+                .timelock(Stage::DstWithdrawal, 5)
+                .timelock(Stage::DstCancellation, 900)
+        };
+
+        let src = timelocks(ImmutablesBuilder::src(&order, "cosmos1taker"))
+            .secret(b"secret")
+            .safety_deposit(100)
+            .build()
+            .unwrap();
+        assert_eq!(src.order_hash, order_hash(&order));
+        assert_eq!(src.token, order.maker_asset);
+        assert_eq!(src.amount, order.making_amount);
+        let decoded = Timelocks::new(src.timelocks, Timestamp::from_seconds(0));
+        assert_eq!(decoded.offset(Stage::SrcCancellation), 1_000);
+        assert_eq!(decoded.offset(Stage::DstCancellation), 900);
+        assert_eq!(decoded.offset(Stage::SrcPublicWithdrawal), 0);
+
+        // Both legs lock the same order, secret and timelocks
+        let dst = timelocks(ImmutablesBuilder::dst(&order, "cosmos1taker"))
+            .hashlock(src.hashlock.clone())
+            .amount(500)
+            .build()
+            .unwrap();
+        assert!(dst.matches_counterpart(&src));
+        assert_eq!(dst.maker, order.receiver);
+        assert_eq!(dst.token, order.taker_asset);
+        assert_eq!(dst.amount, Uint128::new(500));
+
+        // Escrows cannot be built without a hashlock
+        assert!(ImmutablesBuilder::src(&order, "cosmos1taker")
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn hashes() {
+        let order = order();
+        let immutables = ImmutablesBuilder::dst(&order, "cosmos1taker")
+            .secret(b"secret")
+            .build()
+            .unwrap();
+
+        // Every field is committed to
+        let unsalted = OrderBuilder::new("cosmos1maker", "uatom", "0xtoken")
+            .amounts(1_000, 2_000)
+            .build();
+        assert_ne!(order_hash(&order), order_hash(&unsalted));
+        let mut other = immutables.clone();
+        other.safety_deposit = Uint128::new(1);
+        assert_ne!(
+            immutables_hash(&immutables).unwrap(),
+            immutables_hash(&other).unwrap()
+        );
+
+        other.hashlock = "not hex".to_string();
+        assert!(immutables_hash(&other).is_err());
+    }
+}
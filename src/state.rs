@@ -1,8 +1,218 @@
-use cosmwasm_std::{Addr, Uint128, Binary};
-use cw_storage_plus::Map;
+use cosmwasm_std::{
+    Addr, Binary, Coin, IbcChannel, StdError, StdResult, Storage, Timestamp, Uint128, Uint256,
+};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex, UniqueIndex};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::error::ContractError;
+use crate::extra_data::EscrowExtraData;
+use crate::timelocks::Timelocks;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Config {
+    pub owner: Addr,
+    pub safety_deposit_denom: String, // native denom safety deposits are paid in
+    pub rescue_delay: u64,            // seconds after deployment before funds can be rescued
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+/// Fee taken from source escrow withdrawals, in basis points of the escrowed amount
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct FeeConfig {
+    pub fee_bps: u16,
+    pub integrator_share_bps: u16, // share of the fee paid to the order's integrator, if any
+    pub fee_collector: Addr,
+}
+
+// No fees are taken until the owner sets a fee config
+pub const FEE_CONFIG: Item<FeeConfig> = Item::new("fee_config");
+
+/// How expired escrows are swept by the cron callback
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SweepConfig {
+    pub batch_size: u32, // escrows of each side looked at per sweep
+}
+
+impl Default for SweepConfig {
+    fn default() -> Self {
+        SweepConfig { batch_size: 10 }
+    }
+}
+
+// Default sweep config applies until the owner sets one
+pub const SWEEP_CONFIG: Item<SweepConfig> = Item::new("sweep_config");
+
+// Last escrow each side's sweep looked at, keyed by side; unset means start from the first
+pub const SWEEP_CURSORS: Map<&str, String> = Map::new("sweep_cursors");
+
+/// Lifecycle counters of the escrows of one side
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SideStats {
+    pub created: u64,
+    pub withdrawn: u64,
+    pub cancelled: u64,
+}
+
+/// Escrowed volume of one asset across both sides, safety deposits aside
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AssetStats {
+    pub locked: Uint128,  // held by funded escrows that are still active
+    pub settled: Uint128, // paid out by withdrawals, fees included
+}
+
+// Kept up to date on every lifecycle transition so dashboards and listings never iterate.
+// Escrows are moved to a final status, or dropped as cancelled if never funded, so
+// `created` counts them all.
+pub const SIDE_STATS: Map<&str, SideStats> = Map::new("side_stats");
+pub const ASSET_STATS: Map<&str, AssetStats> = Map::new("asset_stats");
+
+// Latest cancellation offset of any escrow created so far, which a new rescue delay has to
+// outlast; checking it spares walking every escrow on migrate
+pub const MAX_CANCELLATION_OFFSET: Item<u64> = Item::new("max_cancellation_offset");
+
+/// Counts a newly created escrow of `side` with `timelocks`
+pub fn record_created(
+    storage: &mut dyn Storage,
+    side: EscrowSide,
+    timelocks: Uint256,
+) -> StdResult<()> {
+    SIDE_STATS.update(storage, side.as_str(), |stats| -> StdResult<_> {
+        let mut stats = stats.unwrap_or_default();
+        stats.created += 1;
+        Ok(stats)
+    })?;
+
+    let offset = Timelocks::new(timelocks, Timestamp::from_seconds(0)).expiry_offset(side) as u64;
+    let max = MAX_CANCELLATION_OFFSET
+        .may_load(storage)?
+        .unwrap_or_default();
+    if offset > max {
+        MAX_CANCELLATION_OFFSET.save(storage, &offset)?;
+    }
+    Ok(())
+}
+
+/// Adds the funds an escrow just locked to its asset's volume
+pub fn record_locked(storage: &mut dyn Storage, immutables: &Immutables) -> StdResult<()> {
+    ASSET_STATS.update(
+        storage,
+        immutables.token.as_str(),
+        |stats| -> StdResult<_> {
+            let mut stats = stats.unwrap_or_default();
+            stats.locked += immutables.amount;
+            Ok(stats)
+        },
+    )?;
+    Ok(())
+}
+
+/// Counts an escrow leaving `Active` for `next`, releasing its funds if it held any
+pub fn record_closed(
+    storage: &mut dyn Storage,
+    escrow: &EscrowState,
+    next: EscrowStatus,
+) -> StdResult<()> {
+    // Release the funds first, the only update that can fail
+    if escrow.funded {
+        let immutables = &escrow.immutables;
+        ASSET_STATS.update(
+            storage,
+            immutables.token.as_str(),
+            |stats| -> StdResult<_> {
+                let mut stats = stats.unwrap_or_default();
+                stats.locked = stats.locked.checked_sub(immutables.amount)?;
+                if next == EscrowStatus::Withdrawn {
+                    stats.settled += immutables.amount;
+                }
+                Ok(stats)
+            },
+        )?;
+    }
+
+    SIDE_STATS.update(storage, escrow.side.as_str(), |stats| -> StdResult<_> {
+        let mut stats = stats.unwrap_or_default();
+        match next {
+            EscrowStatus::Withdrawn => stats.withdrawn += 1,
+            EscrowStatus::Cancelled => stats.cancelled += 1,
+            EscrowStatus::Active | EscrowStatus::Rescued => {}
+        }
+        Ok(stats)
+    })?;
+    Ok(())
+}
+
+/// How a counterpart chain encodes addresses in a 32-byte word
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressFormat {
+    Evm,     // 20-byte address in the low bytes, written as "0x" and 40 hex digits
+    Bytes32, // the whole word, written as "0x" and 64 hex digits
+}
+
+impl AddressFormat {
+    /// Normalizes an address written in this format, or `None` if it is not one
+    pub fn normalize(&self, address: &str) -> Option<String> {
+        let len = match self {
+            AddressFormat::Evm => 20,
+            AddressFormat::Bytes32 => 32,
+        };
+        let bytes = hex::decode(address.strip_prefix("0x")?).ok()?;
+        (bytes.len() == len).then(|| format!("0x{}", hex::encode(bytes)))
+    }
+
+    /// Address held by a 32-byte word as decoded from extra data, or `None` if the word
+    /// does not hold one
+    pub fn from_word(&self, word: &str) -> Option<String> {
+        let bytes = hex::decode(word.strip_prefix("0x")?).ok()?;
+        match self {
+            AddressFormat::Evm if bytes.len() == 32 && bytes[..12].iter().all(|b| *b == 0) => {
+                Some(format!("0x{}", hex::encode(&bytes[12..])))
+            }
+            AddressFormat::Bytes32 if bytes.len() == 32 => {
+                Some(format!("0x{}", hex::encode(bytes)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A counterpart chain source escrows may be created for
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SupportedChain {
+    pub chain_id: Uint256,
+    pub address_format: AddressFormat,
+    pub factory: String, // escrow factory on the counterpart chain, in its address format
+    #[serde(default)]
+    pub connection_id: Option<String>, // IBC connection its state can be queried over
+}
+
+/// A token that may be swapped for `dst_token` on a counterpart chain
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SupportedToken {
+    pub chain_id: Uint256,
+    pub src_token: String,
+    pub dst_token: String, // in the counterpart chain's address format
+}
+
+// Supported counterpart chains, keyed by big-endian chain id so they list in order
+pub const SUPPORTED_CHAINS: Map<Vec<u8>, SupportedChain> = Map::new("supported_chains");
+
+// Supported token pairs, keyed by chain id and source token
+pub const SUPPORTED_TOKENS: Map<(Vec<u8>, String), SupportedToken> = Map::new("supported_tokens");
+
+pub fn chain_key(chain_id: Uint256) -> Vec<u8> {
+    chain_id.to_be_bytes().to_vec()
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct Order {
@@ -25,7 +235,17 @@ pub struct Immutables {
     pub token: Addr,
     pub amount: Uint128,
     pub safety_deposit: Uint128,
-    pub timelocks: Uint128,
+    pub timelocks: Uint256,
+}
+
+impl Immutables {
+    /// Whether two escrows lock the same order with the same secret and timelocks, as the
+    /// two legs of a swap do
+    pub fn matches_counterpart(&self, other: &Immutables) -> bool {
+        self.order_hash == other.order_hash
+            && self.hashlock == other.hashlock
+            && self.timelocks == other.timelocks
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -38,15 +258,331 @@ pub struct SrcEscrowData {
     pub making_amount: Uint128,
     pub taking_amount: Uint128,
     pub remaining_making_amount: Uint128,
-    pub extra_data: Binary,
+    pub extra_data: EscrowExtraData,
 }
 
-// Storage for destination escrows
-pub const DST_ESCROWS: Map<String, Immutables> = Map::new("dst_escrows");
+impl SrcEscrowData {
+    /// Immutables of the source escrow, built from the order and its decoded extra data
+    pub fn immutables(&self) -> Immutables {
+        Immutables {
+            order_hash: self.order_hash.clone(),
+            hashlock: self.extra_data.hashlock_info.clone(),
+            maker: self.order.maker.clone(),
+            taker: self.taker.clone(),
+            token: self.order.maker_asset.clone(),
+            amount: self.making_amount,
+            safety_deposit: self.extra_data.src_safety_deposit,
+            timelocks: self.extra_data.timelocks,
+        }
+    }
+}
 
-// Storage for source escrows  
-pub const SRC_ESCROWS: Map<String, SrcEscrowData> = Map::new("src_escrows");
+/// Lifecycle of an escrow. Every escrow starts `Active` and ends in exactly one of the
+/// other states.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EscrowStatus {
+    Active,
+    Withdrawn,
+    Cancelled,
+    Rescued,
+}
 
-// Event attribute constants
-pub const EVENT_TYPE_DST_ESCROW_CREATED: &str = "dst_escrow_created";
-pub const EVENT_TYPE_SRC_ESCROW_CREATED: &str = "src_escrow_created";
\ No newline at end of file
+impl EscrowStatus {
+    /// Moves to `next`, rejecting any transition the lifecycle does not allow
+    pub fn transition(self, next: EscrowStatus) -> Result<EscrowStatus, ContractError> {
+        match (self, next) {
+            (EscrowStatus::Active, EscrowStatus::Withdrawn)
+            | (EscrowStatus::Active, EscrowStatus::Cancelled)
+            | (EscrowStatus::Active, EscrowStatus::Rescued) => Ok(next),
+            _ => Err(ContractError::InvalidStatusTransition {
+                from: self,
+                to: next,
+            }),
+        }
+    }
+}
+
+/// A destination escrow as stored, with its lifecycle state
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct DstEscrow {
+    pub immutables: Immutables,
+    pub status: EscrowStatus,
+    pub deployed_at: Timestamp,
+    pub funded: bool, // false for escrows migrated from v0.1 and ones opened over IBC until funded
+    #[serde(default)]
+    pub counterpart_verified: bool, // the source leg was proven by an interchain query
+}
+
+/// A source escrow as stored, with its lifecycle state
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SrcEscrow {
+    pub escrow_data: SrcEscrowData,
+    pub status: EscrowStatus,
+    pub deployed_at: Timestamp,
+    pub funded: bool,
+    #[serde(default)]
+    pub counterpart_verified: bool, // the destination leg was proven by an interchain query
+    #[serde(default)]
+    pub fee_config: Option<FeeConfig>, // fee terms in force when the escrow was created
+}
+
+/// Which leg of the swap an escrow belongs to
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EscrowSide {
+    Src,
+    Dst,
+}
+
+impl EscrowSide {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EscrowSide::Src => "src",
+            EscrowSide::Dst => "dst",
+        }
+    }
+}
+
+// Secondary indexes for destination escrows
+pub struct DstEscrowIndexes<'a> {
+    pub maker: MultiIndex<'a, String, DstEscrow, String>,
+    pub taker: MultiIndex<'a, String, DstEscrow, String>,
+    pub token: MultiIndex<'a, String, DstEscrow, String>,
+    pub order_hash: MultiIndex<'a, String, DstEscrow, String>,
+    pub hashlock: UniqueIndex<'a, String, DstEscrow, String>,
+}
+
+impl<'a> IndexList<DstEscrow> for DstEscrowIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<DstEscrow>> + '_> {
+        let v: Vec<&dyn Index<DstEscrow>> = vec![
+            &self.maker,
+            &self.taker,
+            &self.token,
+            &self.order_hash,
+            &self.hashlock,
+        ];
+        Box::new(v.into_iter())
+    }
+}
+
+// Storage for destination escrows, keyed by escrow address
+pub const DST_ESCROWS: IndexedMap<String, DstEscrow, DstEscrowIndexes> = IndexedMap::new(
+    "dst_escrows",
+    DstEscrowIndexes {
+        maker: MultiIndex::new(
+            |_pk, d| d.immutables.maker.to_string(),
+            "dst_escrows",
+            "dst_escrows__maker",
+        ),
+        taker: MultiIndex::new(
+            |_pk, d| d.immutables.taker.to_string(),
+            "dst_escrows",
+            "dst_escrows__taker",
+        ),
+        token: MultiIndex::new(
+            |_pk, d| d.immutables.token.to_string(),
+            "dst_escrows",
+            "dst_escrows__token",
+        ),
+        order_hash: MultiIndex::new(
+            |_pk, d| d.immutables.order_hash.clone(),
+            "dst_escrows",
+            "dst_escrows__order_hash",
+        ),
+        hashlock: UniqueIndex::new(|d| d.immutables.hashlock.clone(), "dst_escrows__hashlock"),
+    },
+);
+
+// Secondary indexes for source escrows
+pub struct SrcEscrowIndexes<'a> {
+    pub maker: MultiIndex<'a, String, SrcEscrow, String>,
+    pub taker: MultiIndex<'a, String, SrcEscrow, String>,
+    pub token: MultiIndex<'a, String, SrcEscrow, String>,
+    pub order_hash: MultiIndex<'a, String, SrcEscrow, String>,
+    pub hashlock: UniqueIndex<'a, String, SrcEscrow, String>,
+}
+
+impl<'a> IndexList<SrcEscrow> for SrcEscrowIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<SrcEscrow>> + '_> {
+        let v: Vec<&dyn Index<SrcEscrow>> = vec![
+            &self.maker,
+            &self.taker,
+            &self.token,
+            &self.order_hash,
+            &self.hashlock,
+        ];
+        Box::new(v.into_iter())
+    }
+}
+
+// Storage for source escrows, keyed by escrow address
+pub const SRC_ESCROWS: IndexedMap<String, SrcEscrow, SrcEscrowIndexes> = IndexedMap::new(
+    "src_escrows",
+    SrcEscrowIndexes {
+        maker: MultiIndex::new(
+            |_pk, d| d.escrow_data.order.maker.to_string(),
+            "src_escrows",
+            "src_escrows__maker",
+        ),
+        taker: MultiIndex::new(
+            |_pk, d| d.escrow_data.taker.to_string(),
+            "src_escrows",
+            "src_escrows__taker",
+        ),
+        token: MultiIndex::new(
+            |_pk, d| d.escrow_data.order.maker_asset.to_string(),
+            "src_escrows",
+            "src_escrows__token",
+        ),
+        order_hash: MultiIndex::new(
+            |_pk, d| d.escrow_data.order_hash.clone(),
+            "src_escrows",
+            "src_escrows__order_hash",
+        ),
+        hashlock: UniqueIndex::new(
+            |d| d.escrow_data.extra_data.hashlock_info.clone(),
+            "src_escrows__hashlock",
+        ),
+    },
+);
+
+/// A secret made public by withdrawing the escrow it unlocked
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RevealedSecret {
+    pub secret: String,
+    pub escrow_address: String,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+// Revealed secrets, keyed by lowercase hashlock
+pub const REVEALED_SECRETS: Map<String, RevealedSecret> = Map::new("revealed_secrets");
+
+// Connected escrow channels, keyed by our channel id
+pub const IBC_CHANNELS: Map<String, IbcChannel> = Map::new("ibc_channels");
+
+// Escrow channel revealed secrets are forwarded over; unset means they are not forwarded
+pub const SECRET_RELAY: Item<String> = Item::new("secret_relay");
+
+/// A secret relayed from the counterpart leg that could not be used on arrival
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RelayedSecret {
+    pub secret: String,
+    pub channel_id: String,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+// Relayed secrets, keyed by lowercase hashlock
+pub const RELAYED_SECRETS: Map<String, RelayedSecret> = Map::new("relayed_secrets");
+
+/// An interchain query watching for the counterpart of a local escrow
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CounterpartQuery {
+    pub escrow_address: String,
+    pub chain_id: Uint256,
+    pub counterpart_address: String, // key of the counterpart escrow in the remote factory
+}
+
+// Query being registered in the current transaction, until the reply gives its id
+pub const PENDING_COUNTERPART_QUERY: Item<CounterpartQuery> =
+    Item::new("pending_counterpart_query");
+
+// Registered counterpart queries, keyed by interchain query id
+pub const COUNTERPART_QUERIES: Map<u64, CounterpartQuery> = Map::new("counterpart_queries");
+
+// Coins of hook transfers whose escrow could not be created, keyed by recovery address
+pub const CLAIMABLE_FUNDS: Map<Addr, Vec<Coin>> = Map::new("claimable_funds");
+
+/// Why a migration could not carry a legacy escrow over
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UnmigratedReason {
+    UndecodableExtraData,
+    DuplicateHashlock,
+}
+
+/// A legacy escrow set aside by a migration, with its record as it was stored
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct UnmigratedEscrow {
+    pub reason: UnmigratedReason,
+    pub record: Binary,
+}
+
+// Legacy escrows set aside by a migration, keyed by side and escrow address
+pub const UNMIGRATED_ESCROWS: Map<(&str, String), UnmigratedEscrow> =
+    Map::new("unmigrated_escrows");
+
+/// An escrow of either side, with its immutables resolved
+pub struct EscrowState {
+    pub side: EscrowSide,
+    pub immutables: Immutables,
+    pub status: EscrowStatus,
+    pub deployed_at: Timestamp,
+    pub funded: bool,
+    pub counterpart_verified: bool,
+}
+
+impl EscrowState {
+    pub fn timelocks(&self) -> Timelocks {
+        Timelocks::new(self.immutables.timelocks, self.deployed_at)
+    }
+}
+
+/// Loads an escrow by address from whichever side stores it
+pub fn load_escrow(
+    storage: &dyn Storage,
+    escrow_address: &str,
+) -> Result<EscrowState, ContractError> {
+    if let Some(escrow) = DST_ESCROWS.may_load(storage, escrow_address.to_string())? {
+        return Ok(EscrowState {
+            side: EscrowSide::Dst,
+            immutables: escrow.immutables,
+            status: escrow.status,
+            deployed_at: escrow.deployed_at,
+            funded: escrow.funded,
+            counterpart_verified: escrow.counterpart_verified,
+        });
+    }
+    if let Some(escrow) = SRC_ESCROWS.may_load(storage, escrow_address.to_string())? {
+        return Ok(EscrowState {
+            side: EscrowSide::Src,
+            immutables: escrow.escrow_data.immutables(),
+            status: escrow.status,
+            deployed_at: escrow.deployed_at,
+            funded: escrow.funded,
+            counterpart_verified: escrow.counterpart_verified,
+        });
+    }
+    Err(ContractError::EscrowNotFound {})
+}
+
+/// Address of the escrow of either side locked by a hashlock
+pub fn escrow_by_hashlock(
+    storage: &dyn Storage,
+    hashlock: &str,
+) -> Result<Option<String>, ContractError> {
+    let pk = match DST_ESCROWS
+        .idx
+        .hashlock
+        .item(storage, hashlock.to_string())?
+    {
+        Some((pk, _)) => Some(pk),
+        None => SRC_ESCROWS
+            .idx
+            .hashlock
+            .item(storage, hashlock.to_string())?
+            .map(|(pk, _)| pk),
+    };
+    Ok(pk
+        .map(String::from_utf8)
+        .transpose()
+        .map_err(StdError::from)?)
+}
@@ -0,0 +1,44 @@
+//! Escrow fixtures shared by the unit and integration tests
+
+use cosmwasm_std::{Addr, Binary, Uint128, Uint256};
+use sha3::{Digest, Keccak256};
+
+use crate::state::Immutables;
+
+/// Chain id the tests register as the counterpart of source escrows
+pub const DST_CHAIN_ID: u128 = 56;
+
+pub fn keccak(secret: &[u8]) -> [u8; 32] {
+    Keccak256::digest(secret).into()
+}
+
+/// Hashlock of the secret in the `0x`-prefixed form escrows store
+pub fn hashlock_of(secret: &[u8]) -> String {
+    format!("0x{}", hex::encode(keccak(secret)))
+}
+
+/// Destination escrow locking 1000 `token_address` and a 100 deposit for `taker_address`
+pub fn dst_immutables(maker: &str, order_hash: &str, hashlock: &str) -> Immutables {
+    Immutables {
+        order_hash: order_hash.to_string(),
+        hashlock: hashlock.to_string(),
+        maker: Addr::unchecked(maker),
+        taker: Addr::unchecked("taker_address"),
+        token: Addr::unchecked("token_address"),
+        amount: Uint128::from(1000u128),
+        safety_deposit: Uint128::from(100u128),
+        timelocks: Uint256::from(3600u128),
+    }
+}
+
+/// `ExtraDataArgs` words: hashlock, dst chain id, dst token, deposits, timelocks
+pub fn extra_data(hashlock: [u8; 32], deposits: (u128, u128), timelocks: Uint256) -> Binary {
+    let mut extra_data = hashlock.to_vec();
+    extra_data.extend_from_slice(&Uint256::from(DST_CHAIN_ID).to_be_bytes());
+    extra_data.extend_from_slice(&[0u8; 12]);
+    extra_data.extend_from_slice(&[0xdd; 20]);
+    extra_data.extend_from_slice(&deposits.0.to_be_bytes());
+    extra_data.extend_from_slice(&deposits.1.to_be_bytes());
+    extra_data.extend_from_slice(&timelocks.to_be_bytes());
+    Binary::from(extra_data)
+}
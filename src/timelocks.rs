@@ -0,0 +1,425 @@
+use cosmwasm_std::{Timestamp, Uint256};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::state::EscrowSide;
+
+/// Stages packed into an escrow's `timelocks`, in the order used by the EVM `TimelocksLib`.
+/// Each stage takes 32 bits holding its offset in seconds from the escrow's deployment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    SrcWithdrawal = 0,
+    SrcPublicWithdrawal = 1,
+    SrcCancellation = 2,
+    SrcPublicCancellation = 3,
+    DstWithdrawal = 4,
+    DstPublicWithdrawal = 5,
+    DstCancellation = 6,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 7] = [
+        Stage::SrcWithdrawal,
+        Stage::SrcPublicWithdrawal,
+        Stage::SrcCancellation,
+        Stage::SrcPublicCancellation,
+        Stage::DstWithdrawal,
+        Stage::DstPublicWithdrawal,
+        Stage::DstCancellation,
+    ];
+}
+
+/// Period an escrow is in, following the stages of its side
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EscrowStage {
+    FinalityLock,
+    PrivateWithdrawal,
+    PublicWithdrawal,
+    PrivateCancellation,
+    PublicCancellation,
+    Closed, // the escrow is no longer active
+}
+
+/// Lifecycle actions that can be called on an escrow
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EscrowAction {
+    Withdraw,
+    PublicWithdraw,
+    Cancel,
+    PublicCancel,
+    Rescue,
+}
+
+/// Who may call an escrow action
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionCaller {
+    Taker,
+    Anyone,
+}
+
+impl EscrowAction {
+    pub const ALL: [EscrowAction; 5] = [
+        EscrowAction::Withdraw,
+        EscrowAction::PublicWithdraw,
+        EscrowAction::Cancel,
+        EscrowAction::PublicCancel,
+        EscrowAction::Rescue,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EscrowAction::Withdraw => "withdraw",
+            EscrowAction::PublicWithdraw => "public_withdraw",
+            EscrowAction::Cancel => "cancel",
+            EscrowAction::PublicCancel => "public_cancel",
+            EscrowAction::Rescue => "rescue",
+        }
+    }
+
+    pub fn caller(&self) -> ActionCaller {
+        match self {
+            EscrowAction::PublicWithdraw | EscrowAction::PublicCancel => ActionCaller::Anyone,
+            EscrowAction::Withdraw | EscrowAction::Cancel | EscrowAction::Rescue => {
+                ActionCaller::Taker
+            }
+        }
+    }
+}
+
+/// Time range `[start, end)` during which an action is callable
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Window {
+    pub start: Timestamp,
+    pub end: Option<Timestamp>,
+}
+
+impl Window {
+    pub fn contains(&self, time: Timestamp) -> bool {
+        time >= self.start && self.end.is_none_or(|end| time < end)
+    }
+}
+
+/// Timelocks of an escrow, anchored to the time it was deployed.
+///
+/// The EVM layout keeps `deployedAt` in the top 32 bits of the packed value; here the
+/// contract records the deployment time itself, so those bits are ignored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timelocks {
+    packed: Uint256,
+    deployed_at: Timestamp,
+}
+
+impl Timelocks {
+    pub fn new(packed: Uint256, deployed_at: Timestamp) -> Self {
+        Timelocks {
+            packed,
+            deployed_at,
+        }
+    }
+
+    /// Packs per-stage offsets in seconds, indexed by `Stage`, into the EVM layout. The
+    /// `deployedAt` bits are left empty.
+    pub fn pack(offsets: [u32; 7]) -> Uint256 {
+        let mut bytes = [0u8; 32];
+        for (stage, offset) in offsets.iter().enumerate() {
+            let end = 32 - 4 * stage;
+            bytes[end - 4..end].copy_from_slice(&offset.to_be_bytes());
+        }
+        Uint256::new(bytes)
+    }
+
+    pub fn deployed_at(&self) -> Timestamp {
+        self.deployed_at
+    }
+
+    /// Offset of the stage in seconds from deployment
+    pub fn offset(&self, stage: Stage) -> u32 {
+        let bytes = self.packed.to_be_bytes();
+        let end = 32 - 4 * stage as usize;
+        u32::from_be_bytes([
+            bytes[end - 4],
+            bytes[end - 3],
+            bytes[end - 2],
+            bytes[end - 1],
+        ])
+    }
+
+    /// Absolute time at which the stage starts
+    pub fn get(&self, stage: Stage) -> Timestamp {
+        self.deployed_at.plus_seconds(self.offset(stage) as u64)
+    }
+
+    /// Absolute time from which the taker may rescue the escrow's funds
+    pub fn rescue_start(&self, rescue_delay: u64) -> Timestamp {
+        self.deployed_at.plus_seconds(rescue_delay)
+    }
+
+    /// Window during which `action` is callable on an escrow of `side`, or `None` if the
+    /// side does not support the action
+    pub fn window(
+        &self,
+        side: EscrowSide,
+        action: EscrowAction,
+        rescue_delay: u64,
+    ) -> Option<Window> {
+        let window = |start: Stage, end: Option<Stage>| Window {
+            start: self.get(start),
+            end: end.map(|end| self.get(end)),
+        };
+        match (side, action) {
+            (EscrowSide::Src, EscrowAction::Withdraw) => {
+                Some(window(Stage::SrcWithdrawal, Some(Stage::SrcCancellation)))
+            }
+            (EscrowSide::Src, EscrowAction::PublicWithdraw) => Some(window(
+                Stage::SrcPublicWithdrawal,
+                Some(Stage::SrcCancellation),
+            )),
+            (EscrowSide::Src, EscrowAction::Cancel) => Some(window(Stage::SrcCancellation, None)),
+            (EscrowSide::Src, EscrowAction::PublicCancel) => {
+                Some(window(Stage::SrcPublicCancellation, None))
+            }
+            (EscrowSide::Dst, EscrowAction::Withdraw) => {
+                Some(window(Stage::DstWithdrawal, Some(Stage::DstCancellation)))
+            }
+            (EscrowSide::Dst, EscrowAction::PublicWithdraw) => Some(window(
+                Stage::DstPublicWithdrawal,
+                Some(Stage::DstCancellation),
+            )),
+            (EscrowSide::Dst, EscrowAction::Cancel) => Some(window(Stage::DstCancellation, None)),
+            // Destination escrows have no public cancellation period
+            (EscrowSide::Dst, EscrowAction::PublicCancel) => None,
+            (_, EscrowAction::Rescue) => Some(Window {
+                start: self.rescue_start(rescue_delay),
+                end: None,
+            }),
+        }
+    }
+
+    /// Time from which an escrow of `side` is expired and may be swept: the start of public
+    /// cancellation, or of cancellation for destination escrows, which have no public period
+    pub fn expiry(&self, side: EscrowSide) -> Timestamp {
+        self.get(Self::expiry_stage(side))
+    }
+
+    /// Offset of the expiry of `side` in seconds from deployment
+    pub fn expiry_offset(&self, side: EscrowSide) -> u32 {
+        self.offset(Self::expiry_stage(side))
+    }
+
+    fn expiry_stage(side: EscrowSide) -> Stage {
+        match side {
+            EscrowSide::Src => Stage::SrcPublicCancellation,
+            EscrowSide::Dst => Stage::DstCancellation,
+        }
+    }
+
+    /// Latest stage of `side` that has started at `now`
+    pub fn stage(&self, side: EscrowSide, now: Timestamp) -> EscrowStage {
+        Self::stages(side)
+            .iter()
+            .rev()
+            .find(|(stage, _)| now >= self.get(*stage))
+            .map_or(EscrowStage::FinalityLock, |(_, escrow_stage)| *escrow_stage)
+    }
+
+    /// Start of the next stage of `side` after `now`, if any is left
+    pub fn next_transition(&self, side: EscrowSide, now: Timestamp) -> Option<Timestamp> {
+        Self::stages(side)
+            .iter()
+            .map(|(stage, _)| self.get(*stage))
+            .filter(|start| *start > now)
+            .min()
+    }
+
+    fn stages(side: EscrowSide) -> &'static [(Stage, EscrowStage)] {
+        match side {
+            EscrowSide::Src => &[
+                (Stage::SrcWithdrawal, EscrowStage::PrivateWithdrawal),
+                (Stage::SrcPublicWithdrawal, EscrowStage::PublicWithdrawal),
+                (Stage::SrcCancellation, EscrowStage::PrivateCancellation),
+                (
+                    Stage::SrcPublicCancellation,
+                    EscrowStage::PublicCancellation,
+                ),
+            ],
+            EscrowSide::Dst => &[
+                (Stage::DstWithdrawal, EscrowStage::PrivateWithdrawal),
+                (Stage::DstPublicWithdrawal, EscrowStage::PublicWithdrawal),
+                (Stage::DstCancellation, EscrowStage::PrivateCancellation),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    /// Fills the `deployedAt` word the EVM `TimelocksLib` stamps on deployment
+    fn pack(offsets: [u32; 7], deployed_at_bits: u32) -> Uint256 {
+        Timelocks::pack(offsets) + (Uint256::from(deployed_at_bits) << 224)
+    }
+
+    fn side() -> impl Strategy<Value = EscrowSide> {
+        prop_oneof![Just(EscrowSide::Src), Just(EscrowSide::Dst)]
+    }
+
+    /// Offsets that only grow from one stage to the next on each side, as resolvers set them
+    fn ordered_offsets() -> impl Strategy<Value = [u32; 7]> {
+        (
+            proptest::array::uniform4(0..10_000u32),
+            proptest::array::uniform3(0..10_000u32),
+        )
+            .prop_map(|(mut src, mut dst)| {
+                src.sort_unstable();
+                dst.sort_unstable();
+                [src[0], src[1], src[2], src[3], dst[0], dst[1], dst[2]]
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn offsets_round_trip(
+            offsets in any::<[u32; 7]>(),
+            deployed_at_bits in any::<u32>(),
+            deployed_at in 0..u32::MAX as u64,
+        ) {
+            let deployed_at = Timestamp::from_seconds(deployed_at);
+            let timelocks = Timelocks::new(pack(offsets, deployed_at_bits), deployed_at);
+
+            for (stage, offset) in Stage::ALL.iter().zip(offsets) {
+                prop_assert_eq!(timelocks.offset(*stage), offset);
+                prop_assert_eq!(timelocks.get(*stage), deployed_at.plus_seconds(offset as u64));
+            }
+            // The packed `deployedAt` bits never move the anchor the contract recorded
+            prop_assert_eq!(timelocks.deployed_at(), deployed_at);
+            prop_assert_eq!(Timelocks::pack(offsets), pack(offsets, 0));
+        }
+
+        #[test]
+        fn stages_follow_windows(
+            offsets in ordered_offsets(),
+            side in side(),
+            elapsed in 0..12_000u64,
+            rescue_delay in 0..20_000u64,
+        ) {
+            let deployed_at = Timestamp::from_seconds(1_000_000);
+            let timelocks = Timelocks::new(pack(offsets, 0), deployed_at);
+            let now = deployed_at.plus_seconds(elapsed);
+            let stage = timelocks.stage(side, now);
+            let open = |action| {
+                timelocks
+                    .window(side, action, rescue_delay)
+                    .is_some_and(|window: Window| window.contains(now))
+            };
+
+            // Withdrawals and cancellations never overlap, and each opens with its stage
+            prop_assert!(!(open(EscrowAction::Withdraw) && open(EscrowAction::Cancel)));
+            prop_assert_eq!(
+                open(EscrowAction::Withdraw),
+                matches!(stage, EscrowStage::PrivateWithdrawal | EscrowStage::PublicWithdrawal)
+            );
+            prop_assert_eq!(
+                open(EscrowAction::Cancel),
+                matches!(stage, EscrowStage::PrivateCancellation | EscrowStage::PublicCancellation)
+            );
+            prop_assert_eq!(
+                open(EscrowAction::PublicCancel),
+                stage == EscrowStage::PublicCancellation
+            );
+            prop_assert_eq!(
+                open(EscrowAction::Rescue),
+                now >= deployed_at.plus_seconds(rescue_delay)
+            );
+            // Public windows only open once the private ones have
+            prop_assert!(!open(EscrowAction::PublicWithdraw) || open(EscrowAction::Withdraw));
+            prop_assert!(!open(EscrowAction::PublicCancel) || open(EscrowAction::Cancel));
+
+            match timelocks.next_transition(side, now) {
+                Some(next) => {
+                    prop_assert!(next > now);
+                    prop_assert_ne!(timelocks.stage(side, next), stage);
+                }
+                None => prop_assert_eq!(timelocks.stage(side, now.plus_seconds(1_000_000)), stage),
+            }
+        }
+    }
+
+    #[test]
+    fn decodes_stage_offsets() {
+        // Offsets 1..=7 for the seven stages, lowest stage in the lowest bits
+        let mut bytes = [0u8; 32];
+        for stage in 0..7u8 {
+            bytes[31 - 4 * stage as usize] = stage + 1;
+        }
+        // deployedAt bits are ignored
+        bytes[0] = 0xff;
+        let timelocks = Timelocks::new(Uint256::new(bytes), Timestamp::from_seconds(1_000));
+
+        assert_eq!(timelocks.offset(Stage::SrcWithdrawal), 1);
+        assert_eq!(timelocks.offset(Stage::SrcPublicCancellation), 4);
+        assert_eq!(timelocks.offset(Stage::DstCancellation), 7);
+        assert_eq!(
+            timelocks.get(Stage::DstWithdrawal),
+            Timestamp::from_seconds(1_005)
+        );
+        assert_eq!(
+            timelocks.rescue_start(86_400),
+            Timestamp::from_seconds(87_400)
+        );
+    }
+
+    #[test]
+    fn stage_boundaries() {
+        // Dst withdrawal at +10, public withdrawal at +20, cancellation at +30
+        let mut bytes = [0u8; 32];
+        bytes[15] = 10;
+        bytes[11] = 20;
+        bytes[7] = 30;
+        let deployed_at = Timestamp::from_seconds(1_000);
+        let timelocks = Timelocks::new(Uint256::new(bytes), deployed_at);
+        let at = |offset: u64| deployed_at.plus_seconds(offset);
+
+        assert_eq!(
+            timelocks.stage(EscrowSide::Dst, at(9)),
+            EscrowStage::FinalityLock
+        );
+        assert_eq!(
+            timelocks.stage(EscrowSide::Dst, at(10)),
+            EscrowStage::PrivateWithdrawal
+        );
+        assert_eq!(
+            timelocks.stage(EscrowSide::Dst, at(29)),
+            EscrowStage::PublicWithdrawal
+        );
+        assert_eq!(
+            timelocks.stage(EscrowSide::Dst, at(30)),
+            EscrowStage::PrivateCancellation
+        );
+        assert_eq!(
+            timelocks.next_transition(EscrowSide::Dst, at(10)),
+            Some(at(20))
+        );
+        assert_eq!(timelocks.next_transition(EscrowSide::Dst, at(30)), None);
+
+        // Withdrawal closes exactly when cancellation opens
+        let withdraw = timelocks
+            .window(EscrowSide::Dst, EscrowAction::Withdraw, 0)
+            .unwrap();
+        assert!(withdraw.contains(at(29)));
+        assert!(!withdraw.contains(at(30)));
+        let cancel = timelocks
+            .window(EscrowSide::Dst, EscrowAction::Cancel, 0)
+            .unwrap();
+        assert!(cancel.contains(at(30)));
+        assert_eq!(
+            timelocks.window(EscrowSide::Dst, EscrowAction::PublicCancel, 0),
+            None
+        );
+    }
+}